@@ -170,12 +170,150 @@ pub enum CaseSensitivity {
     Insensitive,
 }
 
+// Scores for fuzzy matching, modeled after fzf's.
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+/// Bonus for a match at the start, or after whitespace.
+const BONUS_BOUNDARY_WHITE: i32 = SCORE_MATCH / 2 + 2;
+/// Bonus for a match after a delimiter like `/` or `:`.
+const BONUS_BOUNDARY_DELIMITER: i32 = SCORE_MATCH / 2 + 1;
+/// Bonus for a match after other non-word characters like `-` or `.`, or of such a character.
+const BONUS_BOUNDARY: i32 = SCORE_MATCH / 2;
+/// Bonus for a match at a camelCase hump or the start of a number.
+const BONUS_CAMEL123: i32 = BONUS_BOUNDARY - 1;
+/// Minimum bonus for a match right after the previous one. This makes up for the gap penalty.
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+/// The bonus of the first character of the needle counts more.
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum CharClass {
+    White,
+    Delimiter,
+    NonWord,
+    Lower,
+    Upper,
+    Digit,
+    Letter,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_whitespace() {
+            CharClass::White
+        } else if "/,:;|".contains(c) {
+            CharClass::Delimiter
+        } else if c.is_lowercase() {
+            CharClass::Lower
+        } else if c.is_uppercase() {
+            CharClass::Upper
+        } else if c.is_numeric() {
+            CharClass::Digit
+        } else if c.is_alphabetic() {
+            CharClass::Letter
+        } else {
+            CharClass::NonWord
+        }
+    }
+
+    /// Return the bonus for matching a character of this class after one of class `prev`.
+    fn bonus_after(self, prev: CharClass) -> i32 {
+        use CharClass::*;
+        match (prev, self) {
+            (_, White) => BONUS_BOUNDARY_WHITE,
+            (_, Delimiter | NonWord) => BONUS_BOUNDARY,
+            (White, _) => BONUS_BOUNDARY_WHITE,
+            (Delimiter, _) => BONUS_BOUNDARY_DELIMITER,
+            (NonWord, _) => BONUS_BOUNDARY,
+            (Lower, Upper) | (Lower | Upper | Letter, Digit) => BONUS_CAMEL123,
+            _ => 0,
+        }
+    }
+}
+
+/// Return how well `needle` matches `haystack` as a subsequence, or None if it does not. Higher
+/// scores are better. Like in fzf, matches at the start of words and runs of consecutive matches
+/// score higher, and gaps between matches lower. As in [`ifind`] with `fuzzy`, `-` and `_` are
+/// treated as equal.
+pub fn fuzzy_match_score(needle: &wstr, haystack: &wstr, case_sensitive: bool) -> Option<i32> {
+    let canonicalize = |c: char| {
+        let c = if c == '_' { '-' } else { c };
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let needle: Vec<char> = needle.chars().map(canonicalize).collect();
+    if needle.is_empty() {
+        return Some(0);
+    }
+    let mut prev_class = CharClass::White;
+    let bonuses: Vec<i32> = haystack
+        .chars()
+        .map(|c| {
+            let class = CharClass::of(c);
+            let bonus = class.bonus_after(prev_class);
+            prev_class = class;
+            bonus
+        })
+        .collect();
+    let haystack: Vec<char> = haystack.chars().map(canonicalize).collect();
+
+    // For each position in the haystack, the best score of matching the needle so far with its
+    // last character there, and the bonus of the first match in the run of consecutive matches
+    // ending there.
+    let mut prev_row: Vec<Option<(i32, i32)>> = vec![None; haystack.len()];
+    for (i, &nc) in needle.iter().enumerate() {
+        let mut row = vec![None; haystack.len()];
+        // The best score of a match of the previous needle character, followed by a gap up to
+        // the current position.
+        let mut gap_score: Option<i32> = None;
+        for (j, &hc) in haystack.iter().enumerate() {
+            if j >= 2 {
+                let from_gap_start = prev_row[j - 2].map(|(score, _)| score + SCORE_GAP_START);
+                gap_score = gap_score
+                    .map(|s| s + SCORE_GAP_EXTENSION)
+                    .max(from_gap_start);
+            }
+            if hc != nc {
+                continue;
+            }
+            let bonus = bonuses[j];
+            if i == 0 {
+                row[j] = Some((SCORE_MATCH + bonus * BONUS_FIRST_CHAR_MULTIPLIER, bonus));
+                continue;
+            }
+            let after_gap = gap_score.map(|score| (score + SCORE_MATCH + bonus, bonus));
+            let consecutive =
+                j.checked_sub(1)
+                    .and_then(|k| prev_row[k])
+                    .map(|(score, run_bonus)| {
+                        let run_bonus = if bonus >= BONUS_BOUNDARY && bonus > run_bonus {
+                            bonus
+                        } else {
+                            run_bonus
+                        };
+                        let total =
+                            score + SCORE_MATCH + bonus.max(run_bonus).max(BONUS_CONSECUTIVE);
+                        (total, run_bonus)
+                    });
+            row[j] = after_gap.max(consecutive);
+        }
+        prev_row = row;
+    }
+    prev_row.into_iter().flatten().map(|(score, _)| score).max()
+}
+
 /// A lightweight value-type describing how closely a string fuzzy-matches another string.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct StringFuzzyMatch {
     pub from_separator: bool,
     pub typ: ContainType,
     pub case_fold: CaseSensitivity,
+    /// For substring and subsequence matches, how good the match is, see [`fuzzy_match_score`].
+    pub score: i32,
 }
 
 impl StringFuzzyMatch {
@@ -184,6 +322,21 @@ pub fn new(typ: ContainType, case_fold: CaseSensitivity) -> Self {
             from_separator: false,
             typ,
             case_fold,
+            score: 0,
+        }
+    }
+
+    /// Return a substring or subsequence match, with its score.
+    fn scored(
+        typ: ContainType,
+        case_fold: CaseSensitivity,
+        string: &wstr,
+        match_against: &wstr,
+    ) -> Self {
+        let case_sensitive = case_fold == CaseSensitivity::Sensitive;
+        Self {
+            score: fuzzy_match_score(string, match_against, case_sensitive).unwrap_or(0),
+            ..Self::new(typ, case_fold)
         }
     }
     // Helper to return an exact match.
@@ -278,25 +431,31 @@ fn get_case_fold(s: &wstr) -> CaseSensitivity {
             .windows(string.len())
             .any(|window| wstr::from_char_slice(window) == string)
         {
-            return Some(StringFuzzyMatch::new(
+            return Some(StringFuzzyMatch::scored(
                 ContainType::Substr,
                 CaseSensitivity::Sensitive,
+                string,
+                match_against,
             ));
         }
 
         // substr icase
         if ifind(match_against, string, true /* fuzzy */).is_some() {
-            return Some(StringFuzzyMatch::new(
+            return Some(StringFuzzyMatch::scored(
                 ContainType::Substr,
                 get_case_fold(string),
+                string,
+                match_against,
             ));
         }
 
         // subseq samecase
         if subsequence_in_string(string, match_against) {
-            return Some(StringFuzzyMatch::new(
+            return Some(StringFuzzyMatch::scored(
                 ContainType::Subseq,
                 CaseSensitivity::Sensitive,
+                string,
+                match_against,
             ));
         }
 
@@ -576,9 +735,9 @@ pub fn fish_wcwidth_visible(c: char) -> isize {
 #[cfg(test)]
 mod tests {
     use super::{
-        CaseSensitivity, ContainType, LineIterator, count_newlines, ifind, join_strings,
-        split_string_tok, string_fuzzy_match_string, string_prefixes_string_case_insensitive,
-        string_suffixes_string_case_insensitive,
+        CaseSensitivity, ContainType, LineIterator, count_newlines, fuzzy_match_score, ifind,
+        join_strings, split_string_tok, string_fuzzy_match_string,
+        string_prefixes_string_case_insensitive, string_suffixes_string_case_insensitive,
     };
     use fish_widestring::prelude::*;
 
@@ -730,6 +889,30 @@ macro_rules! validate {
         validate!("BB", "ALPHA!", None);
     }
 
+    #[test]
+    fn test_fuzzy_match_score() {
+        let score = |needle: &wstr, haystack: &wstr| fuzzy_match_score(needle, haystack, false);
+        assert_eq!(score(L!("abc"), L!("acb")), None);
+        assert_eq!(score(L!(""), L!("abc")), Some(0));
+        assert!(score(L!("abc"), L!("xabc")).is_some());
+        // Word starts beat the middle of words.
+        assert!(score(L!("fb"), L!("foo-bar")) > score(L!("fb"), L!("affable")));
+        assert!(score(L!("gb"), L!("git/branch")) > score(L!("gb"), L!("ragbag")));
+        assert!(score(L!("fb"), L!("FooBar")) > score(L!("fb"), L!("foobar")));
+        // Consecutive matches beat scattered ones.
+        assert!(score(L!("abc"), L!("xabcx")) > score(L!("abc"), L!("xaxbxcx")));
+        assert!(
+            score(L!("feat"), L!("feature/login")) > score(L!("feat"), L!("fix-elevated-api-test"))
+        );
+        // Shorter gaps are better.
+        assert!(score(L!("ac"), L!("abc")) > score(L!("ac"), L!("abbbbc")));
+        // The best alignment is found, not the first one.
+        assert!(score(L!("ab"), L!("xab-ab")) > score(L!("ab"), L!("xab")));
+        // `-` and `_` are the same, and case sensitivity is optional.
+        assert_eq!(score(L!("a_b"), L!("a-b")), score(L!("a-b"), L!("a-b")));
+        assert_eq!(fuzzy_match_score(L!("AB"), L!("ab"), true), None);
+    }
+
     #[test]
     fn test_split_string_tok() {
         macro_rules! validate {
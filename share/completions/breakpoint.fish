@@ -0,0 +1,14 @@
+set -l subcommands add list erase step next finish continue locals
+complete -f -c breakpoint -n "not __fish_seen_subcommand_from $subcommands" -s h -l help -d "Display help and exit"
+complete -f -c breakpoint -n "not __fish_seen_subcommand_from $subcommands" -a add -d "Stop at a function or file line"
+complete -f -c breakpoint -n "not __fish_seen_subcommand_from $subcommands" -a list -d "List breakpoints"
+complete -f -c breakpoint -n "not __fish_seen_subcommand_from $subcommands" -a erase -d "Remove breakpoints"
+complete -f -c breakpoint -n "not __fish_seen_subcommand_from $subcommands" -a step -d "Stop at the next command"
+complete -f -c breakpoint -n "not __fish_seen_subcommand_from $subcommands" -a next -d "Stop at the next command in this function"
+complete -f -c breakpoint -n "not __fish_seen_subcommand_from $subcommands" -a finish -d "Stop after this function returns"
+complete -f -c breakpoint -n "not __fish_seen_subcommand_from $subcommands" -a continue -d "Resume until a breakpoint"
+complete -f -c breakpoint -n "not __fish_seen_subcommand_from $subcommands" -a locals -d "Print local variables"
+complete -c breakpoint -n "__fish_seen_subcommand_from add" -s c -l condition -x -d "Only stop if this command succeeds"
+complete -c breakpoint -n "__fish_seen_subcommand_from add" -a "(functions | string replace -r '\$' :)" -d Function
+complete -f -c breakpoint -n "__fish_seen_subcommand_from erase" -l all -d "Remove all breakpoints"
+complete -f -c breakpoint -n "__fish_seen_subcommand_from erase" -a "(breakpoint list | string replace ' ' \t)"
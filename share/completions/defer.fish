@@ -0,0 +1,2 @@
+complete -c defer -n 'test (count (commandline -xpc)) -eq 1' -s h -l help -d 'Display help and exit'
+complete -c defer -xa "(__fish_complete_subcommand)"
@@ -1,3 +1,4 @@
 complete -c for -n 'test (count (commandline -xpc)) -eq 1' -s h -l help -d 'Display help and exit'
+complete -c for -n 'test (count (commandline -xpc)) -eq 1' -s i -l index -x -d 'Variable to store the iteration number in'
 complete -c for -n 'test (count (commandline -xpc)) -eq 1' -f
-complete -c for -n 'test (count (commandline -xpc)) -eq 2' -xa in
+complete -c for -n 'set -l tokens (commandline -xpc); test (count $tokens) -ge 2; and not contains -- in $tokens[2..]' -xa in
@@ -1,5 +1,5 @@
 # Note that when a completion file is sourced a new block scope is created so `set -l` works.
-set -l __fish_history_all_commands search delete save merge clear clear-session append
+set -l __fish_history_all_commands search delete save merge clear clear-session append export import vacuum
 
 complete -c history -s h -l help -d "Display help and exit"
 
@@ -10,6 +10,8 @@ complete -c history -n '__fish_seen_subcommand_from search delete; or not __fish
     -s c -l contains -d "Match items containing the string"
 complete -c history -n '__fish_seen_subcommand_from search delete; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
     -s e -l exact -d "Match items identical to the string"
+complete -c history -n '__fish_seen_subcommand_from search delete; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
+    -s r -l regex -d "Match items using a regular expression"
 complete -c history -n '__fish_seen_subcommand_from search delete; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
     -s t -l show-time -d "Output with timestamps"
 complete -c history -n '__fish_seen_subcommand_from search delete; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
@@ -24,6 +26,28 @@ complete -c history -n '__fish_seen_subcommand_from search; or not __fish_seen_s
     -s R -l reverse -d "Output the oldest results first" -x
 complete -c history -n '__fish_seen_subcommand_from search; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
     -l color -d "When to colorize output" -xa "always never auto"
+complete -c history -n '__fish_seen_subcommand_from search; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
+    -l success -d "Only match commands that succeeded"
+complete -c history -n '__fish_seen_subcommand_from search; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
+    -l failed -d "Only match commands that failed"
+complete -c history -n '__fish_seen_subcommand_from search; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
+    -l cwd -d "Only match commands run in this directory" -xa "(__fish_complete_directories)"
+complete -c history -n '__fish_seen_subcommand_from search; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
+    -l since -d "Only match commands run at or after this time" -x
+complete -c history -n '__fish_seen_subcommand_from search; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
+    -l until -d "Only match commands run at or before this time" -x
+complete -c history -n '__fish_seen_subcommand_from search; or not __fish_seen_subcommand_from $__fish_history_all_commands' \
+    -l duration-over -d "Only match commands that took longer than this" -x
+
+complete -c history -n '__fish_seen_subcommand_from export' -l format -d "Format to export history in" -xa jsonl
+complete -c history -n '__fish_seen_subcommand_from import' -l format -d "Format of the imported history" -xa "jsonl bash zsh-extended"
+# Export takes the same filters as search.
+complete -c history -n '__fish_seen_subcommand_from export' -l success -d "Only export commands that succeeded"
+complete -c history -n '__fish_seen_subcommand_from export' -l failed -d "Only export commands that failed"
+complete -c history -n '__fish_seen_subcommand_from export' -l cwd -d "Only export commands run in this directory" -xa "(__fish_complete_directories)"
+complete -c history -n '__fish_seen_subcommand_from export' -l since -d "Only export commands run at or after this time" -x
+complete -c history -n '__fish_seen_subcommand_from export' -l until -d "Only export commands run at or before this time" -x
+complete -c history -n '__fish_seen_subcommand_from export' -l duration-over -d "Only export commands that took longer than this" -x
 
 # We don't include a completion for the "save" subcommand because it should not be used
 # interactively.
@@ -39,3 +63,9 @@ complete -f -c history -n "not __fish_seen_subcommand_from $__fish_history_all_c
     -a clear-session -d "Clears all history from the current session"
 complete -f -c history -n "not __fish_seen_subcommand_from $__fish_history_all_commands" \
     -a append -d "Appends commands to the history without needing to execute them"
+complete -f -c history -n "not __fish_seen_subcommand_from $__fish_history_all_commands" \
+    -a vacuum -d "Compacts the history file and applies retention settings"
+complete -f -c history -n "not __fish_seen_subcommand_from $__fish_history_all_commands" \
+    -a export -d "Prints the history as JSON Lines"
+complete -f -c history -n "not __fish_seen_subcommand_from $__fish_history_all_commands" \
+    -a import -d "Adds commands from a history file"
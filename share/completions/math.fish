@@ -1,3 +1,5 @@
 complete -f -c math -r
 complete -f -c math -s s -l scale -r
 complete -f -c math -s b -l base -r
+complete -f -c math -s i -l int
+complete -f -c math -s q -l quiet
@@ -4,9 +4,10 @@
 #
 function history --description "display or manipulate interactive command history"
     set -l cmd history
-    set -l options --exclusive 'c,e,p' --exclusive 'S,D,M,V,X'
-    set -a options h/help c/contains e/exact p/prefix
+    set -l options --exclusive 'c,e,p,r' --exclusive 'S,D,M,V,X' --exclusive 'success,failed'
+    set -a options h/help c/contains e/exact p/prefix r/regex
     set -a options C/case-sensitive R/reverse z/null 't/show-time=?' 'n#max' 'color='
+    set -a options success failed cwd= since= until= duration-over= format=
     # The following options are deprecated and will be removed in the next major release.
     # Note that they do not have usable short flags.
     set -a options S-search D-delete M-merge V-save X-clear
@@ -23,11 +24,15 @@ function history --description "display or manipulate interactive command histor
     set -l max_count
     set -l search_mode
     set -l color_opt
+    set -l format_opt
     set -q _flag_max
     set max_count -n$_flag_max
 
     set color_opt --color=$_flag_color
 
+    set -q _flag_format
+    and set format_opt --format=$_flag_format
+
     set -q _flag_with_time
     and set -l _flag_show_time $_flag_with_time
     if set -q _flag_show_time[1]
@@ -42,6 +47,22 @@ function history --description "display or manipulate interactive command histor
     and set -l search_mode --contains
     set -q _flag_exact
     and set -l search_mode --exact
+    set -q _flag_regex
+    and set -l search_mode --regex
+
+    set -l filters
+    set -q _flag_success
+    and set -a filters --success
+    set -q _flag_failed
+    and set -a filters --failed
+    set -q _flag_cwd
+    and set -a filters --cwd=$_flag_cwd
+    set -q _flag_since
+    and set -a filters --since=$_flag_since
+    set -q _flag_until
+    and set -a filters --until=$_flag_until
+    set -q _flag_duration_over
+    and set -a filters --duration-over=$_flag_duration_over
 
     if set -q _flag_delete
         set hist_cmd delete
@@ -61,7 +82,7 @@ function history --description "display or manipulate interactive command histor
     # command. This allows the flags to appear before or after the subcommand.
     if not set -q hist_cmd[1]
         and set -q argv[1]
-        if contains $argv[1] search delete merge save clear clear-session append
+        if contains $argv[1] search delete merge save clear clear-session append export import vacuum
             set hist_cmd $argv[1]
             set -e argv[1]
         end
@@ -71,6 +92,13 @@ function history --description "display or manipulate interactive command histor
         set hist_cmd search # default to "search" if the user didn't specify a subcommand
     end
 
+    # Only searching, deleting and exporting look at filters.
+    if set -q filters[1]
+        and not contains -- $hist_cmd search delete export
+        printf (_ "%s: %s: subcommand does not support filter options\n") history $hist_cmd >&2
+        return 1
+    end
+
     switch $hist_cmd
         case search # search the interactive command history
             if isatty stdout
@@ -90,9 +118,9 @@ function history --description "display or manipulate interactive command histor
                 not set -qx LV # ask the pager lv not to strip colors
                 and set -fx LV -c
 
-                builtin history search --color=always $search_mode $show_time $max_count $_flag_case_sensitive $_flag_reverse $_flag_null -- $argv | $pager
+                builtin history search --color=always $search_mode $filters $show_time $max_count $_flag_case_sensitive $_flag_reverse $_flag_null -- $argv | $pager
             else
-                builtin history search $color_opt $search_mode $show_time $max_count $_flag_case_sensitive $_flag_reverse $_flag_null -- $argv
+                builtin history search $color_opt $search_mode $filters $show_time $max_count $_flag_case_sensitive $_flag_reverse $_flag_null -- $argv
             end
 
         case delete # interactively delete history
@@ -103,6 +131,12 @@ function history --description "display or manipulate interactive command histor
             end
 
             if test "$search_mode" = --exact
+                if set -q filters[1]
+                    # Only delete the commands that pass the filters.
+                    set searchterm (builtin history search $search_mode $_flag_case_sensitive $filters --null -- $searchterm | string split0)
+                    set -q searchterm[1]
+                    or return 0
+                end
                 builtin history delete $color_opt $search_mode $_flag_case_sensitive -- $searchterm
                 builtin history save
                 return
@@ -111,7 +145,7 @@ function history --description "display or manipulate interactive command histor
             # TODO: Fix this so that requesting history entries with a timestamp works:
             #   set -l found_items (builtin history search $color_opt $search_mode $show_time -- $argv)
             set -l found_items
-            set found_items (builtin history search $color_opt $search_mode $_flag_case_sensitive --null -- $searchterm | string split0)
+            set found_items (builtin history search $color_opt $search_mode $filters $_flag_case_sensitive --null -- $searchterm | string split0)
             if set -q found_items[1]
                 set -l found_items_count (count $found_items)
                 for i in (seq $found_items_count)
@@ -183,6 +217,8 @@ function history --description "display or manipulate interactive command histor
 
         case save # save our interactive command history to the persistent history
             builtin history save $color_opt $search_mode $show_time $max_count $_flag_case_sensitive $_flag_reverse $_flag_null -- $argv
+        case vacuum # compact the persistent interactive command history
+            builtin history vacuum $color_opt $search_mode $show_time $max_count $_flag_case_sensitive $_flag_reverse $_flag_null -- $argv
         case merge # merge the persistent interactive command history with our history
             builtin history merge $color_opt $search_mode $show_time $max_count $_flag_case_sensitive $_flag_reverse $_flag_null -- $argv
         case clear # clear the interactive command history
@@ -216,6 +252,10 @@ function history --description "display or manipulate interactive command histor
             end
 
             builtin history append $color_opt $search_mode $show_time $max_count $_flag_case_sensitive $_flag_reverse $_flag_null -- $newitem
+        case export
+            builtin history export $format_opt $search_mode $filters $show_time $_flag_case_sensitive $_flag_reverse $_flag_null -- $argv
+        case import
+            builtin history import $format_opt $search_mode $show_time $max_count $_flag_case_sensitive $_flag_reverse $_flag_null -- $argv
         case '*'
             printf "%s: unexpected subcommand '%s'\n" $cmd $hist_cmd
             return 2
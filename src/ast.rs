@@ -19,7 +19,7 @@
 use crate::parse_tree::ParseToken;
 use crate::prelude::*;
 use crate::tokenizer::{
-    TOK_ACCEPT_UNFINISHED, TOK_ARGUMENT_LIST, TOK_CONTINUE_AFTER_ERROR, TOK_SHOW_COMMENTS,
+    Heredoc, TOK_ACCEPT_UNFINISHED, TOK_ARGUMENT_LIST, TOK_CONTINUE_AFTER_ERROR, TOK_SHOW_COMMENTS,
     TokFlags, TokenType, Tokenizer, TokenizerError, variable_assignment_equals_pos,
 };
 use macro_rules_attribute::derive;
@@ -277,6 +277,9 @@ pub enum Kind<'a> {
     JobConjunction(&'a JobConjunction),
     BlockStatementHeader(&'a BlockStatementHeader),
     ForHeader(&'a ForHeader),
+    ForIndex(&'a ForIndex),
+    ForVariable(&'a ForVariable),
+    ForVariableList(&'a ForVariableList),
     WhileHeader(&'a WhileHeader),
     FunctionHeader(&'a FunctionHeader),
     BeginHeader(&'a BeginHeader),
@@ -289,6 +292,8 @@ pub enum Kind<'a> {
     IfStatement(&'a IfStatement),
     CaseItem(&'a CaseItem),
     SwitchStatement(&'a SwitchStatement),
+    CatchClause(&'a CatchClause),
+    TryStatement(&'a TryStatement),
     DecoratedStatement(&'a DecoratedStatement),
     NotStatement(&'a NotStatement),
     JobContinuation(&'a JobContinuation),
@@ -318,6 +323,9 @@ pub enum KindMut<'a> {
     JobConjunction(&'a mut JobConjunction),
     BlockStatementHeader(&'a mut BlockStatementHeader),
     ForHeader(&'a mut ForHeader),
+    ForIndex(&'a mut ForIndex),
+    ForVariable(&'a mut ForVariable),
+    ForVariableList(&'a mut ForVariableList),
     WhileHeader(&'a mut WhileHeader),
     FunctionHeader(&'a mut FunctionHeader),
     BeginHeader(&'a mut BeginHeader),
@@ -330,6 +338,8 @@ pub enum KindMut<'a> {
     IfStatement(&'a mut IfStatement),
     CaseItem(&'a mut CaseItem),
     SwitchStatement(&'a mut SwitchStatement),
+    CatchClause(&'a mut CatchClause),
+    TryStatement(&'a mut TryStatement),
     DecoratedStatement(&'a mut DecoratedStatement),
     NotStatement(&'a mut NotStatement),
     JobContinuation(&'a mut JobContinuation),
@@ -714,6 +724,7 @@ pub enum Statement {
     Brace(Box<BraceStatement>),
     If(Box<IfStatement>),
     Switch(Box<SwitchStatement>),
+    Try(Box<TryStatement>),
 }
 
 impl Default for Statement {
@@ -739,6 +750,7 @@ fn embedded_node(&self) -> &dyn Node {
             Self::Brace(child) => &**child,
             Self::If(child) => &**child,
             Self::Switch(child) => &**child,
+            Self::Try(child) => &**child,
             Self::Decorated(child) => child,
         }
     }
@@ -797,7 +809,10 @@ fn can_be_parsed(pop: &mut Populator<'_>) -> bool {
             || (token.typ == ParseTokenType::String
                 && !matches!(
                     token.keyword,
-                    ParseKeyword::Case | ParseKeyword::End | ParseKeyword::Else
+                    ParseKeyword::Case
+                        | ParseKeyword::Catch
+                        | ParseKeyword::End
+                        | ParseKeyword::Else
                 ))
     }
 }
@@ -806,8 +821,12 @@ fn can_be_parsed(pop: &mut Populator<'_>) -> bool {
 pub struct ForHeader {
     /// 'for'
     pub kw_for: KeywordFor,
+    /// -i index_var_name
+    pub index: Option<ForIndex>,
     /// var_name
     pub var_name: String_,
+    /// further variable names, to iterate in tuples
+    pub more_var_names: ForVariableList,
     /// 'in'
     pub kw_in: KeywordIn,
     /// list of arguments
@@ -816,6 +835,34 @@ pub struct ForHeader {
     pub semi_nl: SemiNl,
 }
 
+/// The option to a for loop which names the variable holding the iteration number.
+#[derive(Default, Debug, Node!, Acceptor!)]
+pub struct ForIndex {
+    /// '-i' or '--index'
+    pub option: String_,
+    /// index_var_name
+    pub var_name: String_,
+}
+impl CheckParse for ForIndex {
+    fn can_be_parsed(pop: &mut Populator<'_>) -> bool {
+        pop.peek_token(0).is_dash_prefix_string()
+    }
+}
+
+/// A variable name in a for loop header after the first one.
+#[derive(Default, Debug, Node!, Acceptor!)]
+pub struct ForVariable {
+    pub var_name: String_,
+}
+impl CheckParse for ForVariable {
+    fn can_be_parsed(pop: &mut Populator<'_>) -> bool {
+        let token = pop.peek_token(0);
+        token.typ == ParseTokenType::String && token.keyword != ParseKeyword::In
+    }
+}
+
+define_list_node!(ForVariableList, ForVariable);
+
 #[derive(Default, Debug, Node!, Acceptor!)]
 pub struct WhileHeader {
     /// 'while'
@@ -945,6 +992,32 @@ pub struct SwitchStatement {
     pub args_or_redirs: ArgumentOrRedirectionList,
 }
 
+#[derive(Default, Debug, Node!, Acceptor!)]
+pub struct CatchClause {
+    /// catch ; body
+    pub kw_catch: KeywordCatch,
+    pub semi_nl: Option<SemiNl>,
+    pub body: JobList,
+}
+impl CheckParse for CatchClause {
+    fn can_be_parsed(pop: &mut Populator<'_>) -> bool {
+        pop.peek_token(0).keyword == ParseKeyword::Catch
+    }
+}
+
+#[derive(Default, Debug, Node!, Acceptor!)]
+pub struct TryStatement {
+    /// try ; body ; catch ; body ; end args_redirs
+    pub kw_try: KeywordTry,
+    pub semi_nl: Option<SemiNl>,
+    /// The jobs whose failure is caught.
+    pub body: JobList,
+    /// The handler to run on failure.
+    pub catch_clause: Option<CatchClause>,
+    pub end: KeywordEnd,
+    pub args_or_redirs: ArgumentOrRedirectionList,
+}
+
 /// A decorated_statement is a command with a list of arguments_or_redirections, possibly with
 /// "builtin" or "command" or "exec"
 #[derive(Default, Debug, Node!, Acceptor!)]
@@ -1095,6 +1168,7 @@ fn can_be_parsed(pop: &mut Populator<'_>) -> bool {
 define_keyword_node!(JobConjunctionDecorator, And, Or);
 define_keyword_node!(KeywordBegin, Begin);
 define_keyword_node!(KeywordCase, Case);
+define_keyword_node!(KeywordCatch, Catch);
 define_keyword_node!(KeywordElse, Else);
 define_keyword_node!(KeywordEnd, End);
 define_keyword_node!(KeywordFor, For);
@@ -1104,6 +1178,7 @@ fn can_be_parsed(pop: &mut Populator<'_>) -> bool {
 define_keyword_node!(KeywordNot, Not, Exclam);
 define_keyword_node!(KeywordSwitch, Switch);
 define_keyword_node!(KeywordTime, Time);
+define_keyword_node!(KeywordTry, Try);
 define_keyword_node!(KeywordWhile, While);
 
 impl CheckParse for JobConjunctionDecorator {
@@ -1216,6 +1291,9 @@ pub fn ast_kind_to_string(k: Kind<'_>) -> &'static wstr {
         Kind::JobConjunction(_) => L!("job_conjunction"),
         Kind::BlockStatementHeader(_) => L!("block_statement_header"),
         Kind::ForHeader(_) => L!("for_header"),
+        Kind::ForIndex(_) => L!("for_index"),
+        Kind::ForVariable(_) => L!("for_variable"),
+        Kind::ForVariableList(_) => L!("for_variable_list"),
         Kind::WhileHeader(_) => L!("while_header"),
         Kind::FunctionHeader(_) => L!("function_header"),
         Kind::BeginHeader(_) => L!("begin_header"),
@@ -1228,6 +1306,8 @@ pub fn ast_kind_to_string(k: Kind<'_>) -> &'static wstr {
         Kind::IfStatement(_) => L!("if_statement"),
         Kind::CaseItem(_) => L!("case_item"),
         Kind::SwitchStatement(_) => L!("switch_statement"),
+        Kind::CatchClause(_) => L!("catch_clause"),
+        Kind::TryStatement(_) => L!("try_statement"),
         Kind::DecoratedStatement(_) => L!("decorated_statement"),
         Kind::NotStatement(_) => L!("not_statement"),
         Kind::JobContinuation(_) => L!("job_continuation"),
@@ -1361,6 +1441,9 @@ pub struct Extras {
 
     /// Set of error ranges, sorted by offset.
     pub errors: SourceRangeList,
+
+    /// Heredocs, sorted by offset. These are always generated.
+    pub heredocs: Vec<Heredoc>,
 }
 
 /// Parse a job list.
@@ -1399,6 +1482,7 @@ fn finalize_parse<N: Node>(mut pops: Populator<'_>, top: N) -> Ast<N> {
         comments: pops.tokens.comment_ranges,
         semis: pops.semis,
         errors: pops.errors,
+        heredocs: pops.tokens.tok.take_heredocs(),
     };
 
     Ast {
@@ -1767,6 +1851,8 @@ fn visit_mut<N: NodeMut>(&mut self, node: &mut N) -> VisitResult {
             KM::JobPipeline(node) => node.accept_mut(self),
             KM::JobConjunction(node) => node.accept_mut(self),
             KM::ForHeader(node) => node.accept_mut(self),
+            KM::ForIndex(node) => node.accept_mut(self),
+            KM::ForVariable(node) => node.accept_mut(self),
             KM::WhileHeader(node) => node.accept_mut(self),
             KM::FunctionHeader(node) => node.accept_mut(self),
             KM::BeginHeader(node) => node.accept_mut(self),
@@ -1778,6 +1864,8 @@ fn visit_mut<N: NodeMut>(&mut self, node: &mut N) -> VisitResult {
             KM::IfStatement(node) => node.accept_mut(self),
             KM::CaseItem(node) => node.accept_mut(self),
             KM::SwitchStatement(node) => node.accept_mut(self),
+            KM::CatchClause(node) => node.accept_mut(self),
+            KM::TryStatement(node) => node.accept_mut(self),
             KM::DecoratedStatement(node) => node.accept_mut(self),
             KM::NotStatement(node) => node.accept_mut(self),
             KM::JobConjunctionContinuation(node) => node.accept_mut(self),
@@ -1791,6 +1879,7 @@ fn visit_mut<N: NodeMut>(&mut self, node: &mut N) -> VisitResult {
             KM::AndorJobList(node) => self.populate_list(node, false),
             KM::JobConjunctionContinuationList(node) => self.populate_list(node, false),
             KM::CaseItemList(node) => self.populate_list(node, false),
+            KM::ForVariableList(node) => self.populate_list(node, false),
             KM::ArgumentList(node) => self.populate_list(node, false),
             KM::JobList(node) => self.populate_list(node, false),
 
@@ -1862,6 +1951,9 @@ fn did_visit_fields_of<'a, N: NodeMut>(&'a mut self, node: &'a mut N, flow: Visi
                 Kind::SwitchStatement(node) => {
                     break Some((node.kw_switch.range.unwrap(), L!("switch statement")));
                 }
+                Kind::TryStatement(node) => {
+                    break Some((node.kw_try.range.unwrap(), L!("try block")));
+                }
                 _ => break None,
             }
         };
@@ -1871,7 +1963,10 @@ fn did_visit_fields_of<'a, N: NodeMut>(&'a mut self, node: &'a mut N, flow: Visi
             if next_token.typ == ParseTokenType::String
                 && matches!(
                     next_token.keyword,
-                    ParseKeyword::Case | ParseKeyword::Else | ParseKeyword::End
+                    ParseKeyword::Case
+                        | ParseKeyword::Catch
+                        | ParseKeyword::Else
+                        | ParseKeyword::End
                 )
             {
                 self.consume_excess_token_generating_error();
@@ -1999,6 +2094,10 @@ fn list_kind_chomps_newlines(&self, kind: Kind) -> bool {
                 // No newlines inside variable assignment lists.
                 false
             }
+            Kind::ForVariableList(_) => {
+                // No newlines between the variable names of a for loop.
+                false
+            }
             Kind::JobList(_) => {
                 // Like echo a \n \n echo b
                 true
@@ -2047,7 +2146,9 @@ fn list_kind_chomps_semis(&self, kind: Kind) -> bool {
                 self.freestanding_arguments
             }
 
-            Kind::ArgumentOrRedirectionList(_) | Kind::VariableAssignmentList(_) => false,
+            Kind::ArgumentOrRedirectionList(_)
+            | Kind::VariableAssignmentList(_)
+            | Kind::ForVariableList(_) => false,
             Kind::JobList(_) => {
                 // Like echo a ; ;  echo b
                 true
@@ -2184,7 +2285,7 @@ fn consume_excess_token_generating_error(&mut self) {
 
         match tok.typ {
             ParseTokenType::String => {
-                // There are three keywords which end a job list.
+                // There are four keywords which end a job list.
                 match tok.keyword {
                     ParseKeyword::Case => {
                         parse_error!(
@@ -2210,6 +2311,14 @@ fn consume_excess_token_generating_error(&mut self) {
                             "'else' builtin not inside of if block"
                         );
                     }
+                    ParseKeyword::Catch => {
+                        parse_error!(
+                            self,
+                            tok,
+                            ParseErrorCode::UnbalancingCatch,
+                            "'catch' builtin not inside of try block"
+                        );
+                    }
                     _ => {
                         internal_error!(
                             self,
@@ -2460,9 +2569,11 @@ fn new_decorated_statement(slf: &mut Populator<'_>) -> Statement {
             // looks like an option (starts with a dash), then parse it as a decorated statement.
             let help_only_kws = [
                 ParseKeyword::Begin,
+                ParseKeyword::For,
                 ParseKeyword::Function,
                 ParseKeyword::If,
                 ParseKeyword::Switch,
+                ParseKeyword::Try,
                 ParseKeyword::While,
             ];
             if if help_only_kws.contains(&self.peek_token(0).keyword) {
@@ -2476,7 +2587,8 @@ fn new_decorated_statement(slf: &mut Populator<'_>) -> Statement {
             // Likewise if the next token doesn't look like an argument at all. This corresponds to
             // e.g. a "naked if".
             let naked_invocation_invokes_help =
-                ![ParseKeyword::Begin, ParseKeyword::End].contains(&self.peek_token(0).keyword);
+                ![ParseKeyword::Begin, ParseKeyword::End, ParseKeyword::Try]
+                    .contains(&self.peek_token(0).keyword);
             if naked_invocation_invokes_help && self.peek_token(1).typ == ParseTokenType::Terminate
             {
                 return new_decorated_statement(self);
@@ -2503,6 +2615,10 @@ fn new_decorated_statement(slf: &mut Populator<'_>) -> Statement {
                 let embedded = self.allocate_boxed_visit::<SwitchStatement>();
                 Statement::Switch(embedded)
             }
+            ParseKeyword::Try => {
+                let embedded = self.allocate_boxed_visit::<TryStatement>();
+                Statement::Try(embedded)
+            }
             ParseKeyword::End => {
                 // 'end' is forbidden as a command.
                 // For example, `if end` or `while end` will produce this error.
@@ -2643,7 +2759,9 @@ fn visit_token(&mut self, token: &mut dyn Token) {
             if self.flags.leave_unterminated
                 && matches!(
                     self.peek_token(0).tok_error,
-                    TokenizerError::UnterminatedQuote | TokenizerError::UnterminatedSubshell
+                    TokenizerError::UnterminatedQuote
+                        | TokenizerError::UnterminatedSubshell
+                        | TokenizerError::UnterminatedHeredoc
                 )
             {
                 return;
@@ -2678,7 +2796,9 @@ fn visit_keyword(&mut self, keyword: &mut dyn Keyword) -> VisitResult {
             if self.flags.leave_unterminated
                 && matches!(
                     self.peek_token(0).tok_error,
-                    TokenizerError::UnterminatedQuote | TokenizerError::UnterminatedSubshell
+                    TokenizerError::UnterminatedQuote
+                        | TokenizerError::UnterminatedSubshell
+                        | TokenizerError::UnterminatedHeredoc
                 )
             {
                 return VisitResult::Continue(());
@@ -47,7 +47,7 @@
     parse_constants::{ParseErrorList, ParseTreeFlags},
     parse_tree::ParsedSource,
     parse_util::detect_parse_errors_in_ast,
-    parser::{BlockType, CancelBehavior, Parser, ParserEnvSetMode},
+    parser::{BlockType, CancelBehavior, Parser, ParserEnvSetMode, ProfileFormat},
     path::path_get_config,
     prelude::*,
     printf,
@@ -81,6 +81,8 @@ struct FishCmdOpts {
     /// File path for profiling output, or empty for none.
     profile_output: Option<OsString>,
     profile_startup_output: Option<OsString>,
+    /// How to write profiling output.
+    profile_format: ProfileFormat,
     /// Commands to be executed in place of interactive shell.
     batch_cmds: Vec<OsString>,
     /// Commands to execute after the shell's config has been read.
@@ -218,6 +220,7 @@ fn fish_parse_opt(args: &mut [WString], opts: &mut FishCmdOpts) -> ControlFlow<i
     const RUSAGE_ARG: char = 1 as char;
     const PRINT_DEBUG_CATEGORIES_ARG: char = 2 as char;
     const PROFILE_STARTUP_ARG: char = 3 as char;
+    const PROFILE_FORMAT_ARG: char = 4 as char;
 
     const SHORT_OPTS: &wstr = L!("+hPilNnvc:C:p:d:f:D:o:");
     const LONG_OPTS: &[WOption<'static>] = &[
@@ -239,6 +242,7 @@ fn fish_parse_opt(args: &mut [WString], opts: &mut FishCmdOpts) -> ControlFlow<i
         ),
         wopt(L!("profile"), RequiredArgument, 'p'),
         wopt(L!("profile-startup"), RequiredArgument, PROFILE_STARTUP_ARG),
+        wopt(L!("profile-format"), RequiredArgument, PROFILE_FORMAT_ARG),
         wopt(L!("private"), NoArgument, 'P'),
         wopt(L!("help"), NoArgument, 'h'),
         wopt(L!("version"), NoArgument, 'v'),
@@ -299,6 +303,17 @@ fn fish_parse_opt(args: &mut [WString], opts: &mut FishCmdOpts) -> ControlFlow<i
                     Some(OsString::from_vec(wcs2bytes(w.woptarg.unwrap())));
                 PROFILING_ACTIVE.store(true);
             }
+            PROFILE_FORMAT_ARG => {
+                let optarg = w.woptarg.unwrap();
+                let Some(format) = ProfileFormat::from_name(optarg) else {
+                    eprintf!(
+                        "%s\n",
+                        wgettext_fmt!("%s: %s: invalid profile format", "fish", optarg)
+                    );
+                    return ControlFlow::Break(1);
+                };
+                opts.profile_format = format;
+            }
             'P' => opts.enable_private_mode = true,
             'v' => {
                 printf!(
@@ -513,7 +528,7 @@ fn throwing_main() -> i32 {
     // TODO(MSRV>=1.88): feature(let_chains)
     if let Some(path) = &opts.profile_startup_output {
         if opts.profile_startup_output != opts.profile_output {
-            parser.emit_profiling(path);
+            parser.emit_profiling(path, opts.profile_format);
 
             // If we are profiling both, ensure the startup data only
             // ends up in the startup file.
@@ -614,7 +629,7 @@ fn throwing_main() -> i32 {
     );
 
     if let Some(profile_output) = opts.profile_output {
-        parser.emit_profiling(&profile_output);
+        parser.emit_profiling(&profile_output, opts.profile_format);
     }
 
     history::save_all();
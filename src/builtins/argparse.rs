@@ -79,8 +79,8 @@ fn new() -> Self {
     }
 }
 
-const SHORT_OPTIONS: &wstr = L!("+hn:siuU:x:SN:X:");
-const LONG_OPTIONS: &[WOption] = &[
+pub(crate) const SHORT_OPTIONS: &wstr = L!("+hn:siuU:x:SN:X:");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
     wopt(L!("stop-nonopt"), ArgType::NoArgument, 's'),
     wopt(L!("ignore-unknown"), ArgType::NoArgument, 'i'),
     wopt(L!("move-unknown"), ArgType::NoArgument, 'u'),
@@ -406,6 +406,23 @@ fn list_modes(&mut self, streams: &mut IoStreams) {
     }
 }
 
+pub(crate) const SHORT_OPTIONS: &wstr = L!("aehkKfM:Lm:s");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
+    wopt(L!("all"), NoArgument, 'a'),
+    wopt(L!("erase"), NoArgument, 'e'),
+    wopt(L!("function-names"), NoArgument, 'f'),
+    wopt(L!("help"), NoArgument, 'h'),
+    wopt(L!("key"), NoArgument, 'k'),
+    wopt(L!("key-names"), NoArgument, 'K'),
+    wopt(L!("list-modes"), NoArgument, 'L'),
+    wopt(L!("mode"), RequiredArgument, 'M'),
+    wopt(L!("preset"), NoArgument, 'p'),
+    wopt(L!("sets-mode"), RequiredArgument, 'm'),
+    wopt(L!("silent"), NoArgument, 's'),
+    wopt(L!("user"), NoArgument, 'u'),
+    wopt(L!("color"), RequiredArgument, COLOR_OPTION_CHAR),
+];
+
 fn parse_cmd_opts(
     opts: &mut Options,
     optind: &mut usize,
@@ -414,23 +431,6 @@ fn parse_cmd_opts(
     streams: &mut IoStreams,
 ) -> BuiltinResult {
     let cmd = argv[0];
-    let short_options = L!("aehkKfM:Lm:s");
-    let long_options: &[WOption] = &[
-        wopt(L!("all"), NoArgument, 'a'),
-        wopt(L!("erase"), NoArgument, 'e'),
-        wopt(L!("function-names"), NoArgument, 'f'),
-        wopt(L!("help"), NoArgument, 'h'),
-        wopt(L!("key"), NoArgument, 'k'),
-        wopt(L!("key-names"), NoArgument, 'K'),
-        wopt(L!("list-modes"), NoArgument, 'L'),
-        wopt(L!("mode"), RequiredArgument, 'M'),
-        wopt(L!("preset"), NoArgument, 'p'),
-        wopt(L!("sets-mode"), RequiredArgument, 'm'),
-        wopt(L!("silent"), NoArgument, 's'),
-        wopt(L!("user"), NoArgument, 'u'),
-        wopt(L!("color"), RequiredArgument, COLOR_OPTION_CHAR),
-    ];
-
     let check_mode_name = |streams: &mut IoStreams, mode_name: &wstr| -> Result<(), ErrorCode> {
         if !valid_var_name(mode_name) {
             streams.err.appendln(&wgettext_fmt!(
@@ -444,7 +444,7 @@ fn parse_cmd_opts(
         Ok(())
     };
 
-    let mut w = WGetopter::new(short_options, long_options, argv);
+    let mut w = WGetopter::new(SHORT_OPTIONS, LONG_OPTIONS, argv);
     while let Some(c) = w.next_opt() {
         match c {
             'a' => opts.all = true,
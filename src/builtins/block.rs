@@ -18,6 +18,14 @@ struct Options {
     print_help: bool,
 }
 
+pub(crate) const SHORT_OPTS: &wstr = L!("eghl");
+pub(crate) const LONG_OPTS: &[WOption] = &[
+    wopt(L!("erase"), ArgType::NoArgument, 'e'),
+    wopt(L!("local"), ArgType::NoArgument, 'l'),
+    wopt(L!("global"), ArgType::NoArgument, 'g'),
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+];
+
 fn parse_options(
     args: &mut [&wstr],
     parser: &Parser,
@@ -25,14 +33,6 @@ fn parse_options(
 ) -> Result<(Options, usize), ErrorCode> {
     let cmd = args[0];
 
-    const SHORT_OPTS: &wstr = L!("eghl");
-    const LONG_OPTS: &[WOption] = &[
-        wopt(L!("erase"), ArgType::NoArgument, 'e'),
-        wopt(L!("local"), ArgType::NoArgument, 'l'),
-        wopt(L!("global"), ArgType::NoArgument, 'g'),
-        wopt(L!("help"), ArgType::NoArgument, 'h'),
-    ];
-
     let mut opts = Options::default();
 
     let mut w = WGetopter::new(SHORT_OPTS, LONG_OPTS, args);
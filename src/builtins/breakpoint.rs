@@ -1,21 +1,13 @@
 use super::prelude::*;
+use crate::common::{EscapeFlags, EscapeStringStyle, escape_string};
+use crate::debugger::{BreakpointLocation, StepMode, stopped_function_depth};
+use crate::expand::expand_escape_variable;
 use crate::parser::{Block, BlockType};
 use crate::reader::reader_read;
 use libc::STDIN_FILENO;
 
-/// Implementation of the builtin breakpoint command, used to launch the interactive debugger.
-pub fn breakpoint(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) -> BuiltinResult {
-    let cmd = argv[0];
-    if argv.len() != 1 {
-        streams.err.appendln(&wgettext_fmt!(
-            BUILTIN_ERR_ARG_COUNT1,
-            cmd,
-            0,
-            argv.len() - 1
-        ));
-        return Err(STATUS_INVALID_ARGS);
-    }
-
+/// Stop the script here and launch the interactive debugger.
+fn stop_here(parser: &Parser, streams: &mut IoStreams, cmd: &wstr) -> BuiltinResult {
     // If we're not interactive then we can't enter the debugger. So treat this command as a no-op.
     if !parser.is_interactive() {
         return Err(STATUS_CMD_ERROR);
@@ -42,3 +34,232 @@ pub fn breakpoint(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr])
     parser.pop_block(bpb);
     BuiltinResult::from_dynamic(parser.get_last_status())
 }
+
+/// Add a breakpoint: `breakpoint add [--condition CMD] LOCATION`.
+fn add(parser: &Parser, streams: &mut IoStreams, cmd: &wstr, args: &mut [&wstr]) -> BuiltinResult {
+    let print_hints = false;
+    let mut condition = None;
+
+    let shortopts: &wstr = L!("c:");
+    let longopts: &[WOption] = &[wopt(L!("condition"), ArgType::RequiredArgument, 'c')];
+
+    let mut w = WGetopter::new(shortopts, longopts, args);
+    while let Some(c) = w.next_opt() {
+        match c {
+            'c' => {
+                condition = Some(w.woptarg.unwrap().to_owned());
+            }
+            ':' => {
+                builtin_missing_argument(parser, streams, cmd, args[w.wopt_index - 1], print_hints);
+                return Err(STATUS_INVALID_ARGS);
+            }
+            '?' => {
+                builtin_unknown_option(parser, streams, cmd, args[w.wopt_index - 1], print_hints);
+                return Err(STATUS_INVALID_ARGS);
+            }
+            _ => {
+                panic!("unexpected retval from WGetopter");
+            }
+        }
+    }
+
+    let locations = &args[w.wopt_index..];
+    if locations.len() != 1 {
+        streams.err.appendln(&wgettext_fmt!(
+            BUILTIN_ERR_ARG_COUNT2,
+            cmd,
+            args[0],
+            1,
+            locations.len()
+        ));
+        return Err(STATUS_INVALID_ARGS);
+    }
+    let Some(location) = BreakpointLocation::parse(locations[0]) else {
+        streams.err.appendln(&wgettext_fmt!(
+            "%s: %s: invalid location, expected FUNCTION:LINE or FILE:LINE",
+            cmd,
+            locations[0]
+        ));
+        return Err(STATUS_INVALID_ARGS);
+    };
+    parser.libdata_mut().debugger.add(location, condition);
+    Ok(SUCCESS)
+}
+
+/// Print the breakpoints in a form that can be used to set them again.
+fn list(streams: &mut IoStreams, parser: &Parser) -> BuiltinResult {
+    let mut out = WString::new();
+    for bp in parser.libdata().debugger.breakpoints() {
+        out.push_utfstr(&sprintf!("%u %s", bp.id, bp.location.describe()));
+        if let Some(condition) = &bp.condition {
+            out.push_str(" --condition ");
+            out.push_utfstr(&escape_string(
+                condition,
+                EscapeStringStyle::Script(EscapeFlags::default()),
+            ));
+        }
+        out.push('\n');
+    }
+    streams.out.append(&out);
+    Ok(SUCCESS)
+}
+
+/// Remove breakpoints by id, or all of them with `--all`.
+fn erase(parser: &Parser, streams: &mut IoStreams, cmd: &wstr, args: &[&wstr]) -> BuiltinResult {
+    if args.is_empty() {
+        streams
+            .err
+            .appendln(&wgettext_fmt!(BUILTIN_ERR_ARG_COUNT0, cmd));
+        return Err(STATUS_INVALID_ARGS);
+    }
+    if args == [L!("--all")] {
+        parser.libdata_mut().debugger.clear();
+        return Ok(SUCCESS);
+    }
+
+    let mut result = Ok(SUCCESS);
+    for arg in args {
+        let id = match fish_wcstoi(arg).ok().and_then(|id| u32::try_from(id).ok()) {
+            Some(id) => id,
+            None => {
+                streams
+                    .err
+                    .appendln(&wgettext_fmt!(BUILTIN_ERR_NOT_NUMBER, cmd, arg));
+                return Err(STATUS_INVALID_ARGS);
+            }
+        };
+        if !parser.libdata_mut().debugger.remove(id) {
+            streams
+                .err
+                .appendln(&wgettext_fmt!("%s: %s: no such breakpoint", cmd, arg));
+            result = Err(STATUS_CMD_ERROR);
+        }
+    }
+    result
+}
+
+/// Print the local variables of a function scope, 0 being that of the stopped function.
+fn locals(parser: &Parser, streams: &mut IoStreams, cmd: &wstr, args: &[&wstr]) -> BuiltinResult {
+    if args.len() > 1 {
+        streams.err.appendln(&wgettext_fmt!(
+            BUILTIN_ERR_MAX_ARG_COUNT1,
+            cmd,
+            1,
+            args.len()
+        ));
+        return Err(STATUS_INVALID_ARGS);
+    }
+    let frame = match args.first() {
+        None => 0,
+        Some(arg) => match fish_wcstoi(arg).ok().and_then(|n| usize::try_from(n).ok()) {
+            Some(frame) => frame,
+            None => {
+                streams
+                    .err
+                    .appendln(&wgettext_fmt!(BUILTIN_ERR_NOT_NUMBER, cmd, arg));
+                return Err(STATUS_INVALID_ARGS);
+            }
+        },
+    };
+    let Some(vars) = parser.vars().get_frame_locals(frame) else {
+        streams
+            .err
+            .appendln(&wgettext_fmt!("%s: %u: no such frame", cmd, frame));
+        return Err(STATUS_CMD_ERROR);
+    };
+
+    let mut out = WString::new();
+    // Like `set` without arguments.
+    for (name, var) in vars {
+        out.push_utfstr(&name);
+        let val = expand_escape_variable(&var);
+        if !val.is_empty() {
+            out.push(' ');
+            out.push_utfstr(&val);
+        }
+        out.push('\n');
+    }
+    streams.out.append(&out);
+    Ok(SUCCESS)
+}
+
+/// Resume the stopped script, and tell the debugger where to stop next.
+fn resume(
+    parser: &Parser,
+    streams: &mut IoStreams,
+    cmd: &wstr,
+    subcmd: &wstr,
+    args: &[&wstr],
+) -> BuiltinResult {
+    if !args.is_empty() {
+        streams.err.appendln(&wgettext_fmt!(
+            BUILTIN_ERR_ARG_COUNT2,
+            cmd,
+            subcmd,
+            0,
+            args.len()
+        ));
+        return Err(STATUS_INVALID_ARGS);
+    }
+    if !parser.is_breakpoint() {
+        streams.err.appendln(&wgettext_fmt!(
+            "%s: %s: only valid at the debug prompt",
+            cmd,
+            subcmd
+        ));
+        return Err(STATUS_ILLEGAL_CMD);
+    }
+
+    let depth = stopped_function_depth(parser);
+    let step_mode = match subcmd.to_string().as_str() {
+        "step" => StepMode::Step,
+        "next" => StepMode::Next(depth),
+        "finish" => StepMode::Finish(depth),
+        _ => StepMode::Continue,
+    };
+    let mut ld = parser.libdata_mut();
+    ld.debugger.set_step_mode(step_mode);
+    // Leave the debug prompt.
+    ld.exit_current_script = true;
+    Ok(SUCCESS)
+}
+
+/// Implementation of the builtin breakpoint command, used to launch the interactive debugger and
+/// control where it stops.
+pub fn breakpoint(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) -> BuiltinResult {
+    let cmd = argv[0];
+    let Some(&subcmd) = argv.get(1) else {
+        return stop_here(parser, streams, cmd);
+    };
+
+    match subcmd.to_string().as_str() {
+        "-h" | "--help" => {
+            builtin_print_help(parser, streams, cmd);
+            Ok(SUCCESS)
+        }
+        "add" => add(parser, streams, cmd, &mut argv[1..]),
+        "list" => {
+            if argv.len() != 2 {
+                streams.err.appendln(&wgettext_fmt!(
+                    BUILTIN_ERR_ARG_COUNT2,
+                    cmd,
+                    subcmd,
+                    0,
+                    argv.len() - 2
+                ));
+                return Err(STATUS_INVALID_ARGS);
+            }
+            list(streams, parser)
+        }
+        "erase" => erase(parser, streams, cmd, &argv[2..]),
+        "locals" => locals(parser, streams, cmd, &argv[2..]),
+        "step" | "next" | "finish" | "continue" => resume(parser, streams, cmd, subcmd, &argv[2..]),
+        _ => {
+            streams
+                .err
+                .appendln(&wgettext_fmt!(BUILTIN_ERR_INVALID_SUBCMD, cmd, subcmd));
+            builtin_print_error_trailer(parser, streams.err, cmd);
+            Err(STATUS_INVALID_ARGS)
+        }
+    }
+}
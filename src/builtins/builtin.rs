@@ -6,20 +6,20 @@ struct builtin_cmd_opts_t {
     list_names: bool,
 }
 
+pub(crate) const SHORT_OPTIONS: &wstr = L!("hnq");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+    wopt(L!("names"), ArgType::NoArgument, 'n'),
+    wopt(L!("query"), ArgType::NoArgument, 'q'),
+];
+
 pub fn r#builtin(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) -> BuiltinResult {
     let cmd = argv[0];
     let argc = argv.len();
     let print_hints = false;
     let mut opts: builtin_cmd_opts_t = Default::default();
 
-    let shortopts: &wstr = L!("hnq");
-    let longopts: &[WOption] = &[
-        wopt(L!("help"), ArgType::NoArgument, 'h'),
-        wopt(L!("names"), ArgType::NoArgument, 'n'),
-        wopt(L!("query"), ArgType::NoArgument, 'q'),
-    ];
-
-    let mut w = WGetopter::new(shortopts, longopts, argv);
+    let mut w = WGetopter::new(SHORT_OPTIONS, LONG_OPTIONS, argv);
     while let Some(c) = w.next_opt() {
         match c {
             'q' => opts.query = true,
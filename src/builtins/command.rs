@@ -8,22 +8,22 @@ struct command_cmd_opts_t {
     find_path: bool,
 }
 
+pub(crate) const SHORT_OPTIONS: &wstr = L!("hasqv");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+    wopt(L!("all"), ArgType::NoArgument, 'a'),
+    wopt(L!("query"), ArgType::NoArgument, 'q'),
+    wopt(L!("quiet"), ArgType::NoArgument, 'q'),
+    wopt(L!("search"), ArgType::NoArgument, 's'),
+];
+
 pub fn r#command(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) -> BuiltinResult {
     let cmd = argv[0];
     let argc = argv.len();
     let print_hints = false;
     let mut opts: command_cmd_opts_t = Default::default();
 
-    let shortopts: &wstr = L!("hasqv");
-    let longopts: &[WOption] = &[
-        wopt(L!("help"), ArgType::NoArgument, 'h'),
-        wopt(L!("all"), ArgType::NoArgument, 'a'),
-        wopt(L!("query"), ArgType::NoArgument, 'q'),
-        wopt(L!("quiet"), ArgType::NoArgument, 'q'),
-        wopt(L!("search"), ArgType::NoArgument, 's'),
-    ];
-
-    let mut w = WGetopter::new(shortopts, longopts, argv);
+    let mut w = WGetopter::new(SHORT_OPTIONS, LONG_OPTIONS, argv);
     while let Some(c) = w.next_opt() {
         match c {
             'a' => opts.all = true,
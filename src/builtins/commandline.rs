@@ -237,6 +237,41 @@ fn write_part(
     }
 }
 
+pub(crate) const SHORT_OPTIONS: &wstr = L!("abijpctfxorhI:CBELSsP");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
+    wopt(L!("append"), ArgType::NoArgument, 'a'),
+    wopt(L!("insert"), ArgType::NoArgument, 'i'),
+    wopt(L!("insert-smart"), ArgType::NoArgument, '\x06'),
+    wopt(L!("replace"), ArgType::NoArgument, 'r'),
+    wopt(L!("current-buffer"), ArgType::NoArgument, 'b'),
+    wopt(L!("current-job"), ArgType::NoArgument, 'j'),
+    wopt(L!("current-process"), ArgType::NoArgument, 'p'),
+    wopt(L!("current-selection"), ArgType::NoArgument, 's'),
+    wopt(L!("current-token"), ArgType::NoArgument, 't'),
+    wopt(L!("cut-at-cursor"), ArgType::NoArgument, 'c'),
+    wopt(L!("function"), ArgType::NoArgument, 'f'),
+    wopt(L!("tokens-expanded"), ArgType::NoArgument, 'x'),
+    wopt(L!("tokens-raw"), ArgType::NoArgument, '\x02'),
+    wopt(L!("tokenize"), ArgType::NoArgument, 'o'),
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+    wopt(L!("input"), ArgType::RequiredArgument, 'I'),
+    wopt(L!("cursor"), ArgType::NoArgument, 'C'),
+    wopt(L!("selection-start"), ArgType::NoArgument, 'B'),
+    wopt(L!("selection-end"), ArgType::NoArgument, 'E'),
+    wopt(L!("line"), ArgType::NoArgument, 'L'),
+    wopt(L!("column"), ArgType::NoArgument, '\x05'),
+    wopt(L!("search-mode"), ArgType::NoArgument, 'S'),
+    wopt(L!("paging-mode"), ArgType::NoArgument, 'P'),
+    wopt(L!("paging-full-mode"), ArgType::NoArgument, 'F'),
+    wopt(L!("search-field"), ArgType::NoArgument, '\x03'),
+    wopt(L!("is-valid"), ArgType::NoArgument, '\x01'),
+    wopt(L!("showing-suggestion"), ArgType::NoArgument, '\x04'),
+    wopt(L!("forward-jump"), ArgType::RequiredArgument, '\x07'),
+    wopt(L!("backward-jump"), ArgType::RequiredArgument, '\x08'),
+    wopt(L!("forward-jump-till"), ArgType::RequiredArgument, '\x09'),
+    wopt(L!("backward-jump-till"), ArgType::RequiredArgument, '\x0a'),
+];
+
 /// The commandline builtin. It is used for specifying a new value for the commandline.
 pub fn commandline(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) -> BuiltinResult {
     let rstate = commandline_get_state(true);
@@ -269,42 +304,7 @@ pub fn commandline(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr])
     let mut backward_jump_till = false;
     let mut jump_target = None;
 
-    let short_options = L!("abijpctfxorhI:CBELSsP");
-    let long_options: &[WOption] = &[
-        wopt(L!("append"), ArgType::NoArgument, 'a'),
-        wopt(L!("insert"), ArgType::NoArgument, 'i'),
-        wopt(L!("insert-smart"), ArgType::NoArgument, '\x06'),
-        wopt(L!("replace"), ArgType::NoArgument, 'r'),
-        wopt(L!("current-buffer"), ArgType::NoArgument, 'b'),
-        wopt(L!("current-job"), ArgType::NoArgument, 'j'),
-        wopt(L!("current-process"), ArgType::NoArgument, 'p'),
-        wopt(L!("current-selection"), ArgType::NoArgument, 's'),
-        wopt(L!("current-token"), ArgType::NoArgument, 't'),
-        wopt(L!("cut-at-cursor"), ArgType::NoArgument, 'c'),
-        wopt(L!("function"), ArgType::NoArgument, 'f'),
-        wopt(L!("tokens-expanded"), ArgType::NoArgument, 'x'),
-        wopt(L!("tokens-raw"), ArgType::NoArgument, '\x02'),
-        wopt(L!("tokenize"), ArgType::NoArgument, 'o'),
-        wopt(L!("help"), ArgType::NoArgument, 'h'),
-        wopt(L!("input"), ArgType::RequiredArgument, 'I'),
-        wopt(L!("cursor"), ArgType::NoArgument, 'C'),
-        wopt(L!("selection-start"), ArgType::NoArgument, 'B'),
-        wopt(L!("selection-end"), ArgType::NoArgument, 'E'),
-        wopt(L!("line"), ArgType::NoArgument, 'L'),
-        wopt(L!("column"), ArgType::NoArgument, '\x05'),
-        wopt(L!("search-mode"), ArgType::NoArgument, 'S'),
-        wopt(L!("paging-mode"), ArgType::NoArgument, 'P'),
-        wopt(L!("paging-full-mode"), ArgType::NoArgument, 'F'),
-        wopt(L!("search-field"), ArgType::NoArgument, '\x03'),
-        wopt(L!("is-valid"), ArgType::NoArgument, '\x01'),
-        wopt(L!("showing-suggestion"), ArgType::NoArgument, '\x04'),
-        wopt(L!("forward-jump"), ArgType::RequiredArgument, '\x07'),
-        wopt(L!("backward-jump"), ArgType::RequiredArgument, '\x08'),
-        wopt(L!("forward-jump-till"), ArgType::RequiredArgument, '\x09'),
-        wopt(L!("backward-jump-till"), ArgType::RequiredArgument, '\x0a'),
-    ];
-
-    let mut w = WGetopter::new(short_options, long_options, args);
+    let mut w = WGetopter::new(SHORT_OPTIONS, LONG_OPTIONS, args);
     let cmd = w.argv[0];
     while let Some(c) = w.next_opt() {
         match c {
@@ -1,6 +1,9 @@
 use super::prelude::*;
 use crate::common::{ScopeGuard, UnescapeFlags, UnescapeStringStyle, unescape_string};
-use crate::complete::{CompletionRequestOptions, complete_add_wrapper, complete_remove_wrapper};
+use crate::complete::{
+    CompletionRequestOptions, complete_add_from_help, complete_add_wrapper,
+    complete_remove_wrapper, complete_set_cache_ttl,
+};
 use crate::highlight::highlight_and_colorize;
 use crate::operation_context::OperationContext;
 use crate::parse_constants::ParseErrorList;
@@ -239,6 +242,35 @@ fn builtin_complete_print(
 
 /// Values used for long-only options.
 const OPT_ESCAPE: char = '\x01';
+const OPT_CACHE_TTL: char = '\x02';
+const OPT_FROM_HELP: char = '\x03';
+
+pub(crate) const SHORT_OPTIONS: &wstr = L!("a:c:p:s:l:o:d:fFrxeuAn:C::w:hk");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
+    wopt(L!("exclusive"), ArgType::NoArgument, 'x'),
+    wopt(L!("no-files"), ArgType::NoArgument, 'f'),
+    wopt(L!("force-files"), ArgType::NoArgument, 'F'),
+    wopt(L!("require-parameter"), ArgType::NoArgument, 'r'),
+    wopt(L!("path"), ArgType::RequiredArgument, 'p'),
+    wopt(L!("command"), ArgType::RequiredArgument, 'c'),
+    wopt(L!("short-option"), ArgType::RequiredArgument, 's'),
+    wopt(L!("long-option"), ArgType::RequiredArgument, 'l'),
+    wopt(L!("old-option"), ArgType::RequiredArgument, 'o'),
+    wopt(L!("description"), ArgType::RequiredArgument, 'd'),
+    wopt(L!("arguments"), ArgType::RequiredArgument, 'a'),
+    wopt(L!("erase"), ArgType::NoArgument, 'e'),
+    wopt(L!("unauthoritative"), ArgType::NoArgument, 'u'),
+    wopt(L!("authoritative"), ArgType::NoArgument, 'A'),
+    wopt(L!("condition"), ArgType::RequiredArgument, 'n'),
+    wopt(L!("wraps"), ArgType::RequiredArgument, 'w'),
+    wopt(L!("do-complete"), ArgType::OptionalArgument, 'C'),
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+    wopt(L!("keep-order"), ArgType::NoArgument, 'k'),
+    wopt(L!("escape"), ArgType::NoArgument, OPT_ESCAPE),
+    wopt(L!("cache-ttl"), ArgType::RequiredArgument, OPT_CACHE_TTL),
+    wopt(L!("from-help"), ArgType::NoArgument, OPT_FROM_HELP),
+    wopt(L!("color"), ArgType::RequiredArgument, COLOR_OPTION_CHAR),
+];
 
 /// The complete builtin. Used for specifying programmable tab-completions. Calls the functions in
 /// complete.rs for any heavy lifting.
@@ -266,35 +298,12 @@ pub fn complete(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) ->
     let mut preserve_order = false;
     let mut unescape_output = true;
     let mut color = ColorEnabled::default();
-
-    let short_options: &wstr = L!("a:c:p:s:l:o:d:fFrxeuAn:C::w:hk");
-    let long_options: &[WOption] = &[
-        wopt(L!("exclusive"), ArgType::NoArgument, 'x'),
-        wopt(L!("no-files"), ArgType::NoArgument, 'f'),
-        wopt(L!("force-files"), ArgType::NoArgument, 'F'),
-        wopt(L!("require-parameter"), ArgType::NoArgument, 'r'),
-        wopt(L!("path"), ArgType::RequiredArgument, 'p'),
-        wopt(L!("command"), ArgType::RequiredArgument, 'c'),
-        wopt(L!("short-option"), ArgType::RequiredArgument, 's'),
-        wopt(L!("long-option"), ArgType::RequiredArgument, 'l'),
-        wopt(L!("old-option"), ArgType::RequiredArgument, 'o'),
-        wopt(L!("description"), ArgType::RequiredArgument, 'd'),
-        wopt(L!("arguments"), ArgType::RequiredArgument, 'a'),
-        wopt(L!("erase"), ArgType::NoArgument, 'e'),
-        wopt(L!("unauthoritative"), ArgType::NoArgument, 'u'),
-        wopt(L!("authoritative"), ArgType::NoArgument, 'A'),
-        wopt(L!("condition"), ArgType::RequiredArgument, 'n'),
-        wopt(L!("wraps"), ArgType::RequiredArgument, 'w'),
-        wopt(L!("do-complete"), ArgType::OptionalArgument, 'C'),
-        wopt(L!("help"), ArgType::NoArgument, 'h'),
-        wopt(L!("keep-order"), ArgType::NoArgument, 'k'),
-        wopt(L!("escape"), ArgType::NoArgument, OPT_ESCAPE),
-        wopt(L!("color"), ArgType::RequiredArgument, COLOR_OPTION_CHAR),
-    ];
+    let mut cache_ttl = None;
+    let mut from_help = false;
 
     let mut have_x = false;
 
-    let mut w = WGetopter::new(short_options, long_options, argv);
+    let mut w = WGetopter::new(SHORT_OPTIONS, LONG_OPTIONS, argv);
     while let Some(opt) = w.next_opt() {
         match opt {
             'x' => {
@@ -399,6 +408,12 @@ pub fn complete(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) ->
             OPT_ESCAPE => {
                 unescape_output = false;
             }
+            OPT_CACHE_TTL => {
+                cache_ttl = Some(parse_duration_arg(streams, cmd, w.woptarg.unwrap())?);
+            }
+            OPT_FROM_HELP => {
+                from_help = true;
+            }
             'h' => {
                 builtin_print_help(parser, streams, cmd);
                 return Ok(SUCCESS);
@@ -458,6 +473,15 @@ pub fn complete(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) ->
         }
     }
 
+    if from_help && (do_complete || remove || !path.is_empty()) {
+        streams.err.appendln(&wgettext_fmt!(
+            BUILTIN_ERR_COMBO2,
+            cmd,
+            "'--from-help' only works with commands"
+        ));
+        return Err(STATUS_INVALID_ARGS);
+    }
+
     for condition_string in &condition {
         let mut errors = ParseErrorList::new();
         if detect_parse_errors(condition_string, Some(&mut errors), false).is_err() {
@@ -589,6 +613,8 @@ pub fn complete(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) ->
         && !result_mode.no_files
         && !result_mode.force_files
         && !result_mode.requires_param
+        && cache_ttl.is_none()
+        && !from_help
     {
         // No arguments that would add or remove anything specified, so we print the definitions of
         // all matching completions.
@@ -618,18 +644,49 @@ pub fn complete(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) ->
                 &wrap_targets,
             );
         } else {
-            builtin_complete_add(
-                &cmd_to_complete,
-                &path,
-                &short_opt,
-                &gnu_opt,
-                &old_opt,
-                result_mode,
-                &condition,
-                &comp,
-                &desc,
-                flags,
-            );
+            if let Some(cache_ttl) = cache_ttl {
+                // A TTL of zero stops caching.
+                let cache_ttl = Some(cache_ttl).filter(|ttl| !ttl.is_zero());
+                for cmd in &cmd_to_complete {
+                    complete_set_cache_ttl(cmd.clone(), false, cache_ttl);
+                }
+                for path in &path {
+                    complete_set_cache_ttl(path.clone(), true, cache_ttl);
+                }
+            }
+
+            if from_help {
+                for cmd in &cmd_to_complete {
+                    complete_add_from_help(cmd.clone());
+                }
+            }
+
+            // `--cache-ttl` and `--from-help` on their own add no options.
+            let only_settings = (cache_ttl.is_some() || from_help)
+                && short_opt.is_empty()
+                && gnu_opt.is_empty()
+                && old_opt.is_empty()
+                && comp.is_empty()
+                && desc.is_empty()
+                && condition.is_empty()
+                && wrap_targets.is_empty()
+                && !result_mode.no_files
+                && !result_mode.force_files
+                && !result_mode.requires_param;
+            if !only_settings {
+                builtin_complete_add(
+                    &cmd_to_complete,
+                    &path,
+                    &short_opt,
+                    &gnu_opt,
+                    &old_opt,
+                    result_mode,
+                    &condition,
+                    &comp,
+                    &desc,
+                    flags,
+                );
+            }
             // Handle wrap targets (probably empty). We only wrap commands, not paths.
             for wrap_target in wrap_targets {
                 for i in &cmd_to_complete {
@@ -7,6 +7,12 @@ struct Options {
     print_index: bool,
 }
 
+pub(crate) const SHORT_OPTS: &wstr = L!("+hi");
+pub(crate) const LONG_OPTS: &[WOption] = &[
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+    wopt(L!("index"), ArgType::NoArgument, 'i'),
+];
+
 fn parse_options(
     args: &mut [&wstr],
     parser: &Parser,
@@ -14,12 +20,6 @@ fn parse_options(
 ) -> Result<(Options, usize), ErrorCode> {
     let cmd = args[0];
 
-    const SHORT_OPTS: &wstr = L!("+hi");
-    const LONG_OPTS: &[WOption] = &[
-        wopt(L!("help"), ArgType::NoArgument, 'h'),
-        wopt(L!("index"), ArgType::NoArgument, 'i'),
-    ];
-
     let mut opts = Options::default();
 
     let mut w = WGetopter::new(SHORT_OPTS, LONG_OPTS, args);
@@ -0,0 +1,44 @@
+//! Implementation of the defer builtin.
+
+use super::prelude::*;
+use crate::common::escape;
+
+pub fn defer(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) -> BuiltinResult {
+    let Some(&cmd) = argv.first() else {
+        return Err(STATUS_INVALID_ARGS);
+    };
+
+    let opts = HelpOnlyCmdOpts::parse(argv, parser, streams)?;
+
+    if opts.print_help {
+        builtin_print_help(parser, streams, cmd);
+        return Ok(SUCCESS);
+    }
+
+    let args = &argv[opts.optind..];
+    if args.is_empty() {
+        streams
+            .err
+            .appendln(&wgettext_fmt!("%s: expected a command", cmd));
+        builtin_print_error_trailer(parser, streams.err, cmd);
+        return Err(STATUS_INVALID_ARGS);
+    }
+
+    // The arguments have already been expanded, so escape them to run them as they are.
+    let mut command = WString::new();
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            command.push(' ');
+        }
+        command.push_utfstr(&escape(arg));
+    }
+
+    if !parser.defer_command(command) {
+        streams.err.appendln(&wgettext_fmt!(
+            "%s: Not inside of function or begin block",
+            cmd
+        ));
+        return Err(STATUS_CMD_ERROR);
+    }
+    Ok(SUCCESS)
+}
@@ -3,6 +3,7 @@
 use super::prelude::*;
 use crate::io::IoBufferfill;
 use crate::parser::BlockType;
+use crate::proc::JobGroupRef;
 use fish_wcstringutil::join_strings;
 use libc::{STDERR_FILENO, STDOUT_FILENO};
 
@@ -13,7 +14,17 @@ pub fn eval(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) -> Bui
     }
 
     let new_cmd = join_strings(&args[1..], ' ');
+    let job_group = streams.job_group.clone();
+    eval_in_job_group(parser, streams, &new_cmd, job_group.as_ref())
+}
 
+/// Evaluate a command like `eval` does, with its jobs in the given job group.
+pub fn eval_in_job_group(
+    parser: &Parser,
+    streams: &mut IoStreams,
+    cmd: &wstr,
+    job_group: Option<&JobGroupRef>,
+) -> BuiltinResult {
     // Copy the full io chain; we may append bufferfills.
     let mut ios = streams.io_chain.clone();
 
@@ -52,13 +63,7 @@ pub fn eval(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) -> Bui
         }
     }
 
-    let res = parser.eval_with(
-        &new_cmd,
-        &ios,
-        streams.job_group.as_ref(),
-        BlockType::top,
-        false,
-    );
+    let res = parser.eval_with(cmd, &ios, job_group, BlockType::top, false);
     let status = if res.was_empty {
         // Issue #5692, in particular, to catch `eval ""`, `eval "begin; end;"`, etc.
         // where we have an argument but nothing is executed.
@@ -23,13 +23,17 @@
 use crate::future_feature_flags;
 use crate::global_safety::RelaxedAtomicBool;
 use crate::highlight::{HighlightRole, HighlightSpec, colorize, highlight_shell};
+use crate::lint::lint;
 use crate::operation_context::OperationContext;
-use crate::parse_constants::{ParseTokenType, ParseTreeFlags, SourceRange};
-use crate::parse_util::{SPACES_PER_INDENT, apply_indents, compute_indents};
+use crate::parse_constants::{ParseKeyword, ParseTokenType, ParseTreeFlags, SourceRange};
+use crate::parse_util::{SPACES_PER_INDENT, apply_indents, compute_indents, lineno};
 use crate::prelude::*;
 use crate::print_help::print_help;
+use crate::redirection::RedirectionMode;
 use crate::threads;
-use crate::tokenizer::{TOK_SHOW_BLANK_LINES, TOK_SHOW_COMMENTS, TokenType, Tokenizer};
+use crate::tokenizer::{
+    Heredoc, PipeOrRedir, TOK_SHOW_BLANK_LINES, TOK_SHOW_COMMENTS, TokenType, Tokenizer,
+};
 use crate::topic_monitor::topic_monitor_init;
 use crate::wutil::fish_iswalnum;
 use assert_matches::assert_matches;
@@ -81,6 +85,9 @@ struct PrettyPrinterState<'source, 'ast> {
     preferred_semi_locations: Vec<usize>,
 
     errors: Option<&'ast SourceRangeList>,
+
+    // The heredocs from our ast, whose bodies are emitted unchanged.
+    heredocs: &'ast [Heredoc],
 }
 
 #[derive(Copy, Clone, Default, Debug)]
@@ -177,6 +184,7 @@ fn new(source: &'source wstr, ast: &'ast Ast, do_indent: bool) -> Self {
                 multi_line_brace_statement_locations: vec![],
                 preferred_semi_locations: vec![],
                 errors: None,
+                heredocs: &ast.extras.heredocs,
             },
         };
         zelf.state.gaps = zelf.compute_gaps();
@@ -490,7 +498,38 @@ fn emit_space_or_indent(&mut self, flags: GapFlags) {
     //   begin | stuff
     //
     //  We do not handle errors here - instead our caller does.
+    //
+    // 4. It may contain the bodies of heredocs, which are emitted unchanged.
     fn emit_gap_text(&mut self, range: SourceRange, flags: GapFlags) -> bool {
+        if let Some(heredoc) = self
+            .heredocs
+            .iter()
+            .find(|heredoc| range.as_usize().contains(&heredoc.body.start()))
+        {
+            let body_start = heredoc.body.start();
+            let end = heredoc.terminator.end();
+            self.emit_gap_text(
+                SourceRange::new(range.start(), body_start - range.start()),
+                flags,
+            );
+            if !self.at_line_start() {
+                self.emit_newline();
+            }
+            self.output.push_utfstr(&self.source[body_start..end]);
+            self.emit_newline();
+            // We just emitted the newline after the terminator.
+            let rest_start = if self.source.char_at(end) == '\n' {
+                end + 1
+            } else {
+                end
+            }
+            .min(range.end());
+            return self.emit_gap_text(
+                SourceRange::new(rest_start, range.end() - rest_start),
+                flags,
+            );
+        }
+
         let gap_text = &self.source[range.start()..range.end()];
         // Common case: if we are only spaces, do nothing.
         if !gap_text.chars().any(|c| c != ' ') {
@@ -707,11 +746,16 @@ fn visit_semi_nl(&mut self, node: &dyn ast::Token) {
         let range = node.source_range();
 
         // Check if we should prefer a semicolon.
+        // Semicolons before the end of a line with a heredoc stay, since a newline would start the
+        // heredoc body.
         let prefer_semi = range.length() > 0
-            && self
+            && (self
                 .preferred_semi_locations
                 .binary_search(&range.start())
-                .is_ok();
+                .is_ok()
+                || self.heredocs.iter().any(|heredoc| {
+                    heredoc.delimiter.start() < range.start() && range.end() < heredoc.body.start()
+                }));
 
         self.emit_gap_text_before(range, self.gap_text_flags_before_node(node.as_node()));
 
@@ -807,13 +851,20 @@ fn visit_redirection(&mut self, node: &ast::Redirection) {
         let Some(trange) = node.target.range() else {
             return;
         };
-        self.emit_text(
-            trange,
-            GapFlags {
-                skip_space: true,
-                ..Default::default()
-            },
-        );
+        let flags = GapFlags {
+            skip_space: true,
+            ..Default::default()
+        };
+        let is_heredoc = PipeOrRedir::try_from(self.substr(orange))
+            .is_ok_and(|redir| redir.mode == RedirectionMode::HereDoc);
+        if is_heredoc {
+            // Whether a heredoc delimiter is quoted matters, so don't clean it up.
+            self.emit_gap_text_before(trange, flags);
+            let source = self.source;
+            self.output.push_utfstr(&source[trange.as_usize()]);
+        } else {
+            self.emit_text(trange, flags);
+        }
     }
 
     fn visit_maybe_newlines(&mut self, node: &ast::MaybeNewlines) {
@@ -850,12 +901,27 @@ fn visit_begin_header(&mut self, node: &ast::BeginHeader) {
         }
     }
 
+    fn visit_try_or_catch_keyword(&mut self, node: &dyn Node) {
+        // Like 'begin', 'try' and 'catch' do not require a newline after them, but we insert one.
+        let semi_nl = match self.traversal.parent(node).kind() {
+            Kind::TryStatement(stmt) => &stmt.semi_nl,
+            Kind::CatchClause(clause) => &clause.semi_nl,
+            _ => return,
+        };
+        if semi_nl.is_none() {
+            self.emit_newline();
+        }
+    }
+
     // Prettify our ast traversal, populating the output.
     fn prettify_traversal(&mut self) {
         while let Some(node) = self.traversal.next() {
             // Leaf nodes we just visit their text.
-            if node.as_keyword().is_some() {
+            if let Some(keyword) = node.as_keyword() {
                 self.emit_node_text(node);
+                if matches!(keyword.keyword(), ParseKeyword::Try | ParseKeyword::Catch) {
+                    self.visit_try_or_catch_keyword(node);
+                }
                 continue;
             }
             if let Some(token) = node.as_token() {
@@ -971,6 +1037,7 @@ enum OutputType {
         PygmentsCsv,
         Check,
         Html,
+        Lint,
     }
 
     let mut output_type = OutputType::PlainText;
@@ -992,6 +1059,7 @@ enum OutputType {
         wopt(L!("ansi"), ArgType::NoArgument, '\x02'),
         wopt(L!("pygments"), ArgType::NoArgument, '\x03'),
         wopt(L!("check"), ArgType::NoArgument, 'c'),
+        wopt(L!("lint"), ArgType::NoArgument, '\x06'),
     ];
 
     let mut shim_args: Vec<&wstr> = args.iter().map(|s| s.as_ref()).collect();
@@ -1024,6 +1092,7 @@ enum OutputType {
             '\x02' => output_type = OutputType::Ansi,
             '\x03' => output_type = OutputType::PygmentsCsv,
             'c' => output_type = OutputType::Check,
+            '\x06' => output_type = OutputType::Lint,
             ';' => {
                 streams.err.appendln(&wgettext_fmt!(
                     BUILTIN_ERR_UNEXP_ARG,
@@ -1100,6 +1169,33 @@ enum OutputType {
             }
         }
 
+        if output_type == OutputType::Lint {
+            // Report problems as FILE:LINE:COLUMN: MESSAGE, with "-" for standard input.
+            let filename = args.get(i).copied().unwrap_or(L!("-"));
+            let diagnostics = lint(&src);
+            for diagnostic in &diagnostics {
+                // Errors may have an unknown location, or one past the end of the source.
+                let start = diagnostic.source_start.min(src.len());
+                let line_start = src[..start]
+                    .as_char_slice()
+                    .iter()
+                    .rposition(|&c| c == '\n')
+                    .map_or(0, |newline| newline + 1);
+                streams.out.appendln(&sprintf!(
+                    "%s:%u:%u: %s",
+                    filename,
+                    lineno(&src, start),
+                    start - line_start + 1,
+                    diagnostic.message
+                ));
+            }
+            if !diagnostics.is_empty() {
+                retval += 1;
+            }
+            i += 1;
+            continue;
+        }
+
         if output_type == OutputType::PygmentsCsv {
             let output = make_pygments_csv(&src);
             streams.out.append(&bytes2wcstring(&output));
@@ -1187,7 +1283,7 @@ enum OutputType {
             OutputType::Html => {
                 colored_output = html_colorize(&output_wtext, &colors);
             }
-            OutputType::PygmentsCsv => {
+            OutputType::PygmentsCsv | OutputType::Lint => {
                 unreachable!()
             }
             OutputType::Check => {
@@ -1,8 +1,18 @@
 //! Implementation of the history builtin.
 
+use std::os::fd::FromRawFd as _;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use nix::{fcntl::OFlag, sys::stat::Mode};
+
+use crate::common::{escape, wcs2zstring};
+use crate::fds::{BorrowedFdFile, wopen_cloexec};
 use crate::history::in_private_mode;
-use crate::history::{self, History, history_session_id};
+use crate::history::{self, History, HistoryFilter, HistoryFormat, history_session_id};
+use crate::nix::isatty;
+use crate::path::path_apply_working_directory;
 use crate::reader::commandline_get_state;
+use crate::wutil::normalize_path;
 
 use super::prelude::*;
 
@@ -17,6 +27,9 @@ enum HistCmd {
     None,
     ClearSession,
     Append,
+    Export,
+    Import,
+    Vacuum,
 }
 
 impl HistCmd {
@@ -30,6 +43,9 @@ fn to_wstr(&self) -> &'static wstr {
             HistCmd::None => panic!(),
             HistCmd::ClearSession => L!("clear-session"),
             HistCmd::Append => L!("append"),
+            HistCmd::Export => L!("export"),
+            HistCmd::Import => L!("import"),
+            HistCmd::Vacuum => L!("vacuum"),
         }
     }
 }
@@ -45,6 +61,9 @@ fn try_from(val: &wstr) -> Result<Self, ()> {
             _ if val == "save" => Ok(HistCmd::Save),
             _ if val == "clear-session" => Ok(HistCmd::ClearSession),
             _ if val == "append" => Ok(HistCmd::Append),
+            _ if val == "export" => Ok(HistCmd::Export),
+            _ if val == "import" => Ok(HistCmd::Import),
+            _ if val == "vacuum" => Ok(HistCmd::Vacuum),
             _ => Err(()),
         }
     }
@@ -61,19 +80,22 @@ struct HistoryCmdOpts {
     null_terminate: bool,
     reverse: bool,
     color: ColorEnabled,
+    filter: HistoryFilter,
+    format: Option<HistoryFormat>,
 }
 
 /// Note: Do not add new flags that represent subcommands. We're encouraging people to switch to
 /// the non-flag subcommand form. While many of these flags are deprecated they must be
 /// supported at least until fish 3.0 and possibly longer to avoid breaking everyones
 /// config.fish and other scripts.
-const SHORT_OPTIONS: &wstr = L!("CRcehmn:pt::z");
+const SHORT_OPTIONS: &wstr = L!("CRcehmn:prt::z");
 const LONG_OPTIONS: &[WOption] = &[
     wopt(L!("prefix"), ArgType::NoArgument, 'p'),
     wopt(L!("contains"), ArgType::NoArgument, 'c'),
     wopt(L!("help"), ArgType::NoArgument, 'h'),
     wopt(L!("show-time"), ArgType::OptionalArgument, 't'),
     wopt(L!("exact"), ArgType::NoArgument, 'e'),
+    wopt(L!("regex"), ArgType::NoArgument, 'r'),
     wopt(L!("max"), ArgType::RequiredArgument, 'n'),
     wopt(L!("null"), ArgType::NoArgument, 'z'),
     wopt(L!("case-sensitive"), ArgType::NoArgument, 'C'),
@@ -84,6 +106,13 @@ struct HistoryCmdOpts {
     wopt(L!("merge"), ArgType::NoArgument, '\x05'),
     wopt(L!("reverse"), ArgType::NoArgument, 'R'),
     wopt(L!("color"), ArgType::RequiredArgument, COLOR_OPTION_CHAR),
+    wopt(L!("success"), ArgType::NoArgument, '\x06'),
+    wopt(L!("failed"), ArgType::NoArgument, '\x07'),
+    wopt(L!("cwd"), ArgType::RequiredArgument, '\x08'),
+    wopt(L!("since"), ArgType::RequiredArgument, '\x09'),
+    wopt(L!("until"), ArgType::RequiredArgument, '\x0a'),
+    wopt(L!("duration-over"), ArgType::RequiredArgument, '\x0b'),
+    wopt(L!("format"), ArgType::RequiredArgument, '\x0c'),
 ];
 
 /// Remember the history subcommand and disallow selecting more than one history subcommand.
@@ -106,13 +135,64 @@ fn set_hist_cmd(
     true
 }
 
+/// Parse the argument to `--since` or `--until`: either seconds since the epoch, optionally with a
+/// leading `@` like `date` accepts, or a local date and time such as `2024-05-07` or
+/// `2024-05-07 13:45:00`.
+fn parse_timestamp(arg: &wstr) -> Option<SystemTime> {
+    if let Ok(seconds) = fish_wcstol(arg.strip_prefix('@').unwrap_or(arg)) {
+        let seconds = u64::try_from(seconds).ok()?;
+        return Some(UNIX_EPOCH + Duration::from_secs(seconds));
+    }
+
+    let input = wcs2zstring(arg);
+    let formats = [
+        c"%Y-%m-%d %H:%M:%S",
+        c"%Y-%m-%dT%H:%M:%S",
+        c"%Y-%m-%d %H:%M",
+        c"%Y-%m-%dT%H:%M",
+        c"%Y-%m-%d",
+    ];
+    for format in formats {
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        let end = unsafe { libc::strptime(input.as_ptr(), format.as_ptr(), &mut tm) };
+        // Only accept the format if it consumed the entire input.
+        if end.is_null() || unsafe { *end } != 0 {
+            continue;
+        }
+        // Let mktime figure out whether daylight saving time is in effect.
+        tm.tm_isdst = -1;
+        let seconds = unsafe { libc::mktime(&mut tm) };
+        let seconds = u64::try_from(seconds).ok()?;
+        return Some(UNIX_EPOCH + Duration::from_secs(seconds));
+    }
+    None
+}
+
+fn parse_timestamp_arg(
+    streams: &mut IoStreams,
+    cmd: &wstr,
+    arg: &wstr,
+) -> Result<SystemTime, ErrorCode> {
+    parse_timestamp(arg).ok_or_else(|| {
+        streams
+            .err
+            .appendln(&wgettext_fmt!("%s: '%s' is not a valid time", cmd, arg));
+        STATUS_INVALID_ARGS
+    })
+}
+
 fn check_for_unexpected_hist_args(
     opts: &HistoryCmdOpts,
     cmd: &wstr,
     args: &[&wstr],
     streams: &mut IoStreams,
 ) -> bool {
-    if opts.search_type.is_some() || opts.show_time_format.is_some() || opts.null_terminate {
+    if opts.search_type.is_some()
+        || opts.show_time_format.is_some()
+        || opts.null_terminate
+        || !opts.filter.is_empty()
+        || opts.format.is_some()
+    {
         let subcmd_str = opts.hist_cmd.to_wstr();
         streams.err.appendln(&wgettext_fmt!(
             "%s: %s: subcommand takes no options",
@@ -135,6 +215,34 @@ fn check_for_unexpected_hist_args(
     false
 }
 
+/// Report errors and skipped entries of an import from `path`. Returns whether everything was
+/// imported.
+fn report_import(
+    cmd: &wstr,
+    path: &wstr,
+    result: std::io::Result<usize>,
+    streams: &mut IoStreams,
+) -> bool {
+    match result {
+        Ok(0) => true,
+        Ok(malformed) => {
+            streams.err.appendln(&wgettext_fmt!(
+                "%s: %s: skipped %u malformed entries",
+                cmd,
+                path,
+                malformed
+            ));
+            false
+        }
+        Err(err) => {
+            streams
+                .err
+                .appendln(&sprintf!("%s: %s: %s", cmd, path, err.to_string()));
+            false
+        }
+    }
+}
+
 fn parse_cmd_opts(
     opts: &mut HistoryCmdOpts,
     optind: &mut usize,
@@ -189,6 +297,9 @@ fn parse_cmd_opts(
             'e' => {
                 opts.search_type = Some(history::SearchType::Exact);
             }
+            'r' => {
+                opts.search_type = Some(history::SearchType::Regex);
+            }
             't' => {
                 opts.show_time_format = Some(w.woptarg.unwrap_or(L!("# %c%n")).to_string());
             }
@@ -231,6 +342,48 @@ fn parse_cmd_opts(
             COLOR_OPTION_CHAR => {
                 opts.color = ColorEnabled::parse_from_opt(streams, cmd, w.woptarg.unwrap())?;
             }
+            '\x06' | '\x07' => {
+                let success = opt == '\x06';
+                if opts.filter.success == Some(!success) {
+                    streams.err.appendln(&wgettext_fmt!(
+                        BUILTIN_ERR_COMBO2_EXCLUSIVE,
+                        cmd,
+                        "--success",
+                        "--failed"
+                    ));
+                    return Err(STATUS_INVALID_ARGS);
+                }
+                opts.filter.success = Some(success);
+            }
+            '\x08' => {
+                let dir = path_apply_working_directory(
+                    w.woptarg.unwrap(),
+                    &parser.vars().get_pwd_slash(),
+                );
+                opts.filter.cwd = Some(normalize_path(&dir, true));
+            }
+            '\x09' => {
+                opts.filter.since = Some(parse_timestamp_arg(streams, cmd, w.woptarg.unwrap())?);
+            }
+            '\x0a' => {
+                opts.filter.until = Some(parse_timestamp_arg(streams, cmd, w.woptarg.unwrap())?);
+            }
+            '\x0b' => {
+                opts.filter.duration_over =
+                    Some(parse_duration_arg(streams, cmd, w.woptarg.unwrap())?);
+            }
+            '\x0c' => {
+                let format = w.woptarg.unwrap();
+                let Some(format) = HistoryFormat::from_name(format) else {
+                    streams.err.appendln(&wgettext_fmt!(
+                        "%s: '%s' is not a valid history format",
+                        cmd,
+                        format
+                    ));
+                    return Err(STATUS_INVALID_ARGS);
+                };
+                opts.format = Some(format);
+            }
             _ => {
                 panic!("unexpected retval from WGetopter");
             }
@@ -282,12 +435,26 @@ pub fn history(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) ->
     let mut status = Ok(SUCCESS);
     match opts.hist_cmd {
         HistCmd::None | HistCmd::Search => {
+            if opts.search_type == Some(history::SearchType::Regex) {
+                for pattern in args {
+                    if let Err(error) = history::compile_search_regex(pattern, opts.case_sensitive)
+                    {
+                        streams.err.appendln(&wgettext_fmt!(
+                            BUILTIN_ERR_REGEX_COMPILE,
+                            cmd,
+                            error.error_message(),
+                        ));
+                        return Err(STATUS_INVALID_ARGS);
+                    }
+                }
+            }
             if !history.search(
                 parser,
                 streams,
                 opts.search_type
                     .unwrap_or(history::SearchType::ContainsGlob),
                 args,
+                &opts.filter,
                 opts.show_time_format.as_deref(),
                 opts.max_items.unwrap_or(usize::MAX),
                 opts.case_sensitive,
@@ -316,6 +483,14 @@ pub fn history(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) ->
                 ));
                 return Err(STATUS_INVALID_ARGS);
             }
+            if !opts.filter.is_empty() {
+                streams.err.appendln(&wgettext_fmt!(
+                    "%s: %s: subcommand does not support filter options",
+                    cmd,
+                    opts.hist_cmd.to_wstr()
+                ));
+                return Err(STATUS_INVALID_ARGS);
+            }
 
             for delete_string in args {
                 history.remove(delete_string);
@@ -355,11 +530,91 @@ pub fn history(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) ->
             }
             history.save();
         }
+        HistCmd::Vacuum => {
+            if check_for_unexpected_hist_args(&opts, cmd, args, streams) {
+                return Err(STATUS_INVALID_ARGS);
+            }
+            history.vacuum();
+        }
         HistCmd::Append => {
             for &arg in args {
                 history.add_commandline(arg.to_owned());
             }
         }
+        HistCmd::Export => {
+            if opts.search_type.is_some() || opts.show_time_format.is_some() || opts.null_terminate
+            {
+                streams.err.appendln(&wgettext_fmt!(
+                    "%s: %s: subcommand only takes --format and filter options",
+                    cmd,
+                    opts.hist_cmd.to_wstr()
+                ));
+                return Err(STATUS_INVALID_ARGS);
+            }
+            if !args.is_empty() {
+                streams.err.appendln(&wgettext_fmt!(
+                    BUILTIN_ERR_ARG_COUNT2,
+                    cmd,
+                    opts.hist_cmd.to_wstr(),
+                    0,
+                    args.len()
+                ));
+                return Err(STATUS_INVALID_ARGS);
+            }
+            let format = opts.format.unwrap_or(HistoryFormat::Jsonl);
+            if format != HistoryFormat::Jsonl {
+                streams.err.appendln(&wgettext_fmt!(
+                    "%s: cannot export history as '%s'",
+                    cmd,
+                    format.name()
+                ));
+                return Err(STATUS_INVALID_ARGS);
+            }
+            history.export_jsonl(streams, &opts.filter, &parser.context().cancel_checker);
+        }
+        HistCmd::Import => {
+            let format = opts.format.take().unwrap_or(HistoryFormat::Jsonl);
+            if check_for_unexpected_hist_args(&opts, cmd, &[], streams) {
+                return Err(STATUS_INVALID_ARGS);
+            }
+            if args.is_empty() || args == [L!("-")] {
+                if streams.is_stdin_closed() {
+                    streams
+                        .err
+                        .appendln(&wgettext_fmt!(BUILTIN_ERR_STDIN_CLOSED, cmd));
+                    return Err(STATUS_CMD_ERROR);
+                }
+                if args.is_empty() && isatty(streams.stdin_fd()) {
+                    // Don't implicitly read from the terminal.
+                    streams.err.appendln(&wgettext_fmt!(
+                        "%s: missing filename argument or input redirection",
+                        cmd
+                    ));
+                    return Err(STATUS_CMD_ERROR);
+                }
+                // Safety: the fd stays open for the duration of the builtin and is not closed by us.
+                let stdin = unsafe { BorrowedFdFile::from_raw_fd(streams.stdin_fd()) };
+                if !report_import(cmd, L!("-"), history.import(format, stdin), streams) {
+                    status = Err(STATUS_CMD_ERROR);
+                }
+            } else {
+                for &path in args {
+                    let Ok(file) = wopen_cloexec(path, OFlag::O_RDONLY, Mode::empty()) else {
+                        streams.err.appendln(&wgettext_fmt!(
+                            "%s: Error encountered while opening file '%s':",
+                            cmd,
+                            &escape(path)
+                        ));
+                        builtin_wperror(cmd, streams);
+                        status = Err(STATUS_CMD_ERROR);
+                        continue;
+                    };
+                    if !report_import(cmd, path, history.import(format, file), streams) {
+                        status = Err(STATUS_CMD_ERROR);
+                    }
+                }
+            }
+        }
     }
 
     status
@@ -124,8 +124,8 @@ fn builtin_jobs_print(j: &Job, mode: JobsPrintMode, header: bool, streams: &mut
     }
 }
 
-const SHORT_OPTIONS: &wstr = L!("cghlpq");
-const LONG_OPTIONS: &[WOption] = &[
+pub(crate) const SHORT_OPTIONS: &wstr = L!("cghlpq");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
     wopt(L!("command"), ArgType::NoArgument, 'c'),
     wopt(L!("group"), ArgType::NoArgument, 'g'),
     wopt(L!("help"), ArgType::NoArgument, 'h'),
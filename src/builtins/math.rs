@@ -2,7 +2,7 @@
 use num_traits::pow;
 
 use super::prelude::*;
-use crate::tinyexpr::te_interp;
+use crate::tinyexpr::{te_interp, te_interp_int};
 
 /// The maximum number of points after the decimal that we'll print.
 const DEFAULT_SCALE: usize = 6;
@@ -26,6 +26,8 @@ struct Options {
     scale: usize,
     base: usize,
     scale_mode: ScaleMode,
+    int: bool,
+    quiet: bool,
 }
 
 fn parse_cmd_opts(
@@ -38,9 +40,11 @@ fn parse_cmd_opts(
 
     // This command is atypical in using the "+" (REQUIRE_ORDER) option for flag parsing.
     // This is needed because of the minus, `-`, operator in math expressions.
-    const SHORT_OPTS: &wstr = L!("+hs:b:m:");
+    const SHORT_OPTS: &wstr = L!("+hiqs:b:m:");
     const LONG_OPTS: &[WOption] = &[
         wopt(L!("scale"), ArgType::RequiredArgument, 's'),
+        wopt(L!("int"), ArgType::NoArgument, 'i'),
+        wopt(L!("quiet"), ArgType::NoArgument, 'q'),
         wopt(L!("base"), ArgType::RequiredArgument, 'b'),
         wopt(L!("help"), ArgType::NoArgument, 'h'),
         wopt(L!("scale-mode"), ArgType::RequiredArgument, 'm'),
@@ -51,9 +55,12 @@ fn parse_cmd_opts(
         scale: DEFAULT_SCALE,
         base: 10,
         scale_mode: DEFAULT_SCALE_MODE,
+        int: false,
+        quiet: false,
     };
 
     let mut have_scale = false;
+    let mut have_scale_mode = false;
 
     let mut w = WGetopter::new(SHORT_OPTS, LONG_OPTS, args);
     while let Some(c) = w.next_opt() {
@@ -78,6 +85,7 @@ fn parse_cmd_opts(
             }
             'm' => {
                 let optarg = w.woptarg.unwrap();
+                have_scale_mode = true;
                 if optarg.eq(L!("truncate")) || optarg.eq(L!("trunc")) {
                     opts.scale_mode = ScaleMode::Truncate;
                 } else if optarg.eq(L!("round")) {
@@ -113,6 +121,12 @@ fn parse_cmd_opts(
                     opts.base = base as usize;
                 }
             }
+            'i' => {
+                opts.int = true;
+            }
+            'q' => {
+                opts.quiet = true;
+            }
             'h' => {
                 opts.print_help = true;
             }
@@ -151,6 +165,15 @@ fn parse_cmd_opts(
         return Err(STATUS_INVALID_ARGS);
     }
 
+    if opts.int && (have_scale || have_scale_mode) {
+        streams.err.appendln(&wgettext_fmt!(
+            BUILTIN_ERR_COMBO2,
+            cmd,
+            "--scale and --scale-mode are not valid with --int"
+        ));
+        return Err(STATUS_INVALID_ARGS);
+    }
+
     Ok((opts, w.wopt_index))
 }
 
@@ -215,34 +238,66 @@ fn format_double(mut v: f64, opts: &Options) -> WString {
     ret
 }
 
+/// Return a formatted version of the integer `v` respecting the given `opts`.
+fn format_int(v: i64, opts: &Options) -> WString {
+    let mneg = if v < 0 { "-" } else { "" };
+    match opts.base {
+        16 => sprintf!("%s0x%x", mneg, v.unsigned_abs()),
+        8 if v == 0 => L!("0").to_owned(),
+        8 => sprintf!("%s0%o", mneg, v.unsigned_abs()),
+        _ => sprintf!("%d", v),
+    }
+}
+
+/// Check a floating point result for errors, and format it.
+fn check_and_format_double(n: f64, opts: &Options) -> Result<WString, &'static wstr> {
+    // Check some runtime errors after the fact.
+    // TODO: Really, this should be done in tinyexpr
+    // (e.g. infinite is the result of "x / 0"),
+    // but that's much more work.
+    if n.is_infinite() {
+        Err(L!("Result is infinite"))
+    } else if n.is_nan() {
+        Err(L!("Result is not a number"))
+    } else if n.abs() >= MAX_CONTIGUOUS_INTEGER {
+        Err(L!("Result magnitude is too large"))
+    } else {
+        Ok(format_double(n, opts))
+    }
+}
+
 fn evaluate_expression(
     cmd: &wstr,
     streams: &mut IoStreams,
     opts: &Options,
     expression: &wstr,
 ) -> BuiltinResult {
-    let ret = te_interp(expression);
+    // A comparison or logical operation also sets the status, so it can be used as a condition.
+    let ret = if opts.int {
+        te_interp_int(expression).map(|e| {
+            let is_false = e.is_condition && e.value == 0;
+            Ok((format_int(e.value, opts), is_false))
+        })
+    } else {
+        te_interp(expression).map(|e| {
+            let is_false = e.is_condition && e.value == 0.0;
+            check_and_format_double(e.value, opts).map(|s| (s, is_false))
+        })
+    };
 
     match ret {
-        Ok(n) => {
-            // Check some runtime errors after the fact.
-            // TODO: Really, this should be done in tinyexpr
-            // (e.g. infinite is the result of "x / 0"),
-            // but that's much more work.
-            let error_message = if n.is_infinite() {
-                L!("Result is infinite")
-            } else if n.is_nan() {
-                L!("Result is not a number")
-            } else if n.abs() >= MAX_CONTIGUOUS_INTEGER {
-                L!("Result magnitude is too large")
-            } else {
-                let mut s = format_double(n, opts);
+        Ok(Ok((mut s, is_false))) => {
+            if !opts.quiet {
                 s.push('\n');
-
                 streams.out.append(&s);
-                return Ok(SUCCESS);
-            };
-
+            }
+            if is_false {
+                Err(STATUS_CMD_ERROR)
+            } else {
+                Ok(SUCCESS)
+            }
+        }
+        Ok(Err(error_message)) => {
             streams
                 .err
                 .append(&sprintf!("%s: Error: %s\n", cmd, error_message));
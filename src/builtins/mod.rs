@@ -15,6 +15,7 @@
 pub mod contains;
 pub mod r#continue;
 pub mod count;
+pub mod defer;
 pub mod disown;
 pub mod echo;
 pub mod emit;
@@ -43,6 +44,7 @@
 pub mod status;
 pub mod string;
 pub mod test;
+pub mod timeout;
 pub mod r#true;
 pub mod r#type;
 pub mod ulimit;
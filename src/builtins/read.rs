@@ -69,8 +69,8 @@ fn new() -> Self {
     }
 }
 
-const SHORT_OPTIONS: &wstr = L!("ac:d:fghiLln:p:sStuxzP:UR:L");
-const LONG_OPTIONS: &[WOption] = &[
+pub(crate) const SHORT_OPTIONS: &wstr = L!("ac:d:fghiLln:p:sStuxzP:UR:L");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
     wopt(L!("array"), ArgType::NoArgument, 'a'),
     wopt(L!("command"), ArgType::RequiredArgument, 'c'),
     wopt(L!("delimiter"), ArgType::RequiredArgument, 'd'),
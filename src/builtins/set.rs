@@ -23,6 +23,38 @@
 };
 use fish_common::help_section;
 
+/// Values used for long-only options.
+const PATH_ARG: char = 1 as char;
+const UNPATH_ARG: char = 2 as char;
+const NO_EVENT_ARG: char = 3 as char;
+const DICT_ARG: char = 4 as char;
+const KEYS_ARG: char = 5 as char;
+// Variables used for parsing the argument list. This command is atypical in using the "+"
+// (REQUIRE_ORDER) option for flag parsing. This is not typical of most fish commands. It means
+// we stop scanning for flags when the first non-flag argument is seen.
+pub(crate) const SHORT_OPTS: &wstr = L!("+LSUaefghlnpqux");
+pub(crate) const LONG_OPTS: &[WOption] = &[
+    wopt(L!("export"), NoArgument, 'x'),
+    wopt(L!("global"), NoArgument, 'g'),
+    wopt(L!("function"), NoArgument, 'f'),
+    wopt(L!("local"), NoArgument, 'l'),
+    wopt(L!("erase"), NoArgument, 'e'),
+    wopt(L!("names"), NoArgument, 'n'),
+    wopt(L!("unexport"), NoArgument, 'u'),
+    wopt(L!("universal"), NoArgument, 'U'),
+    wopt(L!("long"), NoArgument, 'L'),
+    wopt(L!("query"), NoArgument, 'q'),
+    wopt(L!("show"), NoArgument, 'S'),
+    wopt(L!("append"), NoArgument, 'a'),
+    wopt(L!("prepend"), NoArgument, 'p'),
+    wopt(L!("path"), NoArgument, PATH_ARG),
+    wopt(L!("unpath"), NoArgument, UNPATH_ARG),
+    wopt(L!("no-event"), NoArgument, NO_EVENT_ARG),
+    wopt(L!("dict"), NoArgument, DICT_ARG),
+    wopt(L!("keys"), NoArgument, KEYS_ARG),
+    wopt(L!("help"), NoArgument, 'h'),
+];
+
 #[derive(Debug, Clone)]
 struct Options {
     print_help: bool,
@@ -43,6 +75,8 @@ struct Options {
     prepend: bool,
     preserve_failure_exit_status: bool,
     no_event: bool,
+    dict: bool,
+    keys: bool,
 }
 
 impl Default for Options {
@@ -66,6 +100,8 @@ fn default() -> Self {
             prepend: false,
             preserve_failure_exit_status: true,
             no_event: false,
+            dict: false,
+            keys: false,
         }
     }
 }
@@ -82,6 +118,7 @@ fn env_mode(&self) -> EnvMode {
             (self.universal, EnvMode::UNIVERSAL),
             (self.pathvar, EnvMode::PATHVAR),
             (self.unpathvar, EnvMode::UNPATHVAR),
+            (self.dict, EnvMode::DICT),
         ] {
             if is_mode {
                 scope |= mode;
@@ -96,34 +133,6 @@ fn parse(
         parser: &Parser,
         streams: &mut IoStreams,
     ) -> Result<Option<(Options, usize)>, ErrorCode> {
-        /// Values used for long-only options.
-        const PATH_ARG: char = 1 as char;
-        const UNPATH_ARG: char = 2 as char;
-        const NO_EVENT_ARG: char = 3 as char;
-        // Variables used for parsing the argument list. This command is atypical in using the "+"
-        // (REQUIRE_ORDER) option for flag parsing. This is not typical of most fish commands. It means
-        // we stop scanning for flags when the first non-flag argument is seen.
-        const SHORT_OPTS: &wstr = L!("+LSUaefghlnpqux");
-        const LONG_OPTS: &[WOption] = &[
-            wopt(L!("export"), NoArgument, 'x'),
-            wopt(L!("global"), NoArgument, 'g'),
-            wopt(L!("function"), NoArgument, 'f'),
-            wopt(L!("local"), NoArgument, 'l'),
-            wopt(L!("erase"), NoArgument, 'e'),
-            wopt(L!("names"), NoArgument, 'n'),
-            wopt(L!("unexport"), NoArgument, 'u'),
-            wopt(L!("universal"), NoArgument, 'U'),
-            wopt(L!("long"), NoArgument, 'L'),
-            wopt(L!("query"), NoArgument, 'q'),
-            wopt(L!("show"), NoArgument, 'S'),
-            wopt(L!("append"), NoArgument, 'a'),
-            wopt(L!("prepend"), NoArgument, 'p'),
-            wopt(L!("path"), NoArgument, PATH_ARG),
-            wopt(L!("unpath"), NoArgument, UNPATH_ARG),
-            wopt(L!("no-event"), NoArgument, NO_EVENT_ARG),
-            wopt(L!("help"), NoArgument, 'h'),
-        ];
-
         let mut opts = Self::default();
 
         let mut w = WGetopter::new(SHORT_OPTS, LONG_OPTS, args);
@@ -152,6 +161,11 @@ fn parse(
                 PATH_ARG => opts.pathvar = true,
                 UNPATH_ARG => opts.unpathvar = true,
                 NO_EVENT_ARG => opts.no_event = true,
+                DICT_ARG => opts.dict = true,
+                KEYS_ARG => {
+                    opts.keys = true;
+                    opts.preserve_failure_exit_status = false;
+                }
                 'U' => opts.universal = true,
                 'L' => opts.shorten_ok = false,
                 'S' => {
@@ -272,6 +286,20 @@ fn validate(
             return Err(STATUS_INVALID_ARGS);
         }
 
+        // Dictionaries are never path variables.
+        if opts.dict && (opts.pathvar || opts.unpathvar) {
+            streams.err.appendln(&wgettext_fmt!(BUILTIN_ERR_COMBO, cmd));
+            builtin_print_error_trailer(parser, streams.err, cmd);
+            return Err(STATUS_INVALID_ARGS);
+        }
+
+        // Listing keys is a mode of its own.
+        if opts.keys && (opts.query || opts.erase || opts.list || opts.show) {
+            streams.err.appendln(&wgettext_fmt!(BUILTIN_ERR_COMBO, cmd));
+            builtin_print_error_trailer(parser, streams.err, cmd);
+            return Err(STATUS_INVALID_ARGS);
+        }
+
         // Trying to erase and (un)export at the same time doesn't make sense.
         if opts.erase && (opts.exportv || opts.unexport) {
             streams.err.appendln(&wgettext_fmt!(BUILTIN_ERR_COMBO, cmd));
@@ -406,6 +434,8 @@ struct SplitVar<'a> {
     varname: &'a wstr,
     var: Option<EnvVar>,
     indexes: Vec<isize>,
+    /// The key, if this indexes a dictionary.
+    key: Option<&'a wstr>,
 }
 
 impl<'a> SplitVar<'a> {
@@ -419,6 +449,8 @@ fn varsize(&self) -> usize {
 /// The argument `arg` is split into a variable name and list of indexes, which is returned by
 /// reference. Indexes are "expanded" in the sense that range expressions .. and negative values are
 /// handled.
+/// If the variable is a dictionary, or `mode` asks for one, the argument has the form
+/// `var_name[key]` instead.
 ///
 /// Returns:
 ///   a split var on success, none() on error, in which case an error will have been printed.
@@ -452,6 +484,17 @@ fn split_var_and_indexes_internal<'a>(
         return Ok(res);
     };
 
+    if mode.contains(EnvMode::DICT) || res.var.as_ref().is_some_and(|v| v.is_dict()) {
+        // Everything up to the final bracket is the key.
+        if !arg.ends_with(']') {
+            return Err(EnvArrayParseError::InvalidIndex(
+                arg[open_bracket + 1..].to_owned(),
+            ));
+        }
+        res.key = Some(&arg[open_bracket + 1..arg.len() - 1]);
+        return Ok(res);
+    }
+
     // We need the length of the array to validate the indexes.
     let len = res
         .var
@@ -616,7 +659,12 @@ fn query(
             return Err(STATUS_CMD_ERROR);
         };
 
-        if split.indexes.is_empty() {
+        if let Some(key) = split.key {
+            // Increment if the key is missing.
+            if split.var.is_none_or(|var| var.dict_get(key).is_none()) {
+                retval += 1;
+            }
+        } else if split.indexes.is_empty() {
             // No indexes, just increment if our variable is missing.
             if split.var.is_none() {
                 retval += 1;
@@ -655,6 +703,10 @@ fn show_scope(var_name: &wstr, scope: EnvMode, streams: &mut IoStreams, vars: &d
         let mut pathvarv = L!(" ").to_owned();
         pathvarv.push_utfstr(wgettext!("a path variable"));
         pathvarv
+    } else if var.is_dict() {
+        let mut pathvarv = L!(" ").to_owned();
+        pathvarv.push_utfstr(wgettext!("a dictionary"));
+        pathvarv
     } else {
         L!("").to_owned()
     };
@@ -694,13 +746,26 @@ fn show_scope(var_name: &wstr, scope: EnvMode, streams: &mut IoStreams, vars: &d
             }
         }
         let value = &vals[i];
-        let escaped_val = escape_string(
-            value,
-            EscapeStringStyle::Script(EscapeFlags::NO_PRINTABLES | EscapeFlags::NO_QUOTED),
-        );
-        streams
-            .out
-            .append(&sprintf!("$%s[%d]: |%s|\n", var_name, i + 1, &escaped_val));
+        let escape_value = |s: &wstr| {
+            escape_string(
+                s,
+                EscapeStringStyle::Script(EscapeFlags::NO_PRINTABLES | EscapeFlags::NO_QUOTED),
+            )
+        };
+        let escaped_val = escape_value(value);
+        if var.is_dict() {
+            let escaped_key = escape_value(&var.dict_keys()[i]);
+            streams.out.append(&sprintf!(
+                "$%s[%s]: |%s|\n",
+                var_name,
+                &escaped_key,
+                &escaped_val
+            ));
+        } else {
+            streams
+                .out
+                .append(&sprintf!("$%s[%d]: |%s|\n", var_name, i + 1, &escaped_val));
+        }
     }
 }
 
@@ -790,7 +855,25 @@ fn erase(
                 return Err(STATUS_INVALID_ARGS);
             }
             let retval;
-            if split.indexes.is_empty() {
+            if let Some(key) = split.key {
+                // remove just the specified key of the dictionary
+                let Some(var) = split.var else {
+                    return Err(STATUS_CMD_ERROR);
+                };
+                let mut pairs = var.dict_pairs();
+                if let Some(pos) = var.dict_position(key) {
+                    pairs.drain(2 * pos..2 * pos + 2);
+                }
+                retval = env_set_reporting_errors(
+                    cmd,
+                    opts,
+                    split.varname,
+                    mode | EnvMode::DICT,
+                    pairs,
+                    streams,
+                    parser,
+                );
+            } else if split.indexes.is_empty() {
                 // unset the var
                 retval = parser.remove_var(split.varname, ParserEnvSetMode::new(mode));
                 // When a non-existent-variable is unset, return NotFound as $status
@@ -1011,6 +1094,10 @@ fn set_internal(
         }
     }
 
+    if split.key.is_some() || opts.dict {
+        return set_dict(cmd, opts, parser, streams, &split, argv);
+    }
+
     let new_values = if split.indexes.is_empty() {
         // Handle the simple, common, case. Set the var to the specified values.
         new_var_values(split.varname, opts, argv, parser.vars())
@@ -1030,6 +1117,93 @@ fn set_internal(
     retval.into()
 }
 
+/// Set a dictionary, or a key of one.
+fn set_dict(
+    cmd: &wstr,
+    opts: &Options,
+    parser: &Parser,
+    streams: &mut IoStreams,
+    split: &SplitVar,
+    argv: &[&wstr],
+) -> BuiltinResult {
+    if opts.prepend || (opts.append && split.key.is_some()) {
+        streams.err.appendln(&wgettext_fmt!(
+            "%s: Cannot use --prepend with dictionaries, or --append when assigning to a key",
+            cmd
+        ));
+        builtin_print_error_trailer(parser, streams.err, cmd);
+        return Err(STATUS_INVALID_ARGS);
+    }
+
+    // Inherit any existing keys if we are assigning to one key, or appending.
+    let mut pairs = vec![];
+    if split.key.is_some() || opts.append {
+        // Like for lists, appending uses the variable in the default scope.
+        let existing = if opts.append {
+            parser.vars().get(split.varname)
+        } else {
+            split.var.clone()
+        };
+        if let Some(existing) = existing.filter(|var| var.is_dict()) {
+            pairs = existing.dict_pairs();
+        }
+    }
+
+    if let Some(key) = split.key {
+        if argv.len() != 1 {
+            streams.err.appendln(&wgettext_fmt!(
+                "%s: given %d indexes but %d values",
+                cmd,
+                1,
+                argv.len()
+            ));
+            return Err(STATUS_INVALID_ARGS);
+        }
+        pairs.push(key.to_owned());
+        pairs.push(argv[0].to_owned());
+    } else {
+        if argv.len() % 2 != 0 {
+            streams.err.appendln(&wgettext_fmt!(
+                "%s: expected a value for key '%s'",
+                cmd,
+                argv[argv.len() - 1]
+            ));
+            builtin_print_error_trailer(parser, streams.err, cmd);
+            return Err(STATUS_INVALID_ARGS);
+        }
+        pairs.extend(argv.iter().copied().map(|s| s.to_owned()));
+    }
+
+    let mode = opts.env_mode() | EnvMode::DICT;
+    let retval = env_set_reporting_errors(cmd, opts, split.varname, mode, pairs, streams, parser);
+    if retval == EnvStackSetResult::Ok {
+        warn_if_uvar_shadows_global(cmd, opts, split.varname, streams, parser);
+    }
+    retval.into()
+}
+
+/// Keys mode. Print the keys of the given dictionaries.
+fn keys(cmd: &wstr, parser: &Parser, streams: &mut IoStreams, args: &[&wstr]) -> BuiltinResult {
+    let mut retval = 0;
+    for arg in args.iter().copied() {
+        if !valid_var_name(arg) {
+            streams.err.append(&varname_error(cmd, arg));
+            builtin_print_error_trailer(parser, streams.err, cmd);
+            return Err(STATUS_INVALID_ARGS);
+        }
+        match parser.vars().get(arg) {
+            Some(var) if var.is_dict() => {
+                for key in var.dict_keys() {
+                    streams.out.appendln(key);
+                }
+            }
+            // Count the variables that are not dictionaries.
+            _ => retval += 1,
+        }
+    }
+    BuiltinResult::from_dynamic(retval)
+}
+
 /// The set builtin creates, updates, and erases (removes, deletes) variables.
 pub fn set(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) -> BuiltinResult {
     let cmd = args[0];
@@ -1048,6 +1222,8 @@ pub fn set(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) -> Buil
         list(&opts, parser, streams)
     } else if opts.show {
         show(cmd, parser, streams, args)
+    } else if opts.keys {
+        keys(cmd, parser, streams, args)
     } else if args.is_empty() {
         list(&opts, parser, streams)
     } else {
@@ -1,6 +1,7 @@
 use super::prelude::*;
 use crate::common::{Named, bytes2wcstring, escape, get_by_sorted_name, str2wcstring};
 use crate::fds::BorrowedFdFile;
+use crate::future_feature_flags::{FeatureFlag, feature_test};
 use crate::io::OutputStream;
 use crate::parse_constants::UNKNOWN_BUILTIN_ERR_MSG;
 use crate::parse_util::argument_is_help;
@@ -11,6 +12,7 @@
 use fish_common::assert_sorted_by_name;
 use fish_widestring::L;
 use std::io::{BufRead as _, BufReader, Read as _};
+use std::time::Duration;
 
 pub type BuiltinCmd = fn(&Parser, &mut IoStreams, &mut [&wstr]) -> BuiltinResult;
 
@@ -185,6 +187,8 @@ fn builtin_status_code(&self) -> c_int {
 pub const STATUS_READ_TOO_MUCH: c_int = 122;
 /// The status code when an expansion fails, for example, "$foo["
 pub const STATUS_EXPAND_ERROR: c_int = 121;
+/// The status code when the `timeout` builtin stopped a command, like GNU timeout.
+pub const STATUS_TIMED_OUT: c_int = 124;
 
 pub const STATUS_NO_VARIABLES_GIVEN: c_int = 255;
 
@@ -194,8 +198,15 @@ struct BuiltinData {
     name: &'static wstr,
     // Function pointer to the builtin implementation.
     func: BuiltinCmd,
+    // The options of the builtin, for checking scripts without running them. This is only set
+    // for builtins which parse all their arguments with a fixed set of options, and which are not
+    // usually wrapped by a function of the same name.
+    options: Option<BuiltinOptions>,
 }
 
+/// The short and long options a builtin passes to [`WGetopter`].
+pub type BuiltinOptions = (&'static wstr, &'static [WOption<'static>]);
+
 // Data about all the builtin commands in fish.
 // Functions that are bound to builtin_generic are handled directly by the parser.
 // NOTE: These must be kept in sorted order!
@@ -203,258 +214,342 @@ struct BuiltinData {
     BuiltinData {
         name: L!("!"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("."),
         func: source::source,
+        options: None,
     },
     BuiltinData {
         name: L!(":"),
         func: r#true::r#true,
+        options: None,
     },
     BuiltinData {
         name: L!("["), // ]
         func: test::test,
+        options: None,
     },
     BuiltinData {
         name: L!("_"),
         func: gettext::gettext,
+        options: None,
     },
     BuiltinData {
         name: L!("abbr"),
         func: abbr::abbr,
+        options: None,
     },
     BuiltinData {
         name: L!("and"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("argparse"),
         func: argparse::argparse,
+        options: Some((argparse::SHORT_OPTIONS, argparse::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("begin"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("bg"),
         func: bg::bg,
+        options: None,
     },
     BuiltinData {
         name: L!("bind"),
         func: bind::bind,
+        options: Some((bind::SHORT_OPTIONS, bind::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("block"),
         func: block::block,
+        options: Some((block::SHORT_OPTS, block::LONG_OPTS)),
     },
     BuiltinData {
         name: L!("break"),
         func: r#break::r#break,
+        options: None,
     },
     BuiltinData {
         name: L!("breakpoint"),
         func: breakpoint::breakpoint,
+        options: None,
     },
     BuiltinData {
         name: L!("builtin"),
         func: builtin::builtin,
+        options: Some((builtin::SHORT_OPTIONS, builtin::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("case"),
         func: builtin_generic,
+        options: None,
+    },
+    BuiltinData {
+        name: L!("catch"),
+        func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("cd"),
         func: cd::cd,
+        options: None,
     },
     BuiltinData {
         name: L!("command"),
         func: command::command,
+        options: Some((command::SHORT_OPTIONS, command::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("commandline"),
         func: commandline::commandline,
+        options: Some((commandline::SHORT_OPTIONS, commandline::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("complete"),
         func: complete::complete,
+        options: Some((complete::SHORT_OPTIONS, complete::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("contains"),
         func: contains::contains,
+        options: Some((contains::SHORT_OPTS, contains::LONG_OPTS)),
     },
     BuiltinData {
         name: L!("continue"),
         func: r#continue::r#continue,
+        options: None,
     },
     BuiltinData {
         name: L!("count"),
         func: count::count,
+        options: None,
+    },
+    BuiltinData {
+        name: L!("defer"),
+        func: defer::defer,
+        options: None,
     },
     BuiltinData {
         name: L!("disown"),
         func: disown::disown,
+        options: None,
     },
     BuiltinData {
         name: L!("echo"),
         func: echo::echo,
+        options: None,
     },
     BuiltinData {
         name: L!("else"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("emit"),
         func: emit::emit,
+        options: None,
     },
     BuiltinData {
         name: L!("end"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("eval"),
         func: eval::eval,
+        options: None,
     },
     BuiltinData {
         name: L!("exec"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("exit"),
         func: exit::exit,
+        options: None,
     },
     BuiltinData {
         name: L!("false"),
         func: r#false::r#false,
+        options: None,
     },
     BuiltinData {
         name: L!("fg"),
         func: fg::fg,
+        options: None,
     },
     BuiltinData {
         name: L!("fish_indent"),
         func: fish_indent::fish_indent,
+        options: None,
     },
     BuiltinData {
         name: L!("fish_key_reader"),
         func: fish_key_reader::fish_key_reader,
+        options: None,
     },
     BuiltinData {
         name: L!("for"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("function"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("functions"),
         func: functions::functions,
+        options: Some((functions::SHORT_OPTIONS, functions::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("history"),
         func: history::history,
+        options: None,
     },
     BuiltinData {
         name: L!("if"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("jobs"),
         func: jobs::jobs,
+        options: Some((jobs::SHORT_OPTIONS, jobs::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("math"),
         func: math::math,
+        options: None,
     },
     BuiltinData {
         name: L!("not"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("or"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("path"),
         func: path::path,
+        options: None,
     },
     BuiltinData {
         name: L!("printf"),
         func: printf::printf,
+        options: None,
     },
     BuiltinData {
         name: L!("pwd"),
         func: pwd::pwd,
+        options: Some((pwd::SHORT_OPTIONS, pwd::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("random"),
         func: random::random,
+        options: None,
     },
     BuiltinData {
         name: L!("read"),
         func: read::read,
+        options: Some((read::SHORT_OPTIONS, read::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("realpath"),
         func: realpath::realpath,
+        options: None,
     },
     BuiltinData {
         name: L!("return"),
         func: r#return::r#return,
+        options: None,
     },
     BuiltinData {
         name: L!("set"),
         func: set::set,
+        options: Some((set::SHORT_OPTS, set::LONG_OPTS)),
     },
     BuiltinData {
         name: L!("set_color"),
         func: set_color::set_color,
+        options: None,
     },
     BuiltinData {
         name: L!("source"),
         func: source::source,
+        options: None,
     },
     BuiltinData {
         name: L!("status"),
         func: status::status,
+        options: Some((status::SHORT_OPTIONS, status::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("string"),
         func: string::string,
+        options: None,
     },
     BuiltinData {
         name: L!("switch"),
         func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("test"),
         func: test::test,
+        options: None,
     },
     BuiltinData {
         name: L!("time"),
         func: builtin_generic,
+        options: None,
+    },
+    BuiltinData {
+        name: L!("timeout"),
+        func: timeout::timeout,
+        options: Some((timeout::SHORT_OPTS, timeout::LONG_OPTS)),
     },
     BuiltinData {
         name: L!("true"),
         func: r#true::r#true,
+        options: None,
+    },
+    BuiltinData {
+        name: L!("try"),
+        func: builtin_generic,
+        options: None,
     },
     BuiltinData {
         name: L!("type"),
         func: r#type::r#type,
+        options: Some((r#type::SHORT_OPTIONS, r#type::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("ulimit"),
         func: ulimit::ulimit,
+        options: Some((ulimit::SHORT_OPTS, ulimit::LONG_OPTS)),
     },
     BuiltinData {
         name: L!("wait"),
         func: wait::wait,
+        options: Some((wait::SHORT_OPTIONS, wait::LONG_OPTIONS)),
     },
     BuiltinData {
         name: L!("while"),
         func: builtin_generic,
+        options: None,
     },
 ];
 assert_sorted_by_name!(BUILTIN_DATAS);
@@ -466,7 +561,13 @@ fn name(&self) -> &'static wstr {
 }
 
 fn builtin_lookup(name: &wstr) -> Option<&'static BuiltinData> {
-    get_by_sorted_name(name, BUILTIN_DATAS)
+    get_by_sorted_name(name, BUILTIN_DATAS).filter(|builtin| builtin_is_enabled(builtin))
+}
+
+/// `try` and `catch` are only builtins if they are keywords, so they don't shadow commands with
+/// those names otherwise.
+fn builtin_is_enabled(builtin: &BuiltinData) -> bool {
+    !(builtin.name == "try" || builtin.name == "catch") || feature_test(FeatureFlag::TryCatch)
 }
 
 /// Is there a builtin command with the given name?
@@ -474,6 +575,12 @@ pub fn builtin_exists(name: &wstr) -> bool {
     builtin_lookup(name).is_some()
 }
 
+/// Return the options of the builtin with the given name, if they can be checked without running
+/// it.
+pub fn builtin_options(name: &wstr) -> Option<BuiltinOptions> {
+    builtin_lookup(name).and_then(|builtin| builtin.options)
+}
+
 /// Is the command a keyword we need to special-case the handling of `-h` and `--help`.
 fn cmd_needs_help(cmd: &wstr) -> bool {
     [
@@ -562,7 +669,10 @@ pub fn builtin_run(parser: &Parser, argv: &mut [&wstr], streams: &mut IoStreams)
 
 /// Returns a list of all builtin names.
 pub fn builtin_get_names() -> impl Iterator<Item = &'static wstr> {
-    BUILTIN_DATAS.iter().map(|builtin| builtin.name)
+    BUILTIN_DATAS
+        .iter()
+        .filter(|builtin| builtin_is_enabled(builtin))
+        .map(|builtin| builtin.name)
 }
 
 /// Return a one-line description of the specified builtin.
@@ -584,6 +694,7 @@ pub fn builtin_get_desc(name: &wstr) -> Option<&'static wstr> {
         _ if name == "breakpoint" => wgettext!("Halt execution and start debug prompt"),
         _ if name == "builtin" => wgettext!("Run a builtin specifically"),
         _ if name == "case" => wgettext!("Block of code to run conditionally"),
+        _ if name == "catch" => wgettext!("Handle a failure in a try block"),
         _ if name == "cd" => wgettext!("Change working directory"),
         _ if name == "command" => wgettext!("Run a command specifically"),
         _ if name == "commandline" => wgettext!("Set or get the commandline"),
@@ -591,6 +702,7 @@ pub fn builtin_get_desc(name: &wstr) -> Option<&'static wstr> {
         _ if name == "contains" => wgettext!("Search for a specified string in a list"),
         _ if name == "continue" => wgettext!("Skip over remaining innermost loop"),
         _ if name == "count" => wgettext!("Count the number of arguments"),
+        _ if name == "defer" => wgettext!("Run a command when the current function or block exits"),
         _ if name == "disown" => wgettext!("Remove job from job list"),
         _ if name == "echo" => wgettext!("Print arguments"),
         _ if name == "else" => wgettext!("Evaluate block if condition is false"),
@@ -626,7 +738,9 @@ pub fn builtin_get_desc(name: &wstr) -> Option<&'static wstr> {
         _ if name == "switch" => wgettext!("Conditionally run blocks of code"),
         _ if name == "test" => wgettext!("Test a condition"),
         _ if name == "time" => wgettext!("Measure how long a command or block takes"),
+        _ if name == "timeout" => wgettext!("Run a command with a time limit"),
         _ if name == "true" => wgettext!("Return a successful result"),
+        _ if name == "try" => wgettext!("Run a block of code until a command fails"),
         _ if name == "type" => wgettext!("Check if a thing is a thing"),
         _ if name == "ulimit" => wgettext!("Get/set resource usage limits"),
         _ if name == "wait" => wgettext!("Await background process completion"),
@@ -1001,6 +1115,20 @@ fn parsed_pid(
     }
 }
 
+/// Like [`wutil::parse_duration`], but reports an invalid duration on stderr.
+pub fn parse_duration_arg(
+    streams: &mut IoStreams,
+    cmd: &wstr,
+    arg: &wstr,
+) -> Result<Duration, ErrorCode> {
+    wutil::parse_duration(arg).ok_or_else(|| {
+        streams
+            .err
+            .appendln(&wgettext_fmt!("%s: '%s' is not a valid duration", cmd, arg));
+        STATUS_INVALID_ARGS
+    })
+}
+
 /// A generic builtin that only supports showing a help message. This is only a placeholder that
 /// prints the help message. Useful for commands that live in the parser.
 fn builtin_generic(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) -> BuiltinResult {
@@ -57,6 +57,7 @@ fn to_wstr(self) -> &'static wstr {
     (STATUS_CURRENT_CMD, "current-command"),
     (STATUS_CURRENT_COMMANDLINE, "current-commandline"),
     (STATUS_DIRNAME, "dirname", "current-dirname"),
+    (STATUS_FAILED_COMMANDLINE, "failed-commandline"),
     (STATUS_FEATURES, "features"),
     (STATUS_FILENAME, "filename", "current-filename"),
     (STATUS_FISH_PATH, "fish-path"),
@@ -134,8 +135,8 @@ fn default() -> Self {
 const IS_NO_JOB_CTRL_SHORT: char = '\x04';
 const IS_INTERACTIVE_READ_SHORT: char = '\x05';
 
-const SHORT_OPTIONS: &wstr = L!("L:cbilfnhj:t");
-const LONG_OPTIONS: &[WOption] = &[
+pub(crate) const SHORT_OPTIONS: &wstr = L!("L:cbilfnhj:t");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
     wopt(L!("help"), NoArgument, 'h'),
     wopt(L!("current-filename"), NoArgument, 'f'),
     wopt(L!("current-line-number"), NoArgument, 'n'),
@@ -734,6 +735,12 @@ pub fn status(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) -> B
                     let commandline = &parser.libdata().status_vars.commandline;
                     streams.out.appendln(commandline);
                 }
+                STATUS_FAILED_COMMANDLINE => {
+                    let Some(commandline) = parser.failed_commandline() else {
+                        return Err(STATUS_CMD_ERROR);
+                    };
+                    streams.out.appendln(&commandline);
+                }
                 STATUS_FISH_PATH => {
                     use crate::env::config_paths::FishPath::*;
                     let result = match get_fish_path() {
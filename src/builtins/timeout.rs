@@ -0,0 +1,195 @@
+//! Implementation of the timeout builtin.
+
+use super::eval::eval_in_job_group;
+use super::prelude::*;
+use crate::common::escape;
+use crate::job_group::{JobGroup, TimeoutStage};
+use crate::signal::Signal;
+use crate::threads;
+use nix::sys::signal::Signal as NixSignal;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+struct Options {
+    print_help: bool,
+    signal: NixSignal,
+    kill_after: Option<Duration>,
+    preserve_status: bool,
+    verbose: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            print_help: false,
+            signal: NixSignal::SIGTERM,
+            kill_after: None,
+            preserve_status: false,
+            verbose: false,
+        }
+    }
+}
+
+pub(crate) const SHORT_OPTS: &wstr = L!("+hk:s:v");
+pub(crate) const LONG_OPTS: &[WOption] = &[
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+    wopt(L!("foreground"), ArgType::NoArgument, 'f'),
+    wopt(L!("kill-after"), ArgType::RequiredArgument, 'k'),
+    wopt(L!("signal"), ArgType::RequiredArgument, 's'),
+    wopt(L!("preserve-status"), ArgType::NoArgument, 'p'),
+    wopt(L!("verbose"), ArgType::NoArgument, 'v'),
+];
+
+fn parse_options(
+    args: &mut [&wstr],
+    parser: &Parser,
+    streams: &mut IoStreams,
+) -> Result<(Options, usize), ErrorCode> {
+    let cmd = args[0];
+
+    let mut opts = Options::default();
+
+    let mut w = WGetopter::new(SHORT_OPTS, LONG_OPTS, args);
+    while let Some(c) = w.next_opt() {
+        match c {
+            'h' => opts.print_help = true,
+            // Commands always run in the foreground, so this is only accepted for compatibility
+            // with GNU timeout.
+            'f' => (),
+            'k' => opts.kill_after = Some(parse_duration_arg(streams, cmd, w.woptarg.unwrap())?),
+            's' => {
+                let arg = w.woptarg.unwrap();
+                let signal =
+                    Signal::parse(arg).and_then(|sig| NixSignal::try_from(sig.code()).ok());
+                let Some(signal) = signal else {
+                    streams
+                        .err
+                        .appendln(&wgettext_fmt!("%s: Unknown signal '%s'", cmd, arg));
+                    return Err(STATUS_INVALID_ARGS);
+                };
+                opts.signal = signal;
+            }
+            'p' => opts.preserve_status = true,
+            'v' => opts.verbose = true,
+            ':' => {
+                builtin_missing_argument(parser, streams, cmd, args[w.wopt_index - 1], false);
+                return Err(STATUS_INVALID_ARGS);
+            }
+            ';' => {
+                builtin_unexpected_argument(parser, streams, cmd, args[w.wopt_index - 1], false);
+                return Err(STATUS_INVALID_ARGS);
+            }
+            '?' => {
+                builtin_unknown_option(parser, streams, cmd, args[w.wopt_index - 1], false);
+                return Err(STATUS_INVALID_ARGS);
+            }
+            _ => {
+                panic!("unexpected retval from WGetopter");
+            }
+        }
+    }
+
+    Ok((opts, w.wopt_index))
+}
+
+/// Implementation of the builtin timeout command, which runs a command and stops it if it does not
+/// finish in time.
+pub fn timeout(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) -> BuiltinResult {
+    let cmd = args[0];
+
+    let (opts, optind) = parse_options(args, parser, streams)?;
+
+    if opts.print_help {
+        builtin_print_help(parser, streams, cmd);
+        return Ok(SUCCESS);
+    }
+
+    let Some(&duration) = args.get(optind) else {
+        streams
+            .err
+            .appendln(&wgettext_fmt!(BUILTIN_ERR_ARG_COUNT0, cmd));
+        builtin_print_error_trailer(parser, streams.err, cmd);
+        return Err(STATUS_INVALID_ARGS);
+    };
+    let duration = parse_duration_arg(streams, cmd, duration)?;
+
+    let command_args = &args[optind + 1..];
+    if command_args.is_empty() {
+        streams
+            .err
+            .appendln(&wgettext_fmt!("%s: expected a command", cmd));
+        builtin_print_error_trailer(parser, streams.err, cmd);
+        return Err(STATUS_INVALID_ARGS);
+    }
+
+    // The arguments have already been expanded, so escape them to run them as they are.
+    let mut command = WString::new();
+    for (i, arg) in command_args.iter().enumerate() {
+        if i > 0 {
+            command.push(' ');
+        }
+        command.push_utfstr(&escape(arg));
+    }
+
+    // A duration of zero means no time limit.
+    if duration.is_zero() {
+        let job_group = streams.job_group.clone();
+        return eval_in_job_group(parser, streams, &command, job_group.as_ref());
+    }
+
+    // Run the command in a group of its own, so its jobs can be told apart from others.
+    let group =
+        JobGroup::create_with_timeout(command.clone(), opts.signal, streams.job_group.clone());
+
+    // The timer thread advances the timeout, unless the command finishes first, which drops the
+    // sender.
+    let (finished, timer) = mpsc::channel::<()>();
+    let timer_group = group.clone();
+    let kill_after = opts.kill_after;
+    let spawned = threads::spawn(move || {
+        if timer.recv_timeout(duration) != Err(RecvTimeoutError::Timeout) {
+            return;
+        }
+        timer_group.advance_timeout(TimeoutStage::Expired);
+        let Some(kill_after) = kill_after else {
+            return;
+        };
+        if timer.recv_timeout(kill_after) == Err(RecvTimeoutError::Timeout) {
+            timer_group.advance_timeout(TimeoutStage::Killing);
+        }
+    });
+    if !spawned {
+        return Err(STATUS_CMD_ERROR);
+    }
+
+    let result = eval_in_job_group(parser, streams, &command, Some(&group));
+    drop(finished);
+
+    let stage = match group.timeout_stage() {
+        Some((stage, _)) if stage != TimeoutStage::Running => stage,
+        _ => return result,
+    };
+    if opts.verbose {
+        let mut signals = vec![opts.signal];
+        if stage == TimeoutStage::Killing {
+            signals.push(NixSignal::SIGKILL);
+        }
+        for signal in signals {
+            // Like GNU timeout, name the signal without its "SIG" prefix.
+            streams.err.appendln(&wgettext_fmt!(
+                "%s: sending signal %s to command '%s'",
+                cmd,
+                &signal.as_str()[3..],
+                escape(command_args[0])
+            ));
+        }
+    }
+    if opts.preserve_status {
+        result
+    } else if stage == TimeoutStage::Killing {
+        // Like GNU timeout, report that the command was killed.
+        Err(128 + NixSignal::SIGKILL as i32)
+    } else {
+        Err(STATUS_TIMED_OUT)
+    }
+}
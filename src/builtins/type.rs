@@ -17,27 +17,27 @@ struct type_cmd_opts_t {
     color: ColorEnabled,
 }
 
+pub(crate) const SHORT_OPTIONS: &wstr = L!("hasftpPq");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+    wopt(L!("all"), ArgType::NoArgument, 'a'),
+    wopt(L!("short"), ArgType::NoArgument, 's'),
+    wopt(L!("no-functions"), ArgType::NoArgument, 'f'),
+    wopt(L!("type"), ArgType::NoArgument, 't'),
+    wopt(L!("path"), ArgType::NoArgument, 'p'),
+    wopt(L!("force-path"), ArgType::NoArgument, 'P'),
+    wopt(L!("query"), ArgType::NoArgument, 'q'),
+    wopt(L!("quiet"), ArgType::NoArgument, 'q'),
+    wopt(L!("color"), ArgType::RequiredArgument, COLOR_OPTION_CHAR),
+];
+
 pub fn r#type(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) -> BuiltinResult {
     let cmd = argv[0];
     let argc = argv.len();
     let print_hints = false;
     let mut opts: type_cmd_opts_t = Default::default();
 
-    let shortopts: &wstr = L!("hasftpPq");
-    let longopts: &[WOption] = &[
-        wopt(L!("help"), ArgType::NoArgument, 'h'),
-        wopt(L!("all"), ArgType::NoArgument, 'a'),
-        wopt(L!("short"), ArgType::NoArgument, 's'),
-        wopt(L!("no-functions"), ArgType::NoArgument, 'f'),
-        wopt(L!("type"), ArgType::NoArgument, 't'),
-        wopt(L!("path"), ArgType::NoArgument, 'p'),
-        wopt(L!("force-path"), ArgType::NoArgument, 'P'),
-        wopt(L!("query"), ArgType::NoArgument, 'q'),
-        wopt(L!("quiet"), ArgType::NoArgument, 'q'),
-        wopt(L!("color"), ArgType::RequiredArgument, COLOR_OPTION_CHAR),
-    ];
-
-    let mut w = WGetopter::new(shortopts, longopts, argv);
+    let mut w = WGetopter::new(SHORT_OPTIONS, LONG_OPTIONS, argv);
     while let Some(c) = w.next_opt() {
         match c {
             'a' => opts.all = true,
@@ -246,38 +246,37 @@ fn default() -> Self {
     }
 }
 
+pub(crate) const SHORT_OPTS: &wstr = L!("HSabcdefilmnqrstuvwyKPTh");
+pub(crate) const LONG_OPTS: &[WOption] = &[
+    wopt(L!("all"), ArgType::NoArgument, 'a'),
+    wopt(L!("hard"), ArgType::NoArgument, 'H'),
+    wopt(L!("soft"), ArgType::NoArgument, 'S'),
+    wopt(L!("socket-buffers"), ArgType::NoArgument, 'b'),
+    wopt(L!("core-size"), ArgType::NoArgument, 'c'),
+    wopt(L!("data-size"), ArgType::NoArgument, 'd'),
+    wopt(L!("nice"), ArgType::NoArgument, 'e'),
+    wopt(L!("file-size"), ArgType::NoArgument, 'f'),
+    wopt(L!("pending-signals"), ArgType::NoArgument, 'i'),
+    wopt(L!("lock-size"), ArgType::NoArgument, 'l'),
+    wopt(L!("resident-set-size"), ArgType::NoArgument, 'm'),
+    wopt(L!("file-descriptor-count"), ArgType::NoArgument, 'n'),
+    wopt(L!("queue-size"), ArgType::NoArgument, 'q'),
+    wopt(L!("realtime-priority"), ArgType::NoArgument, 'r'),
+    wopt(L!("stack-size"), ArgType::NoArgument, 's'),
+    wopt(L!("cpu-time"), ArgType::NoArgument, 't'),
+    wopt(L!("process-count"), ArgType::NoArgument, 'u'),
+    wopt(L!("virtual-memory-size"), ArgType::NoArgument, 'v'),
+    wopt(L!("swap-size"), ArgType::NoArgument, 'w'),
+    wopt(L!("realtime-maxtime"), ArgType::NoArgument, 'y'),
+    wopt(L!("kernel-queues"), ArgType::NoArgument, 'K'),
+    wopt(L!("ptys"), ArgType::NoArgument, 'P'),
+    wopt(L!("threads"), ArgType::NoArgument, 'T'),
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+];
+
 pub fn ulimit(parser: &Parser, streams: &mut IoStreams, args: &mut [&wstr]) -> BuiltinResult {
     let cmd = args[0];
 
-    const SHORT_OPTS: &wstr = L!("HSabcdefilmnqrstuvwyKPTh");
-
-    const LONG_OPTS: &[WOption] = &[
-        wopt(L!("all"), ArgType::NoArgument, 'a'),
-        wopt(L!("hard"), ArgType::NoArgument, 'H'),
-        wopt(L!("soft"), ArgType::NoArgument, 'S'),
-        wopt(L!("socket-buffers"), ArgType::NoArgument, 'b'),
-        wopt(L!("core-size"), ArgType::NoArgument, 'c'),
-        wopt(L!("data-size"), ArgType::NoArgument, 'd'),
-        wopt(L!("nice"), ArgType::NoArgument, 'e'),
-        wopt(L!("file-size"), ArgType::NoArgument, 'f'),
-        wopt(L!("pending-signals"), ArgType::NoArgument, 'i'),
-        wopt(L!("lock-size"), ArgType::NoArgument, 'l'),
-        wopt(L!("resident-set-size"), ArgType::NoArgument, 'm'),
-        wopt(L!("file-descriptor-count"), ArgType::NoArgument, 'n'),
-        wopt(L!("queue-size"), ArgType::NoArgument, 'q'),
-        wopt(L!("realtime-priority"), ArgType::NoArgument, 'r'),
-        wopt(L!("stack-size"), ArgType::NoArgument, 's'),
-        wopt(L!("cpu-time"), ArgType::NoArgument, 't'),
-        wopt(L!("process-count"), ArgType::NoArgument, 'u'),
-        wopt(L!("virtual-memory-size"), ArgType::NoArgument, 'v'),
-        wopt(L!("swap-size"), ArgType::NoArgument, 'w'),
-        wopt(L!("realtime-maxtime"), ArgType::NoArgument, 'y'),
-        wopt(L!("kernel-queues"), ArgType::NoArgument, 'K'),
-        wopt(L!("ptys"), ArgType::NoArgument, 'P'),
-        wopt(L!("threads"), ArgType::NoArgument, 'T'),
-        wopt(L!("help"), ArgType::NoArgument, 'h'),
-    ];
-
     let mut opts = Options::default();
 
     let mut w = WGetopter::new(SHORT_OPTS, LONG_OPTS, args);
@@ -133,6 +133,12 @@ fn wait_for_completion(parser: &Parser, whs: &[WaitHandleRef], any_flag: bool) -
     }
 }
 
+pub(crate) const SHORT_OPTIONS: &wstr = L!("nh");
+pub(crate) const LONG_OPTIONS: &[WOption] = &[
+    wopt(L!("any"), ArgType::NoArgument, 'n'),
+    wopt(L!("help"), ArgType::NoArgument, 'h'),
+];
+
 pub fn wait(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) -> BuiltinResult {
     let cmd = argv[0];
     let argc = argv.len();
@@ -140,13 +146,7 @@ pub fn wait(parser: &Parser, streams: &mut IoStreams, argv: &mut [&wstr]) -> Bui
     let mut print_help = false;
     let print_hints = false;
 
-    let shortopts: &wstr = L!("nh");
-    let longopts: &[WOption] = &[
-        wopt(L!("any"), ArgType::NoArgument, 'n'),
-        wopt(L!("help"), ArgType::NoArgument, 'h'),
-    ];
-
-    let mut w = WGetopter::new(shortopts, longopts, argv);
+    let mut w = WGetopter::new(SHORT_OPTIONS, LONG_OPTIONS, argv);
     while let Some(c) = w.next_opt() {
         match c {
             'n' => {
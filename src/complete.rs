@@ -1,5 +1,5 @@
 use std::{
-    cmp::Ordering,
+    cmp::{Ordering, Reverse},
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     mem,
     ops::{Deref, DerefMut},
@@ -13,7 +13,7 @@
 use crate::{
     abbrs::with_abbrs,
     autoload::Autoload,
-    builtins::shared::{builtin_exists, builtin_get_desc, builtin_get_names},
+    builtins::shared::{STATUS_TIMED_OUT, builtin_exists, builtin_get_desc, builtin_get_names},
     common::{
         ScopeGuard, UnescapeFlags, UnescapeStringStyle, escape, unescape_string,
         valid_var_name_char,
@@ -26,14 +26,18 @@
     },
     flog::{flog, flogf},
     function,
+    help_completions::parse_help,
     history::{History, history_session_id},
     operation_context::OperationContext,
     parse_constants::SourceRange,
-    parse_util::{get_cmdsubst_extent, get_process_extent, unescape_wildcards},
+    parse_util::{
+        get_cmdsubst_extent, get_process_extent, get_token_extent, has_cmdsub, unescape_wildcards,
+    },
     parser::{Block, Parser, ParserEnvSetMode},
     parser_keywords::parser_keywords_is_subcommand,
     path::{path_get_path, path_try_get_path},
     prelude::*,
+    signal::signal_check_cancel,
     tokenizer::{Tok, TokFlags, TokenType, Tokenizer, variable_assignment_equals_pos},
     wildcard::{wildcard_complete, wildcard_has, wildcard_match},
     wutil::wrealpath,
@@ -407,6 +411,8 @@ struct CompletionEntry {
     /// Order for when this completion was created. This aids in outputting completions sorted by
     /// time.
     order: usize,
+    /// How long the expanded arguments of the options are cached, if at all.
+    cache_ttl: Option<Duration>,
 }
 
 impl CompletionEntry {
@@ -414,6 +420,7 @@ pub fn new() -> Self {
         Self {
             options: vec![],
             order: COMPLETE_ORDER.fetch_add(1, atomic::Ordering::Relaxed),
+            cache_ttl: None,
         }
     }
 
@@ -433,7 +440,7 @@ pub fn add_option(&mut self, opt: CompleteEntryOpt) {
     pub fn remove_option(&mut self, option: &wstr, typ: CompleteOptionType) -> bool {
         self.options
             .retain(|opt| opt.option != option || opt.typ != typ);
-        self.options.is_empty()
+        self.options.is_empty() && self.cache_ttl.is_none()
     }
 }
 
@@ -447,6 +454,59 @@ struct CompletionEntryIndex {
 static COMPLETION_MAP: Mutex<CompletionEntryMap> = Mutex::new(BTreeMap::new());
 static COMPLETION_TOMBSTONES: Mutex<BTreeSet<WString>> = Mutex::new(BTreeSet::new());
 
+/// Key of the argument cache: the arguments, and what their command substitutions see, which is
+/// the command line up to the token being completed and the working directory.
+#[derive(PartialEq, Eq, Hash)]
+struct ArgumentCacheKey {
+    args: WString,
+    commandline_prefix: WString,
+    cwd: WString,
+}
+
+struct CachedArguments {
+    completions: Vec<Completion>,
+    /// When the entry expires, or None if it never does.
+    expires: Option<Instant>,
+}
+
+/// Expanded arguments of commands with a cache TTL, see `complete --cache-ttl`.
+static ARGUMENT_CACHE: LazyLock<Mutex<HashMap<ArgumentCacheKey, CachedArguments>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Return the cached expansion of arguments, unless it has expired.
+fn argument_cache_get(key: &ArgumentCacheKey) -> Option<Vec<Completion>> {
+    let now = Instant::now();
+    let cache = ARGUMENT_CACHE.lock().unwrap();
+    let cached = cache.get(key)?;
+    cached
+        .expires
+        .is_none_or(|expires| expires > now)
+        .then(|| cached.completions.clone())
+}
+
+/// Cache the expansion of arguments for the given time.
+fn argument_cache_put(key: ArgumentCacheKey, completions: Vec<Completion>, ttl: Duration) {
+    let now = Instant::now();
+    let mut cache = ARGUMENT_CACHE.lock().unwrap();
+    // Drop expired entries, so the cache does not grow with every command line.
+    cache.retain(|_, cached| cached.expires.is_none_or(|expires| expires > now));
+    let expires = now.checked_add(ttl);
+    cache.insert(
+        key,
+        CachedArguments {
+            completions,
+            expires,
+        },
+    );
+}
+
+/// Commands whose completions are generated from their `--help` output, and whether that has
+/// been done. See `complete --from-help`.
+static HELP_COMMANDS: Mutex<BTreeMap<WString, bool>> = Mutex::new(BTreeMap::new());
+
+/// How long `cmd --help` may run when generating completions from it, as a `timeout` duration.
+const HELP_COMMAND_TIMEOUT: &wstr = L!("2s");
+
 /// Completion "wrapper" support. The map goes from wrapping-command to wrapped-command-list.
 type WrapperMap = HashMap<WString, Vec<WString>>;
 static WRAPPER_MAP: LazyLock<Mutex<WrapperMap>> = LazyLock::new(|| Mutex::new(HashMap::new()));
@@ -538,6 +598,18 @@ pub fn sort_and_prioritize(comps: &mut Vec<Completion>, flags: CompletionRequest
     // Here we do not pass suppress_exact, so that exact matches appear first.
     comps.sort_by(natural_compare_completions);
 
+    // If nothing matched as a prefix, put the closest fuzzy matches first. The sort is stable, so
+    // equally close matches, and those from a source with --keep-order, stay in order.
+    if !comps[0].r#match.is_exact_or_prefix() {
+        comps.sort_by_key(|c| {
+            let keep_order = c.flags.contains(CompleteFlags::DONT_SORT);
+            (
+                !keep_order,
+                Reverse(if keep_order { 0 } else { c.r#match.score }),
+            )
+        });
+    }
+
     // Lastly, if this is for an autosuggestion, prefer to avoid completions that duplicate
     // arguments, and penalize files that end in tilde - they're frequently autosave files from e.g.
     // emacs. Also prefer samecase to smartcase.
@@ -599,6 +671,12 @@ struct Completer<'ctx> {
     completions: CompletionReceiver,
     /// Commands which we would have tried to load, if we had a parser.
     needs_load: Vec<WString>,
+    /// Whether we skipped running script because we have no parser, so the completions may be
+    /// incomplete.
+    needs_parser: bool,
+    /// The command line before the token being completed, including variable assignments. Cached
+    /// arguments are keyed by it.
+    commandline_prefix: WString,
     /// Table of completions conditions that have already been tested and the corresponding test
     /// results.
     condition_cache: HashMap<WString, bool>,
@@ -614,14 +692,16 @@ pub fn new(ctx: &'ctx OperationContext<'ctx>, flags: CompletionRequestOptions) -
             flags,
             completions: CompletionReceiver::new(ctx.expansion_limit),
             needs_load: vec![],
+            needs_parser: false,
+            commandline_prefix: WString::new(),
             condition_cache: HashMap::new(),
         }
     }
 
     fn perform_for_commandline(&mut self, cmdline: WString) {
         // Limit recursion, in case a user-defined completion has cycles, or the completion for "x"
-        // wraps "A=B x" (#3474, #7344).  No need to do that when there is no parser: this happens only
-        // for autosuggestions where we don't evaluate command substitutions or variable assignments.
+        // wraps "A=B x" (#3474, #7344).  No need to do that when there is no parser: then we don't
+        // evaluate command substitutions or variable assignments.
         if let Some(parser) = self.ctx.maybe_parser() {
             let level = &mut parser.libdata_mut().complete_recursion_level;
             if *level >= 24 {
@@ -869,6 +949,7 @@ fn condition_test(&mut self, condition: &wstr) -> bool {
             return true;
         }
         let Some(parser) = self.ctx.maybe_parser() else {
+            self.needs_parser = true;
             return false;
         };
 
@@ -948,7 +1029,10 @@ fn complete_strings(
 
     fn expand_flags(&self) -> ExpandFlags {
         let mut result = ExpandFlags::empty();
-        result.set(ExpandFlags::FAIL_ON_CMDSUBST, self.flags.autosuggestion);
+        result.set(
+            ExpandFlags::FAIL_ON_CMDSUBST,
+            self.flags.autosuggestion || !self.ctx.has_parser(),
+        );
         result.set(ExpandFlags::FUZZY_MATCH, self.flags.fuzzy_match);
         result.set(ExpandFlags::GEN_DESCRIPTIONS, self.flags.descriptions);
         result
@@ -957,10 +1041,6 @@ fn expand_flags(&self) -> ExpandFlags {
     /// If command to complete is short enough, substitute the description with the whatis information
     /// for the executable.
     fn complete_cmd_desc(&mut self, s: &wstr) {
-        let Some(parser) = self.ctx.maybe_parser() else {
-            return;
-        };
-
         let cmd = if let Some(pos) = s.chars().rposition(|c| c == '/') {
             if pos + 1 > s.len() {
                 return;
@@ -988,6 +1068,12 @@ fn complete_cmd_desc(&mut self, s: &wstr) {
             return;
         }
 
+        // The descriptions come from a script.
+        let Some(parser) = self.ctx.maybe_parser() else {
+            self.needs_parser = true;
+            return;
+        };
+
         // On Cygwin, if `cmd` contains part of the `.exe` extension (e.g. `lsmod.e`), we are unlikely
         // to find a description since they are usually associated to the POSIX name (`lsmod`). So we also
         // need to search for the stripped command (`lsmod`), and later associate the description to
@@ -1183,8 +1269,64 @@ fn complete_abbr(&mut self, cmd: WString) {
     /// - `args`: The list of option arguments to be evaluated.
     /// - `desc`: Description of the completion
     /// - `flags`: The flags
-    fn complete_from_args(&mut self, s: &wstr, args: &wstr, desc: &wstr, flags: CompleteFlags) {
+    /// - `cache_ttl`: How long to cache the expanded arguments, if at all
+    fn complete_from_args(
+        &mut self,
+        s: &wstr,
+        args: &wstr,
+        desc: &wstr,
+        flags: CompleteFlags,
+        cache_ttl: Option<Duration>,
+    ) {
+        // Only arguments with command substitutions are worth caching.
+        let cache_key = cache_ttl.filter(|_| has_cmdsub(args)).map(|ttl| {
+            let key = ArgumentCacheKey {
+                args: args.to_owned(),
+                commandline_prefix: self.commandline_prefix.clone(),
+                cwd: self.ctx.vars().get_pwd_slash(),
+            };
+            (key, ttl)
+        });
+        let cached = cache_key
+            .as_ref()
+            .and_then(|(key, _)| argument_cache_get(key));
+        let possible_comp = match cached {
+            Some(possible_comp) => possible_comp,
+            None => {
+                let Some(possible_comp) = self.expand_args(args) else {
+                    return;
+                };
+                // Don't cache what was cut short, or expanded without command substitutions.
+                if let Some((key, ttl)) = cache_key {
+                    if self.ctx.has_parser()
+                        && !self.flags.autosuggestion
+                        && !self.ctx.check_cancel()
+                    {
+                        argument_cache_put(key, possible_comp.clone(), ttl);
+                    }
+                }
+                possible_comp
+            }
+        };
+
+        // Allow leading dots - see #3707.
+        self.complete_strings(
+            &escape(s),
+            &const_desc(desc),
+            &possible_comp,
+            flags,
+            ExpandFlags::ALLOW_NONLITERAL_LEADING_DOT,
+        );
+    }
+
+    /// Expand the arguments of a completion. Returns None if that needs a parser, which we don't
+    /// have.
+    fn expand_args(&mut self, args: &wstr) -> Option<Vec<Completion>> {
         let is_autosuggest = self.flags.autosuggestion;
+        if !is_autosuggest && !self.ctx.has_parser() && has_cmdsub(args) {
+            self.needs_parser = true;
+            return None;
+        }
 
         let mut saved_statuses = None;
         let mut scope = None;
@@ -1193,7 +1335,7 @@ fn complete_from_args(&mut self, s: &wstr, args: &wstr, desc: &wstr, flags: Comp
             scope = Some(parser.push_scope(|s| s.is_interactive = false));
         }
 
-        let eflags = if is_autosuggest {
+        let eflags = if is_autosuggest || !self.ctx.has_parser() {
             ExpandFlags::FAIL_ON_CMDSUBST
         } else {
             ExpandFlags::empty()
@@ -1205,15 +1347,7 @@ fn complete_from_args(&mut self, s: &wstr, args: &wstr, desc: &wstr, flags: Comp
             parser.set_last_statuses(saved_statuses.unwrap());
         }
         std::mem::drop(scope);
-
-        // Allow leading dots - see #3707.
-        self.complete_strings(
-            &escape(s),
-            &const_desc(desc),
-            &possible_comp,
-            flags,
-            ExpandFlags::ALLOW_NONLITERAL_LEADING_DOT,
-        );
+        Some(possible_comp)
     }
 
     /// complete_param: Given a command, find completions for the argument `s` of command `cmd_orig`
@@ -1255,16 +1389,21 @@ fn complete_param_for_command(
             flog!(complete, "Skipping completions for non-existent command");
         } else if let Some(parser) = self.ctx.maybe_parser() {
             complete_load(&cmd, parser);
+            // Running `cmd --help` may take a while, so wait for an explicit tab.
+            if !self.flags.autosuggestion {
+                complete_load_from_help(&cmd, parser);
+            }
         } else if !COMPLETION_AUTOLOADER
             .lock()
             .unwrap()
             .has_attempted_autoload(&cmd)
+            || (!self.flags.autosuggestion && help_completions_pending(&cmd))
         {
             self.needs_load.push(cmd.clone());
         }
 
         // Make a list of lists of all options that we care about.
-        let all_options: Vec<Vec<CompleteEntryOpt>> = COMPLETION_MAP
+        let all_options: Vec<(Vec<CompleteEntryOpt>, Option<Duration>)> = COMPLETION_MAP
             .lock()
             .unwrap()
             .iter()
@@ -1283,7 +1422,7 @@ fn complete_param_for_command(
                     let mut options = completion.get_options().to_vec();
                     // We have to copy them in reverse order to preserve legacy behavior (#9221).
                     options.reverse();
-                    Some(options)
+                    Some((options, completion.cache_ttl))
                 } else {
                     None
                 }
@@ -1292,7 +1431,7 @@ fn complete_param_for_command(
 
         // Now release the lock and test each option that we captured above. We have to do this outside
         // the lock because callouts (like the condition) may add or remove completions. See issue #2.
-        for options in all_options {
+        for (options, cache_ttl) in all_options {
             let short_opt_pos = short_option_pos(s, &options);
             // We want last_option_requires_param to default to false but distinguish between when
             // a previous completion has set it to false and when it has its default value.
@@ -1335,7 +1474,13 @@ fn complete_param_for_command(
                                 }
                                 let (arg_prefix, arg) = s.split_once(arg_offset);
                                 let first_new = self.completions.completions.len();
-                                self.complete_from_args(arg, &o.comp, o.desc.localize(), o.flags);
+                                self.complete_from_args(
+                                    arg,
+                                    &o.comp,
+                                    o.desc.localize(),
+                                    o.flags,
+                                    cache_ttl,
+                                );
                                 for compl in &mut self.completions.completions[first_new..] {
                                     if compl.replaces_token() {
                                         compl.completion.insert_utfstr(0, arg_prefix);
@@ -1366,7 +1511,13 @@ fn complete_param_for_command(
                             if o.result_mode.force_files {
                                 has_force = true;
                             }
-                            self.complete_from_args(s, &o.comp, o.desc.localize(), o.flags);
+                            self.complete_from_args(
+                                s,
+                                &o.comp,
+                                o.desc.localize(),
+                                o.flags,
+                                cache_ttl,
+                            );
                         }
                     }
 
@@ -1402,7 +1553,13 @@ fn complete_param_for_command(
                                 if o.result_mode.force_files {
                                     has_force = true;
                                 }
-                                self.complete_from_args(s, &o.comp, o.desc.localize(), o.flags);
+                                self.complete_from_args(
+                                    s,
+                                    &o.comp,
+                                    o.desc.localize(),
+                                    o.flags,
+                                    cache_ttl,
+                                );
                             }
                         }
                     }
@@ -1425,7 +1582,7 @@ fn complete_param_for_command(
                 if o.option.is_empty() {
                     use_files &= !o.result_mode.no_files;
                     has_force |= o.result_mode.force_files;
-                    self.complete_from_args(s, &o.comp, o.desc.localize(), o.flags);
+                    self.complete_from_args(s, &o.comp, o.desc.localize(), o.flags, cache_ttl);
                 }
 
                 if !use_switches || s.is_empty() {
@@ -1868,7 +2025,12 @@ fn apply_var_assignments<T: AsRef<wstr>>(
         &mut self,
         var_assignments: &[T],
     ) -> Option<ScopeGuard<(), impl FnOnce(()) + 'ctx + use<'ctx, T>>> {
-        if !self.ctx.has_parser() || var_assignments.is_empty() {
+        if var_assignments.is_empty() {
+            return None;
+        }
+        if !self.ctx.has_parser() {
+            // Completion scripts or expansions may use the variables.
+            self.needs_parser = true;
             return None;
         }
         let parser = self.ctx.parser();
@@ -1936,8 +2098,8 @@ fn complete_custom(&mut self, cmd: &wstr, cmdline: &wstr, ad: &mut CustomArgData
         // Perhaps set a transient commandline so that custom completions
         // builtin_commandline will refer to the wrapped command. But not if
         // we're doing autosuggestions.
-        let _remove_transient = (!is_autosuggest).then(|| {
-            let parser = self.ctx.parser();
+        let parser = self.ctx.maybe_parser().filter(|_| !is_autosuggest);
+        let _remove_transient = parser.map(|parser| {
             let saved_transient = parser
                 .libdata_mut()
                 .transient_commandline
@@ -1953,6 +2115,14 @@ fn complete_custom(&mut self, cmd: &wstr, cmdline: &wstr, ad: &mut CustomArgData
             return;
         }
 
+        let (token, _) = get_token_extent(cmdline, cmdline.len());
+        self.commandline_prefix.clear();
+        for var_assign in ad.var_assignments.iter() {
+            self.commandline_prefix.push_utfstr(var_assign);
+            self.commandline_prefix.push(' ');
+        }
+        self.commandline_prefix.push_utfstr(&cmdline[..token.start]);
+
         // Invoke any custom completions for this command.
         self.complete_param_for_command(
             cmd,
@@ -2344,6 +2514,32 @@ pub fn complete_remove(cmd: WString, cmd_is_path: bool, option: &wstr, typ: Comp
     }
 }
 
+/// Set how long the expanded arguments of the completions for a command are cached, or stop
+/// caching them if `ttl` is None.
+pub fn complete_set_cache_ttl(cmd: WString, cmd_is_path: bool, ttl: Option<Duration>) {
+    let mut completion_map = COMPLETION_MAP.lock().expect("mutex poisoned");
+    let idx = CompletionEntryIndex {
+        name: cmd,
+        is_path: cmd_is_path,
+    };
+    match ttl {
+        Some(_) => {
+            completion_map
+                .entry(idx)
+                .or_insert_with(CompletionEntry::new)
+                .cache_ttl = ttl;
+        }
+        None => {
+            if let Some(c) = completion_map.get_mut(&idx) {
+                c.cache_ttl = None;
+                if c.options.is_empty() {
+                    completion_map.remove(&idx);
+                }
+            }
+        }
+    }
+}
+
 /// Removes all completions for a given command.
 pub fn complete_remove_all(cmd: WString, cmd_is_path: bool, explicit: bool) {
     let mut completion_map = COMPLETION_MAP.lock().expect("mutex poisoned");
@@ -2353,6 +2549,9 @@ pub fn complete_remove_all(cmd: WString, cmd_is_path: bool, explicit: bool) {
     };
     let removed = completion_map.remove(&idx).is_some();
     WRAPPER_MAP.lock().unwrap().remove(&idx.name);
+    if !idx.is_path {
+        HELP_COMMANDS.lock().unwrap().remove(&idx.name);
+    }
     if explicit && !removed && !idx.is_path {
         COMPLETION_TOMBSTONES.lock().unwrap().insert(idx.name);
     }
@@ -2378,6 +2577,30 @@ pub fn complete(
     )
 }
 
+/// Returns all completions of the command cmd, if they can be computed without a parser, for example
+/// on a background thread. Returns None if they depend on running script, like command
+/// substitutions or completion conditions, or on completions which are not loaded yet. Then they
+/// must be computed again with a parser.
+pub fn complete_without_parser(
+    cmd_with_subcmds: &wstr,
+    flags: CompletionRequestOptions,
+    ctx: &OperationContext,
+) -> Option<Vec<Completion>> {
+    assert!(!ctx.has_parser(), "Context should not have a parser");
+    let cmdsubst = get_cmdsubst_extent(cmd_with_subcmds, cmd_with_subcmds.len());
+    let cmd = cmd_with_subcmds[cmdsubst].to_owned();
+    if has_cmdsub(&cmd) {
+        return None;
+    }
+    let mut completer = Completer::new(ctx, flags);
+    completer.perform_for_commandline(cmd);
+
+    if completer.needs_parser || !completer.needs_load.is_empty() {
+        return None;
+    }
+    Some(completer.acquire_completions())
+}
+
 /// Print the short switch `opt`, and the argument `arg` to the specified
 /// [`WString`], but only if `argument` isn't an empty string.
 fn append_switch_short_arg(out: &mut WString, opt: char, arg: &wstr) {
@@ -2442,6 +2665,24 @@ fn completion2string(index: &CompletionEntryIndex, o: &CompleteEntryOpt) -> WStr
     out
 }
 
+fn cache_ttl2string(index: &CompletionEntryIndex, ttl: Duration) -> WString {
+    let mut out = WString::from(L!("complete"));
+    if index.is_path {
+        append_switch_short_arg(&mut out, 'p', &index.name);
+    } else {
+        out.push(' ');
+        out.push_utfstr(&escape(&index.name));
+    }
+    let ttl = if ttl.subsec_nanos() == 0 {
+        sprintf!("%us", ttl.as_secs())
+    } else {
+        sprintf!("%ums", u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX))
+    };
+    append_switch_long_arg(&mut out, L!("cache-ttl"), &ttl);
+    out.push('\n');
+    out
+}
+
 /// If the cmd contains a partial executable extension, return the stripped
 /// command and missing part of the full extension.
 /// E.g. `cmd.e` -> `Some(("cmd", "xe"))``
@@ -2506,6 +2747,102 @@ pub fn complete_load(cmd: &wstr, parser: &Parser) -> bool {
     loaded_new
 }
 
+/// Generate the completions for a command from its `--help` output, when they are first needed.
+pub fn complete_add_from_help(cmd: WString) {
+    HELP_COMMANDS.lock().unwrap().entry(cmd).or_insert(false);
+}
+
+/// Return whether the completions for a command are still to be generated from its `--help`.
+fn help_completions_pending(cmd: &wstr) -> bool {
+    HELP_COMMANDS.lock().unwrap().get(cmd) == Some(&false)
+}
+
+/// Run `cmd --help` and add the completions found in its output, if they are pending. This comes
+/// after `complete_load`, since the completion file may ask for it. The command is stopped if it
+/// takes too long, in which case nothing is added. Returns `true` if something was added.
+fn complete_load_from_help(cmd: &wstr, parser: &Parser) -> bool {
+    {
+        let mut commands = HELP_COMMANDS.lock().unwrap();
+        let Some(generated) = commands.get_mut(cmd).filter(|generated| !**generated) else {
+            return false;
+        };
+        // Mark them as generated before running anything, like the autoloader does.
+        *generated = true;
+    }
+
+    let mut help = vec![];
+    let help_cmd = sprintf!(
+        "builtin timeout %s %s --help </dev/null 2>&1",
+        HELP_COMMAND_TIMEOUT,
+        escape(cmd)
+    );
+    let status = exec_subshell(
+        &help_cmd,
+        parser,
+        Some(&mut help),
+        false, /* don't apply exit status */
+    );
+    if signal_check_cancel() != 0 {
+        // Cancelled with ctrl-c, so try again the next time.
+        if let Some(generated) = HELP_COMMANDS.lock().unwrap().get_mut(cmd) {
+            *generated = false;
+        }
+        return false;
+    }
+    if status == Err(STATUS_TIMED_OUT) {
+        flog!(complete, "Timed out running", cmd, "--help");
+        return false;
+    }
+
+    let parsed = parse_help(&help);
+    let flags = CompleteFlags::AUTO_SPACE | CompleteFlags::DONT_ESCAPE_TILDES;
+    for option in &parsed.options {
+        let mut comp = WString::new();
+        for value in &option.values {
+            if !comp.is_empty() {
+                comp.push(' ');
+            }
+            comp.push_utfstr(&escape(value));
+        }
+        let result_mode = CompletionMode {
+            no_files: !option.values.is_empty(),
+            force_files: false,
+            requires_param: option.requires_param,
+        };
+        for (name, typ) in &option.names {
+            complete_add(
+                cmd.to_owned(),
+                false,
+                name.clone(),
+                *typ,
+                result_mode,
+                vec![],
+                comp.clone(),
+                option.description.clone(),
+                flags,
+            );
+        }
+    }
+    for subcommand in &parsed.subcommands {
+        complete_add(
+            cmd.to_owned(),
+            false,
+            WString::new(),
+            CompleteOptionType::ArgsOnly,
+            CompletionMode {
+                no_files: true,
+                force_files: false,
+                requires_param: false,
+            },
+            vec![L!("__fish_use_subcommand").to_owned()],
+            escape(&subcommand.name),
+            subcommand.description.clone(),
+            flags,
+        );
+    }
+    !parsed.options.is_empty() || !parsed.subcommands.is_empty()
+}
+
 /// Return a list of all current completions.
 /// Used by the bare `complete`, loaded completions are printed out as commands
 pub fn complete_print(cmd: &wstr) -> WString {
@@ -2525,6 +2862,9 @@ pub fn complete_print(cmd: &wstr) -> WString {
         for o in entry.get_options().iter().rev() {
             out.push_utfstr(&completion2string(key, o));
         }
+        if let Some(ttl) = entry.cache_ttl {
+            out.push_utfstr(&cache_ttl2string(key, ttl));
+        }
     }
 
     // Append wraps.
@@ -2541,6 +2881,18 @@ pub fn complete_print(cmd: &wstr) -> WString {
         }
     }
 
+    // Append commands whose completions come from their help.
+    let help_commands = HELP_COMMANDS.lock().expect("poisoned mutex");
+    for help_cmd in help_commands.keys() {
+        if !cmd.is_empty() && help_cmd != cmd {
+            continue;
+        }
+        out.push_utfstr(L!("complete "));
+        out.push_utfstr(&escape(help_cmd));
+        append_switch_long(&mut out, L!("from-help"));
+        out.push_utfstr(L!("\n"));
+    }
+
     out
 }
 
@@ -2557,6 +2909,9 @@ pub fn complete_invalidate_path() {
     for cmd in cmds {
         complete_remove_all(cmd, /*cmd_is_path=*/ false, /*explicit=*/ false);
     }
+
+    // The cached arguments may come from the completions we just unloaded.
+    ARGUMENT_CACHE.lock().unwrap().clear();
 }
 
 /// Adds a "wrap target." A wrap target is a command that completes like another command.
@@ -2628,9 +2983,9 @@ pub struct CompletionRequestOptions {
 #[cfg(test)]
 mod tests {
     use super::{
-        CompleteFlags, CompleteOptionType, CompletionMode, CompletionRequestOptions, complete,
-        complete_add, complete_add_wrapper, complete_get_wrap_targets, complete_remove_wrapper,
-        sort_and_prioritize,
+        CompleteFlags, CompleteOptionType, Completion, CompletionMode, CompletionRequestOptions,
+        complete, complete_add, complete_add_wrapper, complete_get_wrap_targets,
+        complete_remove_all, complete_remove_wrapper, complete_without_parser, sort_and_prioritize,
     };
     use crate::abbrs::{self, Abbreviation, with_abbrs_mut};
     use crate::common::str2wcstring;
@@ -2643,7 +2998,7 @@ mod tests {
     use crate::prelude::*;
     use crate::reader::completion_apply_to_command_line;
     use crate::tests::prelude::*;
-    use fish_wcstringutil::join_strings;
+    use fish_wcstringutil::{join_strings, string_fuzzy_match_string};
     use std::collections::HashMap;
     use std::ffi::CString;
 
@@ -2652,6 +3007,59 @@ fn comma_join(lst: Vec<WString>) -> WString {
         join_strings(&lst, ',')
     }
 
+    #[test]
+    fn test_sort_fuzzy_matches() {
+        let fuzzy_completions = |needle: &wstr, candidates: &[&wstr]| {
+            candidates
+                .iter()
+                .map(|&candidate| {
+                    let r#match = string_fuzzy_match_string(needle, candidate, false).unwrap();
+                    Completion::new(
+                        candidate.to_owned(),
+                        WString::new(),
+                        r#match,
+                        CompleteFlags::REPLACES_TOKEN,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let sorted = |mut completions: Vec<Completion>| {
+            sort_and_prioritize(&mut completions, CompletionRequestOptions::default());
+            completions
+                .into_iter()
+                .map(|c| c.completion.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Closer matches come first: word starts and consecutive characters count.
+        let candidates = [L!("affable"), L!("fooxbar"), L!("foo-bar"), L!("fix/bug")];
+        assert_eq!(
+            sorted(fuzzy_completions(L!("fb"), &candidates)),
+            ["fix/bug", "foo-bar", "fooxbar", "affable"]
+        );
+
+        // Equally close matches stay in natural order.
+        let candidates = [L!("b-2"), L!("a-2"), L!("c-2")];
+        assert_eq!(
+            sorted(fuzzy_completions(L!("2"), &candidates)),
+            ["a-2", "b-2", "c-2"]
+        );
+
+        // Prefix matches are not reordered.
+        let candidates = [L!("fooxbar"), L!("foo-bar")];
+        assert_eq!(
+            sorted(fuzzy_completions(L!("foo"), &candidates)),
+            ["foo-bar", "fooxbar"]
+        );
+
+        // Nor are completions which keep their order.
+        let mut completions = fuzzy_completions(L!("fb"), &[L!("affable"), L!("fix/bug")]);
+        for c in &mut completions {
+            c.flags |= CompleteFlags::DONT_SORT;
+        }
+        assert_eq!(sorted(completions), ["affable", "fix/bug"]);
+    }
+
     #[test]
     #[serial]
     fn test_complete() {
@@ -2978,6 +3386,33 @@ macro_rules! unique_completion_applies_as {
         assert_eq!(completions.len(), 1);
         assert_eq!(completions[0].completion, L!("qux"));
 
+        // Without a parser, completions are only computed if they need no script.
+        let bg_ctx = OperationContext::background(&vars, EXPANSION_LIMIT_DEFAULT);
+        let complete_in_bg =
+            |cmd: &wstr| complete_without_parser(cmd, CompletionRequestOptions::default(), &bg_ctx);
+        let completions = complete_in_bg(L!("foobarbaz ")).unwrap();
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].completion, L!("qux"));
+        for (conditions, arguments) in [
+            (vec![L!("true").to_owned()], L!("quux")),
+            (vec![], L!("(echo quux)")),
+        ] {
+            complete_add(
+                L!("foobarqux").into(),
+                false,
+                WString::new(),
+                CompleteOptionType::ArgsOnly,
+                no_files,
+                conditions,
+                arguments.to_owned(),
+                WString::new(),
+                CompleteFlags::AUTO_SPACE,
+            );
+            assert_eq!(complete_in_bg(L!("foobarqux ")), None);
+            complete_remove_all(L!("foobarqux").into(), false, false);
+        }
+        assert_eq!(complete_in_bg(L!("echo (foobarbaz) ")), None);
+
         // Don't complete variable names in single quotes (#1023).
         let completions = do_complete(L!("echo '$Foo"), CompletionRequestOptions::default());
         assert_eq!(completions, vec![]);
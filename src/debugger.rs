@@ -0,0 +1,243 @@
+//! The debugger. Execution stops at breakpoints set by location, or while stepping through
+//! a script, and the user is presented with a prompt to inspect and change its state.
+
+use crate::ast::Node as _;
+use crate::function;
+use crate::io::IoChain;
+use crate::parser::{Block, BlockType, Parser};
+use crate::prelude::*;
+use crate::reader::reader_read;
+use crate::wutil::fish_wcstoi;
+use libc::STDIN_FILENO;
+
+/// Where a breakpoint stops execution.
+pub enum BreakpointLocation {
+    /// A line in a function, where the line with the function definition is line 1.
+    Function { name: WString, line: u32 },
+    /// A line in a file, which is matched against the end of the file's path.
+    File { path: WString, line: u32 },
+}
+
+impl BreakpointLocation {
+    /// Parse a location like `function:line` or `file:line`. Names which contain a slash or end in
+    /// `.fish` are files. Return None if the location is malformed.
+    pub fn parse(location: &wstr) -> Option<Self> {
+        let colon = location.as_char_slice().iter().rposition(|&c| c == ':')?;
+        let name = location[..colon].to_owned();
+        let line = fish_wcstoi(&location[colon + 1..]).ok()?;
+        if name.is_empty() || line <= 0 {
+            return None;
+        }
+        let line = line as u32;
+        if name.contains('/') || name.ends_with(".fish") {
+            Some(Self::File { path: name, line })
+        } else {
+            Some(Self::Function { name, line })
+        }
+    }
+
+    /// Return the location as it was given.
+    pub fn describe(&self) -> WString {
+        match self {
+            Self::Function { name, line } => sprintf!("%s:%u", name, line),
+            Self::File { path, line } => sprintf!("%s:%u", path, line),
+        }
+    }
+}
+
+pub struct Breakpoint {
+    pub id: u32,
+    pub location: BreakpointLocation,
+    /// A command which must succeed for the breakpoint to stop execution.
+    pub condition: Option<WString>,
+}
+
+/// Where execution stops next, besides breakpoints.
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub enum StepMode {
+    /// Only stop at breakpoints.
+    #[default]
+    Continue,
+    /// Stop before the next job.
+    Step,
+    /// Stop before the next job which is not in a function called from the given depth.
+    Next(usize),
+    /// Stop before the next job after the function at the given depth returned.
+    Finish(usize),
+}
+
+/// The state of the debugger, which is kept in the parser's library data.
+#[derive(Default)]
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    last_id: u32,
+    step_mode: StepMode,
+    /// Set while evaluating the condition of a breakpoint, which must not stop itself.
+    in_condition: bool,
+}
+
+impl Debugger {
+    /// Add a breakpoint, returning its id.
+    pub fn add(&mut self, location: BreakpointLocation, condition: Option<WString>) -> u32 {
+        self.last_id += 1;
+        self.breakpoints.push(Breakpoint {
+            id: self.last_id,
+            location,
+            condition,
+        });
+        self.last_id
+    }
+
+    /// Remove the breakpoint with the given id. Return whether it existed.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|bp| bp.id != id);
+        self.breakpoints.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn set_step_mode(&mut self, mode: StepMode) {
+        self.step_mode = mode;
+    }
+
+    /// Stop stepping. This happens once the command typed at the prompt is done.
+    pub fn stop_stepping(&mut self) {
+        self.step_mode = StepMode::Continue;
+    }
+
+    fn is_active(&self) -> bool {
+        !self.in_condition && (self.step_mode != StepMode::Continue || !self.breakpoints.is_empty())
+    }
+}
+
+/// Return the number of function calls the stopped job is in, when at the debug prompt.
+pub fn stopped_function_depth(parser: &Parser) -> usize {
+    parser
+        .blocks_iter_rev()
+        .skip_while(|b| b.typ() != BlockType::breakpoint)
+        .filter(|b| b.is_function_call())
+        .count()
+}
+
+/// Evaluate the condition of a breakpoint, leaving $status untouched.
+fn condition_holds(parser: &Parser, condition: &wstr, io: &IoChain) -> bool {
+    let statuses = parser.get_last_statuses();
+    parser.libdata_mut().debugger.in_condition = true;
+    parser.eval(condition, io);
+    parser.libdata_mut().debugger.in_condition = false;
+    let holds = parser.get_last_status() == 0;
+    parser.set_last_statuses(statuses);
+    holds
+}
+
+/// Return the id of the breakpoint at the current job, if any, and if its condition holds.
+fn hit_breakpoint(parser: &Parser, io: &IoChain) -> Option<u32> {
+    let lineno = parser.get_lineno()?.get();
+    let filename = parser.current_filename();
+    let function = parser.get_function_name(1);
+    let function_line = function
+        .as_ref()
+        .and_then(|name| function::get_props(name))
+        .map(|props| i64::from(lineno) - i64::from(props.definition_lineno()) + 1);
+
+    let candidates: Vec<_> = parser
+        .libdata()
+        .debugger
+        .breakpoints
+        .iter()
+        .filter(|bp| match &bp.location {
+            BreakpointLocation::Function { name, line } => {
+                function.as_ref() == Some(name) && function_line == Some(i64::from(*line))
+            }
+            BreakpointLocation::File { path, line } => {
+                let mut suffix = L!("/").to_owned();
+                suffix.push_utfstr(path);
+                *line == lineno
+                    && filename
+                        .as_ref()
+                        .is_some_and(|f| **f == *path || f.ends_with(&suffix))
+            }
+        })
+        .map(|bp| (bp.id, bp.condition.clone()))
+        .collect();
+
+    candidates.into_iter().find_map(|(id, condition)| {
+        condition
+            .is_none_or(|condition| condition_holds(parser, &condition, io))
+            .then_some(id)
+    })
+}
+
+/// Describe where execution stopped, with the first line of the job that is about to run.
+fn describe_stop(parser: &Parser, breakpoint_id: Option<u32>) -> WString {
+    let mut text = match breakpoint_id {
+        Some(id) => wgettext_fmt!("Breakpoint %u", id),
+        None => wgettext!("Stopped").to_owned(),
+    };
+    if let Some(function) = parser.get_function_name(1) {
+        text.push_utfstr(&wgettext_fmt!(" in function '%s'", function));
+    }
+    text.push('\n');
+
+    let filename = parser.current_filename();
+    let job = parser.current_node_ref().map_or(WString::new(), |node| {
+        let source = node.source(node.source_str());
+        source.split('\n').next().unwrap_or(L!("")).to_owned()
+    });
+    text.push_utfstr(&sprintf!(
+        "%s (line %u): %s\n",
+        filename.as_ref().map_or(L!("-"), |f| f.as_utfstr()),
+        parser.get_lineno_for_display(),
+        job
+    ));
+    text
+}
+
+/// Stop at the debug prompt before the current job runs, if a breakpoint or stepping asks for it.
+pub fn debugger_stop_if_requested(parser: &Parser, io: &IoChain) {
+    if !parser.libdata().debugger.is_active() {
+        return;
+    }
+    // Like the breakpoint builtin, only stop in interactive sessions. Commands run from the debug
+    // prompt never stop.
+    if !parser.is_interactive() || parser.is_breakpoint() {
+        return;
+    }
+
+    let depth = parser
+        .blocks_iter_rev()
+        .filter(|b| b.is_function_call())
+        .count();
+    let step_mode = parser.libdata().debugger.step_mode;
+    let stop_for_step = match step_mode {
+        StepMode::Continue => false,
+        StepMode::Step => true,
+        StepMode::Next(stopped_depth) => depth <= stopped_depth,
+        StepMode::Finish(stopped_depth) => depth < stopped_depth,
+    };
+    let breakpoint_id = if stop_for_step {
+        None
+    } else {
+        match hit_breakpoint(parser, io) {
+            Some(id) => Some(id),
+            None => return,
+        }
+    };
+
+    parser.libdata_mut().debugger.stop_stepping();
+    eprintf!("%s", describe_stop(parser, breakpoint_id));
+
+    // The debug prompt must not change the status seen by the script.
+    let statuses = parser.get_last_statuses();
+    let bpb = parser.push_block(Block::breakpoint_block());
+    let _ = reader_read(parser, STDIN_FILENO, io);
+    parser.pop_block(bpb);
+    parser.set_last_statuses(statuses);
+}
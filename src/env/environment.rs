@@ -323,6 +323,11 @@ pub fn push(&self, new_scope: bool) {
         }
     }
 
+    /// Return the local variables of a function scope, where 0 is the current scope.
+    pub fn get_frame_locals(&self, frame: usize) -> Option<Vec<(WString, EnvVar)>> {
+        self.lock().get_frame_locals(frame)
+    }
+
     /// Pop the variable stack. Used for implementing local variables for functions and for-loops.
     pub fn pop(&self, is_repainting: bool) {
         assert!(self.can_push_pop, "push/pop not allowed on global stack");
@@ -109,6 +109,9 @@ struct Query {
     pub pathvar: bool,
     pub unpathvar: bool,
 
+    /// Whether to set a dictionary.
+    pub dict: bool,
+
     /// Whether this is a "user" set.
     pub user: bool,
 }
@@ -144,6 +147,8 @@ fn new(mode: EnvMode, user: bool) -> Self {
             pathvar: mode.contains(EnvMode::PATHVAR),
             unpathvar: mode.contains(EnvMode::UNPATHVAR),
 
+            dict: mode.contains(EnvMode::DICT),
+
             user,
         }
     }
@@ -681,6 +686,9 @@ struct VarFlags {
 
     /// Whether the variable is exported by some parent.
     pub parent_exports: bool,
+
+    /// Whether the values are the alternating keys and values of a dictionary.
+    pub dict: bool,
 }
 
 #[derive(Copy, Clone, Default)]
@@ -750,6 +758,7 @@ pub fn set(&mut self, key: &wstr, mode: EnvSetMode, mut val: Vec<WString>) -> Mo
         if query.has_pathvar_unpathvar {
             flags.pathvar = Some(query.pathvar);
         }
+        flags.dict = query.dict;
 
         let mut result = ModResult::new(EnvStackSetResult::Ok);
         if query.has_scope {
@@ -911,6 +920,27 @@ pub fn pop(&mut self) -> Vec<WString> {
         var_names
     }
 
+    /// Return the local variables of a function scope, sorted by name. Scope 0 is the current
+    /// one, 1 is that of its caller and so on. Return None if there is no such scope.
+    pub fn get_frame_locals(&self, frame: usize) -> Option<Vec<(WString, EnvVar)>> {
+        let node = match frame {
+            0 => &self.base.locals,
+            _ => self.shadowed_locals.iter().rev().nth(frame - 1)?,
+        };
+        let mut names = HashSet::new();
+        let mut vars = vec![];
+        // Go from inner scopes outwards, so inner variables shadow outer ones.
+        for cursor in node.iter() {
+            for (key, val) in cursor.borrow().env.iter() {
+                if names.insert(key.clone()) {
+                    vars.push((key.clone(), val.clone()));
+                }
+            }
+        }
+        vars.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Some(vars)
+    }
+
     /// Find the first node in the chain starting at `node` which contains the given key `key`.
     fn find_in_chain(node: &EnvNodeRef, key: &wstr) -> Option<EnvNodeRef> {
         #[allow(clippy::manual_find)]
@@ -959,6 +989,11 @@ fn try_set_electric(
             return Some(EnvStackSetResult::Perm);
         }
 
+        // Electric variables are never dictionaries.
+        if query.dict {
+            return Some(EnvStackSetResult::Invalid);
+        }
+
         // Be picky about exporting.
         if query.has_export_unexport {
             let matches = if ev.exports() {
@@ -991,6 +1026,7 @@ fn try_set_electric(
             exports: Some(ev.exports()),
             parent_exports: ev.exports(),
             pathvar: Some(false),
+            dict: false,
         };
         Self::set_in_node(&mut self.base.globals, key, val, flags);
         Some(EnvStackSetResult::Ok)
@@ -1022,7 +1058,7 @@ fn set_universal(&mut self, key: &wstr, mut val: Vec<WString>, query: Query) {
         }
 
         // Split about ':' if it's a path variable.
-        if pathvar {
+        if pathvar && !query.dict {
             val = colon_split(&val);
         }
 
@@ -1030,6 +1066,7 @@ fn set_universal(&mut self, key: &wstr, mut val: Vec<WString>, query: Query) {
         let mut varflags = EnvVarFlags::empty();
         varflags.set(EnvVarFlags::EXPORT, exports);
         varflags.set(EnvVarFlags::PATHVAR, pathvar);
+        varflags.set(EnvVarFlags::DICT, query.dict);
         let new_var = EnvVar::new_vec(val, varflags);
 
         locked_uvars.set(key, new_var);
@@ -1048,16 +1085,22 @@ fn set_in_node(node: &mut EnvNodeRef, key: &wstr, mut val: Vec<WString>, flags:
         };
 
         // Pathvar is inferred from the name. If set, split our entry about colons.
-        let res_pathvar = match flags.pathvar {
-            Some(pathvar) => pathvar,
-            None => variable_should_auto_pathvar(key),
-        };
+        // Dictionaries are never path variables.
+        let res_pathvar = !flags.dict
+            && match flags.pathvar {
+                Some(pathvar) => pathvar,
+                None => variable_should_auto_pathvar(key),
+            };
         if res_pathvar {
             val = colon_split(&val);
         }
 
-        *var = var
-            .setting_vals(val)
+        let var_with_vals = if flags.dict {
+            var.setting_dict(val)
+        } else {
+            var.setting_vals(val)
+        };
+        *var = var_with_vals
             .setting_exports(res_exports)
             .setting_pathvar(res_pathvar);
 
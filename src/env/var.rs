@@ -32,6 +32,8 @@ pub struct EnvMode: u16 {
         const PATHVAR = 1 << 6;
         /// Flag to unmark a variable as a path variable.
         const UNPATHVAR = 1 << 7;
+        /// Flag to make a variable a dictionary. The values are alternating keys and values.
+        const DICT = 1 << 8;
     }
 }
 
@@ -114,6 +116,7 @@ pub struct EnvVarFlags: u8 {
         const EXPORT = 1 << 0;    // whether the variable is exported
         const READ_ONLY = 1 << 1; // whether the variable is read only
         const PATHVAR = 1 << 2;   // whether the variable is a path variable
+        const DICT = 1 << 3;      // whether the variable is a dictionary
     }
 }
 
@@ -124,6 +127,10 @@ pub struct EnvVar {
     /// The list of values in this variable.
     /// Arc allows for cheap copying
     values: Arc<[WString]>,
+    /// For dictionaries, the key of each value. Empty otherwise.
+    keys: Arc<[WString]>,
+    /// For dictionaries, the position of each key in `keys`. Empty otherwise.
+    key_positions: Arc<HashMap<WString, usize>>,
     /// The variable's flags.
     flags: EnvVarFlags,
 }
@@ -134,9 +141,14 @@ fn default() -> Self {
         /// A shared read-only empty list.
         static EMPTY_LIST: OnceLock<Arc<[WString]>> = OnceLock::new();
         let empty_list = EMPTY_LIST.get_or_init(|| Arc::new([]));
+        /// A shared read-only empty key map.
+        static EMPTY_MAP: OnceLock<Arc<HashMap<WString, usize>>> = OnceLock::new();
+        let empty_map = EMPTY_MAP.get_or_init(|| Arc::new(HashMap::new()));
 
         EnvVar {
             values: Arc::clone(empty_list),
+            keys: Arc::clone(empty_list),
+            key_positions: Arc::clone(empty_map),
             flags: EnvVarFlags::empty(),
         }
     }
@@ -150,12 +162,43 @@ pub fn new(value: WString, flags: EnvVarFlags) -> Self {
 
     /// Creates a new `EnvVar`.
     pub fn new_vec(values: Vec<WString>, flags: EnvVarFlags) -> Self {
+        if flags.contains(EnvVarFlags::DICT) {
+            return Self::new_dict(values, flags);
+        }
+        let empty = EnvVar::default();
         EnvVar {
             values: values.into(),
+            keys: empty.keys,
+            key_positions: empty.key_positions,
             flags,
         }
     }
 
+    /// Creates a new dictionary `EnvVar` from alternating keys and values. A trailing key without a
+    /// value is ignored. If a key is given more than once, the last value wins, but the key keeps
+    /// the position where it was first given.
+    pub fn new_dict(pairs: Vec<WString>, flags: EnvVarFlags) -> Self {
+        let mut keys: Vec<WString> = Vec::with_capacity(pairs.len() / 2);
+        let mut values: Vec<WString> = Vec::with_capacity(pairs.len() / 2);
+        let mut positions: HashMap<WString, usize> = HashMap::new();
+        let mut pairs = pairs.into_iter();
+        while let (Some(key), Some(value)) = (pairs.next(), pairs.next()) {
+            if let Some(&pos) = positions.get(&key) {
+                values[pos] = value;
+            } else {
+                positions.insert(key.clone(), keys.len());
+                keys.push(key);
+                values.push(value);
+            }
+        }
+        EnvVar {
+            values: values.into(),
+            keys: keys.into(),
+            key_positions: Arc::new(positions),
+            flags: (flags | EnvVarFlags::DICT) - EnvVarFlags::PATHVAR,
+        }
+    }
+
     /// Creates a new `EnvVar`, inferring the flags from the variable name.
     pub fn new_from_name_vec(name: &wstr, values: Vec<WString>) -> Self {
         Self::new_vec(values, Self::flags_for(name))
@@ -186,6 +229,37 @@ pub fn is_read_only(&self) -> bool {
         self.flags.contains(EnvVarFlags::READ_ONLY)
     }
 
+    /// Returns whether the variable is a dictionary.
+    pub fn is_dict(&self) -> bool {
+        self.flags.contains(EnvVarFlags::DICT)
+    }
+
+    /// Returns the keys of a dictionary, in the same order as its values.
+    /// This is empty if the variable is not a dictionary.
+    pub fn dict_keys(&self) -> &[WString] {
+        &self.keys
+    }
+
+    /// Returns the position of the given key, if the variable is a dictionary that contains it.
+    pub fn dict_position(&self, key: &wstr) -> Option<usize> {
+        self.key_positions.get(key).copied()
+    }
+
+    /// Returns the value for the given key, if the variable is a dictionary that contains it.
+    pub fn dict_get(&self, key: &wstr) -> Option<&WString> {
+        let pos = self.dict_position(key)?;
+        Some(&self.values[pos])
+    }
+
+    /// Returns the keys and values of a dictionary, alternating.
+    pub fn dict_pairs(&self) -> Vec<WString> {
+        self.keys
+            .iter()
+            .zip(self.values.iter())
+            .flat_map(|(k, v)| [k.clone(), v.clone()])
+            .collect()
+    }
+
     /// Returns the variable's flags.
     pub fn get_flags(&self) -> EnvVarFlags {
         self.flags
@@ -210,20 +284,30 @@ pub fn get_delimiter(&self) -> char {
         }
     }
 
-    /// Returns a copy of the variable with new values.
+    /// Returns a copy of the variable with new values. This turns a dictionary into a list.
     pub fn setting_vals(&self, values: Vec<WString>) -> Self {
+        let empty = EnvVar::default();
         EnvVar {
             values: values.into(),
-            flags: self.flags,
+            keys: empty.keys,
+            key_positions: empty.key_positions,
+            flags: self.flags - EnvVarFlags::DICT,
         }
     }
 
+    /// Returns a copy of the variable as a dictionary with new alternating keys and values.
+    pub fn setting_dict(&self, pairs: Vec<WString>) -> Self {
+        Self::new_dict(pairs, self.flags)
+    }
+
     /// Returns a copy of the variable with the export flag changed.
     pub fn setting_exports(&self, export: bool) -> Self {
         let mut flags = self.flags;
         flags.set(EnvVarFlags::EXPORT, export);
         EnvVar {
             values: self.values.clone(),
+            keys: self.keys.clone(),
+            key_positions: self.key_positions.clone(),
             flags,
         }
     }
@@ -231,9 +315,11 @@ pub fn setting_exports(&self, export: bool) -> Self {
     /// Returns a copy of the variable with the path variable flag changed.
     pub fn setting_pathvar(&self, pathvar: bool) -> Self {
         let mut flags = self.flags;
-        flags.set(EnvVarFlags::PATHVAR, pathvar);
+        flags.set(EnvVarFlags::PATHVAR, pathvar && !self.is_dict());
         EnvVar {
             values: self.values.clone(),
+            keys: self.keys.clone(),
+            key_positions: self.key_positions.clone(),
             flags,
         }
     }
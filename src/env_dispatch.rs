@@ -4,6 +4,7 @@
 use crate::env::{EnvMode, EnvStack, Environment as _, setenv_lock, unsetenv_lock};
 use crate::flog::flog;
 use crate::function;
+use crate::history::{HistoryRetention, set_history_retention};
 use crate::input_common::{update_wait_on_escape_ms, update_wait_on_sequence_key_ms};
 use crate::locale::{invalidate_numeric_locale, set_libc_locales};
 use crate::prelude::*;
@@ -15,9 +16,10 @@
 use crate::screen::{IS_DUMB, ONLY_GRAYSCALE, screen_set_midnight_commander_hack};
 use crate::terminal::ColorSupport;
 use crate::tty_handoff::xtversion;
-use crate::wutil::fish_wcstoi;
+use crate::wutil::{fish_wcstoi, fish_wcstoul, parse_duration};
 use fish_wcstringutil::{bool_from_string, string_prefixes_string};
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 /// List of all locale environment variable names that might trigger (re)initializing of the locale
@@ -72,6 +74,18 @@ macro_rules! vars {
         table.add_anon(L!("fish_function_path"), vars!(handle_function_path_change));
         table.add_anon(L!("fish_read_limit"), vars!(handle_read_limit_change));
         table.add_anon(L!("fish_history"), vars!(handle_fish_history_change));
+        table.add_anon(
+            L!("fish_history_max_items"),
+            vars!(handle_history_retention_change),
+        );
+        table.add_anon(
+            L!("fish_history_max_age"),
+            vars!(handle_history_retention_change),
+        );
+        table.add_anon(
+            L!("fish_history_ignore"),
+            vars!(handle_history_retention_change),
+        );
         table.add_anon(
             L!("fish_autosuggestion_enabled"),
             vars!(handle_autosuggestion_change),
@@ -266,6 +280,35 @@ fn handle_fish_history_change(vars: &EnvStack) {
     reader_change_history(&session_id);
 }
 
+fn handle_history_retention_change(vars: &EnvStack) {
+    let max_items = vars
+        .get_unless_empty(L!("fish_history_max_items"))
+        .and_then(|var| {
+            let max_items = fish_wcstoul(&var.as_string())
+                .ok()
+                .and_then(|n| usize::try_from(n).ok())
+                .and_then(NonZeroUsize::new);
+            if max_items.is_none() {
+                flog!(warning, "Ignoring invalid $fish_history_max_items");
+            }
+            max_items
+        });
+    let max_age = vars
+        .get_unless_empty(L!("fish_history_max_age"))
+        .and_then(|var| {
+            let max_age = parse_duration(&var.as_string());
+            if max_age.is_none() {
+                flog!(warning, "Ignoring invalid $fish_history_max_age");
+            }
+            max_age
+        });
+    let ignore_patterns = vars.get(L!("fish_history_ignore"));
+    let ignore_patterns = ignore_patterns
+        .as_ref()
+        .map_or(&[][..], |var| var.as_list());
+    set_history_retention(HistoryRetention::new(max_items, max_age, ignore_patterns));
+}
+
 fn handle_fish_cursor_selection_mode_change(vars: &EnvStack) {
     use crate::reader::CursorSelectionMode;
 
@@ -397,6 +440,7 @@ fn run_inits(vars: &EnvStack) {
     update_wait_on_escape_ms(vars);
     update_wait_on_sequence_key_ms(vars);
     handle_read_limit_change(vars);
+    handle_history_retention_change(vars);
     handle_fish_use_posix_spawn_change(vars);
     handle_fish_trace(vars);
 }
@@ -322,14 +322,17 @@ fn serialize_with_vars(vars: &VarTable) -> Vec<u8> {
             // Preserve legacy behavior by sorting the values first
             .sorted_by_key(|(k, _)| *k)
             .for_each(|(k, v)| {
+                // Dictionaries are stored as alternating keys and values.
+                let pairs;
+                let vals = if v.is_dict() {
+                    pairs = v.dict_pairs();
+                    &pairs
+                } else {
+                    v.as_list()
+                };
                 // Append the entry. Note that append_file_entry may fail,
                 // but that only affects one variable; soldier on.
-                append_file_entry(
-                    v.get_flags(),
-                    k,
-                    &encode_serialized(v.as_list()),
-                    &mut contents,
-                );
+                append_file_entry(v.get_flags(), k, &encode_serialized(vals), &mut contents);
             });
 
         contents
@@ -562,6 +565,8 @@ fn parse_message_30_internal(msg: &wstr, vars: &mut VarTable, storage: &mut WStr
                 flags |= EnvVarFlags::EXPORT;
             } else if r#match(&mut cursor, f3::PATH) {
                 flags |= EnvVarFlags::PATHVAR;
+            } else if r#match(&mut cursor, f3::DICT) {
+                flags |= EnvVarFlags::DICT;
             } else {
                 // Skip this unknown flag, for future proofing.
                 while !cursor.is_empty() && !matches!(cursor.char_at(0), ' ' | '\t') {
@@ -653,6 +658,7 @@ mod fish3_uvars {
     pub const SETUVAR: &[u8] = b"SETUVAR";
     pub const EXPORT: &[u8] = b"--export";
     pub const PATH: &[u8] = b"--path";
+    pub const DICT: &[u8] = b"--dict";
 }
 
 /// Return the default variable path, or an empty string on failure.
@@ -742,6 +748,10 @@ fn append_file_entry(
         result.extend_from_slice(f3::PATH);
         result.push(b' ');
     }
+    if flags.contains(EnvVarFlags::DICT) {
+        result.extend_from_slice(f3::DICT);
+        result.push(b' ');
+    }
 
     // Append variable name like "fish_color_cwd".
     if !valid_var_name(key_in) {
@@ -932,6 +942,18 @@ fn test_universal_output() {
                 EnvVarFlags::empty(),
             ),
         );
+        vars.insert(
+            L!("varG").to_owned(),
+            EnvVar::new_dict(
+                vec![
+                    L!("key1").to_owned(),
+                    L!("ValG1").to_owned(),
+                    L!("key2").to_owned(),
+                    L!("ValG2").to_owned(),
+                ],
+                flag_export,
+            ),
+        );
 
         let text = EnvUniversal::serialize_with_vars(&vars);
         let expected = concat!(
@@ -943,6 +965,7 @@ fn test_universal_output() {
             "SETUVAR --export --path varD:ValD1\n",
             "SETUVAR --path varE:ValE1\\x1eValE2\n",
             "SETUVAR varF:\\xfc\n",
+            "SETUVAR --export --dict varG:key1\\x1eValG1\\x1ekey2\\x1eValG2\n",
         )
         .as_bytes();
         assert_eq!(text, expected);
@@ -960,6 +983,7 @@ fn test_universal_parsing() {
             "SETUVAR --nonsenseflag varC:ValC1\n",
             "SETUVAR --export --path varD:ValD1\n",
             "SETUVAR --path --path varE:ValE1\\x1eValE2\n",
+            "SETUVAR --dict varF:key1\\x1eValF1\\x1ekey2\\x1eValF2\n",
         )
         .as_bytes();
 
@@ -994,6 +1018,18 @@ fn test_universal_parsing() {
                 flag_pathvar,
             ),
         );
+        vars.insert(
+            L!("varF").to_owned(),
+            EnvVar::new_dict(
+                vec![
+                    L!("key1").to_owned(),
+                    L!("ValF1").to_owned(),
+                    L!("key2").to_owned(),
+                    L!("ValF2").to_owned(),
+                ],
+                EnvVarFlags::empty(),
+            ),
+        );
 
         let mut parsed_vars = VarTable::new();
         EnvUniversal::populate_variables(input, &mut parsed_vars);
@@ -3,9 +3,10 @@
 // Some of the code in this file is based on code from the Glibc manual, though the changes
 // performed have been massive.
 
+use crate::ast::{self, Node as _};
 use crate::builtins::shared::{
     ErrorCode, STATUS_CMD_ERROR, STATUS_CMD_UNKNOWN, STATUS_NOT_EXECUTABLE, STATUS_READ_TOO_MUCH,
-    builtin_run,
+    builtin_exists, builtin_run,
 };
 use crate::common::{
     ScopeGuard, bytes2wcstring, exit_without_destructors, truncate_at_nul, wcs2bytes, wcs2zstring,
@@ -14,6 +15,7 @@
 use crate::env::{EnvMode, EnvSetMode, EnvStack, Environment as _, READ_BYTE_LIMIT, Statuses};
 #[cfg(have_posix_spawn)]
 use crate::env_dispatch::use_posix_spawn;
+use crate::expand::{ExpandFlags, expand_one};
 use crate::fds::{
     BorrowedFdFile, PIPE_ERROR, make_autoclose_pipes, make_fd_blocking, open_cloexec,
 };
@@ -33,6 +35,7 @@
 };
 use crate::nix::{getpid, isatty};
 use crate::null_terminated_array::OwningNullTerminatedArray;
+use crate::parse_constants::{ParseTreeFlags, StatementDecoration};
 use crate::parser::{Block, BlockId, BlockType, EvalRes, Parser, ParserEnvSetMode};
 use crate::prelude::*;
 use crate::proc::Pid;
@@ -56,6 +59,7 @@
 use nix::sys::stat;
 use nix::unistd::getpgrp;
 use std::ffi::CStr;
+use std::fs::File;
 use std::io::{Read as _, Write as _};
 use std::mem::MaybeUninit;
 use std::num::NonZeroU32;
@@ -64,6 +68,7 @@
 use std::sync::{
     Arc, OnceLock,
     atomic::{AtomicUsize, Ordering},
+    mpsc,
 };
 
 /// The singleton shared exec thread pool.
@@ -234,7 +239,8 @@ pub fn exec_job(parser: &Parser, job: &Job, block_io: IoChain) -> bool {
 
     // If exec_error then a backgrounded job would have been terminated before it was ever assigned
     // a pgroup, so error out before setting last_pid.
-    if !job.is_foreground() {
+    // Process substitutions are not visible as jobs, so they don't set it either.
+    if !job.is_foreground() && !job.is_process_substitution() {
         if let Some(last_pid) = job.get_last_pid() {
             parser.set_one(
                 L!("last_pid"),
@@ -306,6 +312,151 @@ pub fn exec_subshell_for_expand(
     if break_expand { ret } else { Ok(()) }
 }
 
+/// A process substitution, `<(cmd)` or `>(cmd)`, which the job using it reads from or writes to as
+/// `/dev/fd/N`.
+pub struct ProcessSubstitution {
+    /// Our end of the pipe. It is passed to the job under its own fd number.
+    io: Arc<IoPipe>,
+
+    /// For a `>(cmd)` whose command runs within fish: the command, and the data written by the
+    /// job, which the command reads once the job is done.
+    deferred: Option<(WString, mpsc::Receiver<Vec<u8>>)>,
+}
+
+/// Set up the process substitution `cmd`, which is `>(cmd)` if `is_output` is set, and `<(cmd)`
+/// otherwise. It stays open until the current job is done. Return the fd for the job to use.
+///
+/// A command consisting of external processes runs in the background, concurrently with the job.
+/// Other commands cannot, because fish only runs one thing at a time: For `<(cmd)` the output is
+/// collected before the job starts, and for `>(cmd)` the command only runs once the job is done.
+pub fn exec_process_substitution(
+    cmd: &wstr,
+    is_output: bool,
+    parser: &Parser,
+) -> Result<RawFd, ErrorCode> {
+    let pipes = make_autoclose_pipes().map_err(|_| STATUS_CMD_ERROR)?;
+    let (ours, theirs) = if is_output {
+        (pipes.write, pipes.read)
+    } else {
+        (pipes.read, pipes.write)
+    };
+    let fd = ours.as_raw_fd();
+    let mut deferred = None;
+    if process_substitution_is_external(cmd, parser) {
+        let target = if is_output {
+            STDIN_FILENO
+        } else {
+            STDOUT_FILENO
+        };
+        let mut io_chain = IoChain::new();
+        io_chain.push(Arc::new(IoPipe::new(target, is_output, theirs)));
+        run_process_substitution(cmd, parser, &io_chain, true);
+    } else if is_output {
+        let (sender, receiver) = mpsc::channel();
+        exec_thread_pool().perform(move || {
+            let mut data = vec![];
+            let _ = File::from(theirs).read_to_end(&mut data);
+            let _ = sender.send(data);
+        });
+        deferred = Some((cmd.to_owned(), receiver));
+    } else {
+        let bufferfill = IoBufferfill::create().map_err(|_| STATUS_CMD_ERROR)?;
+        let mut io_chain = IoChain::new();
+        io_chain.push(bufferfill.clone());
+        run_process_substitution(cmd, parser, &io_chain, false);
+        drop(io_chain);
+        let data = IoBufferfill::finish(bufferfill).newline_serialized();
+        exec_thread_pool().perform(move || {
+            let _ = write_loop(&theirs, &data);
+        });
+    }
+    parser
+        .libdata_mut()
+        .process_substitutions
+        .push(ProcessSubstitution {
+            io: Arc::new(IoPipe::new(fd, !is_output, ours)),
+            deferred,
+        });
+    Ok(fd)
+}
+
+/// Add the fds of process substitutions to the io chain of the job using them.
+pub fn add_process_substitutions(io_chain: &mut IoChain, psubs: &[ProcessSubstitution]) {
+    for psub in psubs {
+        io_chain.push(psub.io.clone());
+    }
+}
+
+/// Close process substitutions once the job using them is done, and run the commands of
+/// those that were deferred.
+pub fn finish_process_substitutions(parser: &Parser, psubs: Vec<ProcessSubstitution>) {
+    for ProcessSubstitution { io, deferred } in psubs {
+        drop(io);
+        let Some((cmd, receiver)) = deferred else {
+            continue;
+        };
+        let data = receiver.recv().unwrap_or_default();
+        let Ok(pipes) = make_autoclose_pipes() else {
+            continue;
+        };
+        exec_thread_pool().perform(move || {
+            let _ = write_loop(&pipes.write, &data);
+        });
+        let mut io_chain = IoChain::new();
+        io_chain.push(Arc::new(IoPipe::new(STDIN_FILENO, true, pipes.read)));
+        run_process_substitution(&cmd, parser, &io_chain, false);
+    }
+}
+
+/// Run the command of a process substitution, leaving $status alone. If `background` is set,
+/// the command's job is not waited for.
+fn run_process_substitution(cmd: &wstr, parser: &Parser, io_chain: &IoChain, background: bool) {
+    let _scoped = parser.push_scope(|s| {
+        s.is_subshell = true;
+        s.is_process_substitution = background;
+    });
+    let prev_statuses = parser.get_last_statuses();
+    parser.eval_with(cmd, io_chain, None, BlockType::subst, false);
+    parser.set_last_statuses(prev_statuses);
+}
+
+/// Return whether `cmd` is a single job made up only of external commands, which can run in the
+/// background.
+fn process_substitution_is_external(cmd: &wstr, parser: &Parser) -> bool {
+    let ast = ast::parse(cmd, ParseTreeFlags::default(), None);
+    if ast.errored() {
+        return false;
+    }
+    let [job_conjunction] = &ast.top()[..] else {
+        return false;
+    };
+    let job = &job_conjunction.job;
+    if job_conjunction.decorator.is_some()
+        || !job_conjunction.continuations.is_empty()
+        || job.time.is_some()
+        || job.bg.is_some()
+    {
+        return false;
+    }
+    let ctx = parser.context();
+    std::iter::once(&job.statement)
+        .chain(job.continuation.iter().map(|c| &c.statement))
+        .all(|statement| {
+            let ast::Statement::Decorated(statement) = statement else {
+                return false;
+            };
+            match statement.decoration() {
+                StatementDecoration::Command => return true,
+                StatementDecoration::Builtin | StatementDecoration::Exec => return false,
+                StatementDecoration::None => (),
+            }
+            let mut name = statement.command.source(cmd).to_owned();
+            expand_one(&mut name, ExpandFlags::FAIL_ON_CMDSUBST, &ctx, None)
+                && !function::exists(&name, parser)
+                && !builtin_exists(&name)
+        })
+}
+
 /// Number of calls to fork() or posix_spawn().
 static FORK_COUNT: AtomicUsize = AtomicUsize::new(0);
 
@@ -992,8 +1143,10 @@ fn function_prepare_environment(
     fb
 }
 
-// Given that we are done executing a function, restore the environment.
-fn function_restore_environment(parser: &Parser, block: BlockId) {
+// Given that we are done executing a function, run its deferred commands and restore the
+// environment.
+fn function_restore_environment(parser: &Parser, block: BlockId, io_chain: &IoChain) {
+    parser.run_deferred_commands(block, io_chain);
     parser.pop_block(block);
 
     // If we returned due to a return statement, then stop returning now.
@@ -1061,7 +1214,7 @@ fn get_performer_for_function(
             BlockType::top,
             false,
         );
-        function_restore_environment(parser, fb);
+        function_restore_environment(parser, fb, &io_chain);
 
         // If the function did not execute anything, treat it as success.
         if res.was_empty {
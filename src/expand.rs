@@ -13,7 +13,7 @@
 };
 use crate::complete::{CompleteFlags, Completion, CompletionList, CompletionReceiver};
 use crate::env::{EnvVar, Environment};
-use crate::exec::exec_subshell_for_expand;
+use crate::exec::{exec_process_substitution, exec_subshell_for_expand};
 use crate::future_feature_flags::{FeatureFlag, feature_test};
 use crate::history::{History, history_session_id};
 use crate::operation_context::OperationContext;
@@ -648,8 +648,24 @@ fn expand_variables(
     let mut all_values = true;
     let slice_start = var_name_stop;
     let mut var_idx_list = vec![];
+    let mut dict_key = None;
 
-    if instr.as_char_slice().get(slice_start) == Some(&'[') {
+    let is_dict = var.as_ref().is_some_and(|var| var.is_dict());
+    if is_dict && instr.as_char_slice().get(slice_start) == Some(&'[') {
+        all_values = false;
+        // A dictionary is indexed by a single key, which is everything up to the closing bracket.
+        let Some(len) = instr[slice_start..].find_char(']') else {
+            append_syntax_error!(errors, slice_start, "Invalid index value");
+            return ExpandResult::make_error(STATUS_EXPAND_ERROR);
+        };
+        var_name_and_slice_stop = slice_start + len + 1;
+        dict_key = Some(
+            instr[slice_start + 1..slice_start + len]
+                .chars()
+                .filter(|&c| c != INTERNAL_SEPARATOR)
+                .collect::<WString>(),
+        );
+    } else if instr.as_char_slice().get(slice_start) == Some(&'[') {
         all_values = false;
         // If a variable is missing, behave as though we have one value, so that $var[1] always
         // works.
@@ -727,6 +743,8 @@ fn expand_variables(
                     var_item_list.push(item.clone());
                 }
             }
+        } else if let Some(key) = dict_key {
+            var_item_list.extend(var.as_ref().unwrap().dict_get(&key).cloned());
         } else {
             let all_var_items = var.as_ref().unwrap().as_list();
             for item_index in var_idx_list {
@@ -947,6 +965,27 @@ pub fn expand_cmdsubst(
         MaybeParentheses::CommandSubstitution(parens) => parens,
     };
 
+    // A process substitution like <(cmd) or >(cmd) becomes the path of a pipe connected to cmd.
+    if parens.start() == 1
+        && matches!(input.char_at(0), '<' | '>')
+        && feature_test(FeatureFlag::ProcessSubstitution)
+    {
+        let is_output = input.char_at(0) == '>';
+        let Ok(fd) = exec_process_substitution(&input[parens.command()], is_output, ctx.parser())
+        else {
+            append_cmdsub_error!(
+                errors,
+                0,
+                parens.end() - 1,
+                "Unable to create a pipe for process substitution"
+            );
+            return ExpandResult::make_error(STATUS_CMD_ERROR);
+        };
+        let mut path = sprintf!("/dev/fd/%d", fd);
+        path.push_utfstr(&input[parens.end()..]);
+        return expand_cmdsubst(path, ctx, out, errors);
+    }
+
     let mut sub_res = vec![];
     let job_group = ctx.job_group.clone();
     let subshell_status = exec_subshell_for_expand(
@@ -39,6 +39,12 @@ pub enum FeatureFlag {
 
     /// Do not try to work around incompatible terminal.
     OmitTermWorkarounds,
+
+    /// Whether `<(cmd)` and `>(cmd)` are process substitutions.
+    ProcessSubstitution,
+
+    /// Whether `try` and `catch` are keywords.
+    TryCatch,
 }
 
 struct Features {
@@ -153,6 +159,22 @@ pub struct FeatureMetadata {
         default_value: false,
         read_only: false,
     },
+    FeatureMetadata {
+        flag: FeatureFlag::ProcessSubstitution,
+        name: L!("process-substitution"),
+        groups: L!("4.6"),
+        description: L!("<(cmd) and >(cmd) are process substitutions"),
+        default_value: false,
+        read_only: false,
+    },
+    FeatureMetadata {
+        flag: FeatureFlag::TryCatch,
+        name: L!("try-catch"),
+        groups: L!("4.6"),
+        description: L!("try and catch are keywords for error handling blocks"),
+        default_value: false,
+        read_only: false,
+    },
 ];
 
 thread_local!(
@@ -0,0 +1,501 @@
+//! Parsing of the `--help` output of commands into completions. This backs `complete --from-help`.
+//!
+//! The layouts of GNU tools, clap, argparse, cobra and Go's flag package are understood: options
+//! are lines starting with a dash, with their description after two spaces or a tab, or on the
+//! next line. Subcommands are listed in sections with a title like "Commands:".
+
+use crate::complete::CompleteOptionType;
+use crate::prelude::*;
+
+/// An option found in help output.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HelpOption {
+    /// The names of the option without dashes, like `v` or `verbose`, and how they are written.
+    pub names: Vec<(WString, CompleteOptionType)>,
+    /// Whether the option takes an argument which is not optional.
+    pub requires_param: bool,
+    /// The possible values of the argument, if they are listed.
+    pub values: Vec<WString>,
+    pub description: WString,
+}
+
+/// A subcommand found in help output.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HelpSubcommand {
+    pub name: WString,
+    pub description: WString,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedHelp {
+    pub options: Vec<HelpOption>,
+    pub subcommands: Vec<HelpSubcommand>,
+}
+
+/// Parse the lines of help output.
+pub fn parse_help(lines: &[WString]) -> ParsedHelp {
+    let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    let mut parsed = ParsedHelp::default();
+    let mut in_commands = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let text = line.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if text.starts_with('-') {
+            let Some(mut option) = parse_option(text) else {
+                continue;
+            };
+            if option.description.is_empty() {
+                // The description may be on the next line, indented further.
+                if let Some(next) = lines.get(i + 1) {
+                    let next_indent = next.len() - next.trim_start().len();
+                    let next = next.trim();
+                    if next_indent > indent && !next.starts_with('-') {
+                        option.description = clean_description(next, &mut option.values);
+                    }
+                }
+            }
+            let seen = parsed
+                .options
+                .iter()
+                .any(|o| o.names.iter().any(|name| option.names.contains(name)));
+            if !seen {
+                parsed.options.push(option);
+            }
+        } else if indent == 0 {
+            // Section titles decide whether indented lines are subcommands. Titles which don't end
+            // in a colon, like those git uses to group its commands, don't end the section.
+            let title = text.to_lowercase();
+            if title.contains("commands") && !title.starts_with("usage") {
+                in_commands = true;
+            } else if text.ends_with(':') {
+                in_commands = false;
+            }
+        } else if let Some(values) = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+            // argparse lists its subcommands like `{start,stop}`, then describes them below.
+            for name in values.split(',') {
+                add_subcommand(&mut parsed.subcommands, name, "");
+            }
+            in_commands = true;
+        } else if in_commands {
+            let (name, description) = split_description(text);
+            let is_name = name.starts_with(char::is_alphanumeric)
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+            // A word followed by text after a single space is prose, not a subcommand.
+            if is_name && (description.is_empty() || text.len() > name.len() + 1) {
+                let description = clean_description(description, &mut vec![]);
+                add_subcommand(&mut parsed.subcommands, name, &description.to_string());
+            }
+        }
+    }
+    parsed
+}
+
+fn add_subcommand(subcommands: &mut Vec<HelpSubcommand>, name: &str, description: &str) {
+    let name = WString::from_str(name);
+    match subcommands.iter_mut().find(|s| s.name == name) {
+        Some(existing) => {
+            if existing.description.is_empty() {
+                existing.description = WString::from_str(description);
+            }
+        }
+        None => subcommands.push(HelpSubcommand {
+            name,
+            description: WString::from_str(description),
+        }),
+    }
+}
+
+/// Split a line into what it describes and its description, which follows after a tab or at
+/// least two spaces.
+fn split_description(text: &str) -> (&str, &str) {
+    let end = [text.find('\t'), text.find("  ")]
+        .into_iter()
+        .flatten()
+        .min();
+    match end {
+        Some(end) => (&text[..end], text[end..].trim()),
+        None => (text, ""),
+    }
+}
+
+/// Parse a line describing options, like `-o, --output=FILE  Write to FILE`.
+fn parse_option(text: &str) -> Option<HelpOption> {
+    let (spec, description) = split_description(text);
+    let mut option = HelpOption {
+        names: vec![],
+        requires_param: false,
+        values: vec![],
+        description: WString::new(),
+    };
+    let mut description = description;
+    let mut had_metavar = false;
+
+    let words = spec
+        .split(' ')
+        .map(|word| word.trim_end_matches(','))
+        .filter(|word| !word.is_empty());
+    for word in words {
+        if let Some(unprefixed) = word.strip_prefix('-') {
+            let (dashes, unprefixed) = match unprefixed.strip_prefix('-') {
+                Some(long) => (2, long),
+                None => (1, unprefixed),
+            };
+            let unprefixed = unprefixed.strip_suffix("...").unwrap_or(unprefixed);
+            let mut name_len = unprefixed
+                .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(unprefixed.len());
+            if name_len == 0 && dashes == 1 {
+                // Short options like `-?`.
+                name_len = unprefixed.chars().next().map_or(0, char::len_utf8);
+            }
+            if name_len == 0 {
+                continue;
+            }
+            let (name, rest) = unprefixed.split_at(name_len);
+            let typ = match (dashes, name.chars().count()) {
+                (2, _) => CompleteOptionType::DoubleLong,
+                (_, 1) => CompleteOptionType::Short,
+                _ => CompleteOptionType::SingleLong,
+            };
+            option.names.push((WString::from_str(name), typ));
+            had_metavar = false;
+            // An attached argument, like `--color=WHEN`, `--color[=WHEN]` or `-c<FILE>`.
+            if let Some(metavar) = rest.strip_prefix('=').or_else(|| rest.strip_prefix('<')) {
+                option.requires_param = true;
+                parse_values(metavar, &mut option.values);
+                had_metavar = true;
+            } else if rest.starts_with("[=") {
+                had_metavar = true;
+            }
+        } else if !option.names.is_empty() && !had_metavar {
+            // A separate argument, like `-o FILE` or `--level {1,2,3}`.
+            if !word.starts_with('[') {
+                option.requires_param = true;
+            }
+            parse_values(word, &mut option.values);
+            had_metavar = true;
+        } else if !option.names.is_empty() {
+            // More words are the description, separated by a single space.
+            let offset = word.as_ptr() as usize - spec.as_ptr() as usize;
+            description = text[offset..].trim();
+            break;
+        } else {
+            return None;
+        }
+    }
+
+    if option.names.is_empty() {
+        return None;
+    }
+    option.description = clean_description(description, &mut option.values);
+    Some(option)
+}
+
+/// Collect the values of an argument given like `{a,b,c}`.
+fn parse_values(metavar: &str, values: &mut Vec<WString>) {
+    let metavar = metavar.trim_start_matches('=');
+    if let Some(list) = metavar.strip_prefix('{').and_then(|m| m.strip_suffix('}')) {
+        values.extend(
+            list.split(',')
+                .filter(|value| !value.is_empty())
+                .map(WString::from_str),
+        );
+    }
+}
+
+/// Shorten a description to its first sentence or line, without the notes clap appends in
+/// brackets.
+/// Possible values listed by clap are collected into `values`.
+fn clean_description(description: &str, values: &mut Vec<WString>) -> WString {
+    let mut description = description.to_owned();
+    while let Some(start) = description.find(" [") {
+        let note = &description[start + 2..];
+        let Some(end) = note.find(']') else {
+            break;
+        };
+        if !["default:", "env:", "aliases:", "possible values:"]
+            .iter()
+            .any(|prefix| note.starts_with(prefix))
+        {
+            break;
+        }
+        if let Some(list) = note[..end].strip_prefix("possible values:") {
+            if values.is_empty() {
+                values.extend(list.split(',').map(|v| WString::from_str(v.trim())));
+            }
+        }
+        description.replace_range(start..start + 2 + end + 1, "");
+    }
+    if let Some(end) = description.find(". ") {
+        description.truncate(end);
+    }
+    let description = description.trim().trim_end_matches(['.', ',', ';', ':']);
+    WString::from_str(description.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HelpOption, HelpSubcommand, parse_help};
+    use crate::complete::CompleteOptionType::{DoubleLong, Short, SingleLong};
+    use crate::prelude::*;
+
+    fn parse(help: &str) -> super::ParsedHelp {
+        let lines: Vec<WString> = help.lines().map(WString::from_str).collect();
+        parse_help(&lines)
+    }
+
+    fn option(
+        names: &[(&str, crate::complete::CompleteOptionType)],
+        requires_param: bool,
+        values: &[&str],
+        description: &str,
+    ) -> HelpOption {
+        HelpOption {
+            names: names
+                .iter()
+                .map(|(name, typ)| (WString::from_str(name), *typ))
+                .collect(),
+            requires_param,
+            values: values.iter().map(|v| WString::from_str(v)).collect(),
+            description: WString::from_str(description),
+        }
+    }
+
+    fn subcommand(name: &str, description: &str) -> HelpSubcommand {
+        HelpSubcommand {
+            name: WString::from_str(name),
+            description: WString::from_str(description),
+        }
+    }
+
+    #[test]
+    fn test_parse_gnu_help() {
+        let parsed = parse(
+            "Usage: ls [OPTION]... [FILE]...
+List information about the FILEs (the current directory by default).
+
+Mandatory arguments to long options are mandatory for short options too.
+  -a, --all                  do not ignore entries starting with .
+      --block-size=SIZE      with -l, scale sizes by SIZE when printing them;
+                               e.g., '--block-size=M'; see SIZE format below
+      --color[=WHEN]         color the output WHEN; more info below
+  -w, --width=COLS           set output width to COLS.  0 means no limit
+      --help        display this help and exit
+",
+        );
+        assert_eq!(
+            parsed.options,
+            vec![
+                option(
+                    &[("a", Short), ("all", DoubleLong)],
+                    false,
+                    &[],
+                    "do not ignore entries starting with"
+                ),
+                option(
+                    &[("block-size", DoubleLong)],
+                    true,
+                    &[],
+                    "with -l, scale sizes by SIZE when printing them"
+                ),
+                option(
+                    &[("color", DoubleLong)],
+                    false,
+                    &[],
+                    "color the output WHEN; more info below"
+                ),
+                option(
+                    &[("w", Short), ("width", DoubleLong)],
+                    true,
+                    &[],
+                    "set output width to COLS"
+                ),
+                option(
+                    &[("help", DoubleLong)],
+                    false,
+                    &[],
+                    "display this help and exit"
+                ),
+            ]
+        );
+        assert!(parsed.subcommands.is_empty());
+    }
+
+    #[test]
+    fn test_parse_clap_help() {
+        let parsed = parse(
+            "A build tool
+
+Usage: tool [OPTIONS] <COMMAND>
+
+Commands:
+  build  Compile the project
+  help   Print this message or the help of the given subcommand(s)
+
+Options:
+  -v, --verbose...
+          Increase verbosity
+  -c, --config <FILE>
+          Sets a config file [default: tool.toml]
+      --mode <MODE>  How to build [possible values: debug, release]
+  -h, --help
+          Print help (see a summary with '-h')
+",
+        );
+        assert_eq!(
+            parsed.subcommands,
+            vec![
+                subcommand("build", "Compile the project"),
+                subcommand(
+                    "help",
+                    "Print this message or the help of the given subcommand(s)"
+                ),
+            ]
+        );
+        assert_eq!(
+            parsed.options,
+            vec![
+                option(
+                    &[("v", Short), ("verbose", DoubleLong)],
+                    false,
+                    &[],
+                    "Increase verbosity"
+                ),
+                option(
+                    &[("c", Short), ("config", DoubleLong)],
+                    true,
+                    &[],
+                    "Sets a config file"
+                ),
+                option(
+                    &[("mode", DoubleLong)],
+                    true,
+                    &["debug", "release"],
+                    "How to build"
+                ),
+                option(
+                    &[("h", Short), ("help", DoubleLong)],
+                    false,
+                    &[],
+                    "Print help (see a summary with '-h')"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_argparse_help() {
+        let parsed = parse(
+            "usage: prog [-h] [-o OUTPUT] [--level {1,2,3}] {start,stop} ...
+
+positional arguments:
+  {start,stop}
+    start               Start the service
+    stop                Stop the service
+
+options:
+  -h, --help            show this help message and exit
+  -o OUTPUT, --output OUTPUT
+                        write to OUTPUT
+  --level {1,2,3}       how loud to be
+",
+        );
+        assert_eq!(
+            parsed.subcommands,
+            vec![
+                subcommand("start", "Start the service"),
+                subcommand("stop", "Stop the service"),
+            ]
+        );
+        assert_eq!(
+            parsed.options,
+            vec![
+                option(
+                    &[("h", Short), ("help", DoubleLong)],
+                    false,
+                    &[],
+                    "show this help message and exit"
+                ),
+                option(
+                    &[("o", Short), ("output", DoubleLong)],
+                    true,
+                    &[],
+                    "write to OUTPUT"
+                ),
+                option(
+                    &[("level", DoubleLong)],
+                    true,
+                    &["1", "2", "3"],
+                    "how loud to be"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_go_help() {
+        // Go's flag package.
+        let parsed = parse(
+            "Usage of tool:
+  -addr string
+    \tlisten on this address (default \":8080\")
+  -v\tbe verbose
+",
+        );
+        assert_eq!(
+            parsed.options,
+            vec![
+                option(
+                    &[("addr", SingleLong)],
+                    true,
+                    &[],
+                    "listen on this address (default \":8080\")"
+                ),
+                option(&[("v", Short)], false, &[], "be verbose"),
+            ]
+        );
+
+        // Cobra.
+        let parsed = parse(
+            "Usage:
+  tool [command]
+
+Available Commands:
+  completion  Generate the autocompletion script for the specified shell
+  serve       Start the server
+
+Flags:
+  -h, --help          help for tool
+      --port int      port to listen on
+",
+        );
+        assert_eq!(
+            parsed.subcommands,
+            vec![
+                subcommand(
+                    "completion",
+                    "Generate the autocompletion script for the specified shell"
+                ),
+                subcommand("serve", "Start the server"),
+            ]
+        );
+        assert_eq!(
+            parsed.options,
+            vec![
+                option(
+                    &[("h", Short), ("help", DoubleLong)],
+                    false,
+                    &[],
+                    "help for tool"
+                ),
+                option(&[("port", DoubleLong)], true, &[], "port to listen on"),
+            ]
+        );
+    }
+}
@@ -156,6 +156,10 @@ pub fn test_redirection_target(&self, target: &wstr, mode: RedirectionMode) -> F
                     _ => Err(IsErr),
                 }
             }
+            RedirectionMode::HereString | RedirectionMode::HereDoc => {
+                // The target is text, not a file.
+                Ok(IsFile(false))
+            }
             RedirectionMode::Input | RedirectionMode::TryInput => {
                 // Input redirections must have a readable non-directory.
                 // Note we color "try_input" files as errors if they are invalid,
@@ -20,13 +20,15 @@
     ParseKeyword, ParseTokenType, ParseTreeFlags, SourceRange, StatementDecoration,
 };
 use crate::parse_util::{
-    MaybeParentheses, get_process_first_token_offset, locate_cmdsubst_range, slice_length,
+    MaybeParentheses, Parentheses, get_process_first_token_offset, has_cmdsub,
+    locate_cmdsubst_range, slice_length,
 };
 use crate::path::{path_as_implicit_cd, path_get_cdpath, path_get_path, paths_are_same_file};
+use crate::redirection::RedirectionMode;
 use crate::terminal::Outputter;
 use crate::text_face::{SpecifiedTextFace, TextFace, UnderlineStyle, parse_text_face};
 use crate::threads::assert_is_background_thread;
-use crate::tokenizer::{PipeOrRedir, variable_assignment_equals_pos};
+use crate::tokenizer::{Heredoc, PipeOrRedir, variable_assignment_equals_pos};
 use fish_color::Color;
 use fish_common::{ASCII_MAX, EXPAND_RESERVED_BASE, EXPAND_RESERVED_END};
 use fish_wcstringutil::string_prefixes_string;
@@ -683,6 +685,40 @@ enum Mode {
     }
 }
 
+/// Colors the body of an expanding heredoc, which works like a double-quoted string without the
+/// quotes. Returns the command substitutions within it, as pairs of an offset and the parentheses
+/// relative to that offset.
+fn color_heredoc_body(body: &wstr, colors: &mut [HighlightSpec]) -> Vec<(usize, Parentheses)> {
+    colors.fill(HighlightSpec::with_fg(HighlightRole::quote));
+    let mut cmdsubs = vec![];
+    let mut in_pos = 0;
+    while in_pos < body.len() {
+        let next = body.char_at(in_pos + 1);
+        match body.char_at(in_pos) {
+            '\\' if matches!(next, '$' | '\\' | '\n') => {
+                colors[in_pos..in_pos + 2].fill(HighlightSpec::with_fg(HighlightRole::escape));
+                in_pos += 2;
+            }
+            '$' if next == '(' => {
+                colors[in_pos] = HighlightSpec::with_fg(HighlightRole::operat);
+                in_pos += 1;
+                if let MaybeParentheses::CommandSubstitution(parens) =
+                    locate_cmdsubst_range(&body[in_pos..], &mut 0, true, None, None)
+                {
+                    let end = in_pos + parens.end();
+                    cmdsubs.push((in_pos, parens));
+                    in_pos = end;
+                }
+            }
+            '$' if next == '$' || valid_var_name_char(next) => {
+                in_pos += color_variable(&body[in_pos..], &mut colors[in_pos..]);
+            }
+            _ => in_pos += 1,
+        }
+    }
+    cmdsubs
+}
+
 pub type ColorArray = Vec<HighlightSpec>;
 
 /// Syntax highlighter helper.
@@ -757,8 +793,13 @@ pub fn highlight(&mut self) -> ColorArray {
             return std::mem::take(&mut self.color_array);
         }
 
-        // Color every comment.
+        // Color every heredoc body.
         let extras = &ast.extras;
+        for heredoc in &extras.heredocs {
+            self.color_heredoc(heredoc);
+        }
+
+        // Color every comment.
         for range in &extras.comments {
             self.color_range(*range, HighlightSpec::with_fg(HighlightRole::comment));
         }
@@ -834,32 +875,56 @@ fn color_as_argument(&mut self, node: &dyn ast::Node, options_allowed: bool /* =
             Some(&mut is_quoted),
             None,
         ) {
-            // Highlight the parens. The open parens must exist; the closed paren may not if it was
-            // incomplete.
             assert!(parens.start() < arg_str.len());
-            self.color_array[arg_start..][parens.opening()]
-                .fill(HighlightSpec::with_fg(HighlightRole::operat));
-            self.color_array[arg_start..][parens.closing()]
-                .fill(HighlightSpec::with_fg(HighlightRole::operat));
-
-            // Highlight it recursively.
-            let arg_cursor = self
-                .cursor
-                .map(|c| c.wrapping_sub(arg_start + parens.start()));
-            let cmdsub_contents = &arg_str[parens.command()];
-            let mut cmdsub_highlighter = Highlighter::new(
-                cmdsub_contents,
-                arg_cursor,
-                self.ctx,
-                self.working_directory.clone(),
-                self.io_still_ok(),
-            );
-            let subcolors = cmdsub_highlighter.highlight();
+            self.color_cmdsub(arg_start, &parens);
+        }
+    }
 
-            // Copy out the subcolors back into our array.
-            assert_eq!(subcolors.len(), cmdsub_contents.len());
-            self.color_array[arg_start..][parens.command()].copy_from_slice(&subcolors);
+    // Color a command substitution, given the parentheses relative to an offset into our buffer.
+    fn color_cmdsub(&mut self, offset: usize, parens: &Parentheses) {
+        // Highlight the parens. The open parens must exist; the closed paren may not if it was
+        // incomplete.
+        self.color_array[offset..][parens.opening()]
+            .fill(HighlightSpec::with_fg(HighlightRole::operat));
+        self.color_array[offset..][parens.closing()]
+            .fill(HighlightSpec::with_fg(HighlightRole::operat));
+
+        // Highlight it recursively.
+        let cmdsub_cursor = self.cursor.map(|c| c.wrapping_sub(offset + parens.start()));
+        let buff = self.buff;
+        let cmdsub_contents = &buff[offset..][parens.command()];
+        let mut cmdsub_highlighter = Highlighter::new(
+            cmdsub_contents,
+            cmdsub_cursor,
+            self.ctx,
+            self.working_directory.clone(),
+            self.io_still_ok(),
+        );
+        let subcolors = cmdsub_highlighter.highlight();
+
+        // Copy out the subcolors back into our array.
+        assert_eq!(subcolors.len(), cmdsub_contents.len());
+        self.color_array[offset..][parens.command()].copy_from_slice(&subcolors);
+    }
+
+    // Color the body of a heredoc, and the line ending it like its delimiter.
+    fn color_heredoc(&mut self, heredoc: &Heredoc) {
+        let body = heredoc.body;
+        if heredoc.expand {
+            let cmdsubs = color_heredoc_body(
+                self.get_source(body),
+                &mut self.color_array[body.as_usize()],
+            );
+            for (offset, parens) in cmdsubs {
+                self.color_cmdsub(body.start() + offset, &parens);
+            }
+        } else {
+            self.color_range(body, HighlightSpec::with_fg(HighlightRole::quote));
         }
+        self.color_range(
+            heredoc.terminator,
+            HighlightSpec::with_fg(HighlightRole::redirection),
+        );
     }
     // Colors the source range of a node with a given color.
     fn color_node(&mut self, node: &dyn ast::Node, color: HighlightSpec) {
@@ -882,6 +947,7 @@ fn visit_keyword(&mut self, node: &dyn Keyword) {
             ParseKeyword::Begin
             | ParseKeyword::Builtin
             | ParseKeyword::Case
+            | ParseKeyword::Catch
             | ParseKeyword::Command
             | ParseKeyword::Else
             | ParseKeyword::End
@@ -891,6 +957,7 @@ fn visit_keyword(&mut self, node: &dyn Keyword) {
             | ParseKeyword::If
             | ParseKeyword::In
             | ParseKeyword::Switch
+            | ParseKeyword::Try
             | ParseKeyword::While => role = HighlightRole::keyword,
             ParseKeyword::And
             | ParseKeyword::Or
@@ -971,6 +1038,12 @@ fn visit_redirection(&mut self, redir: &Redirection) {
             HighlightSpec::with_fg(HighlightRole::redirection),
         );
 
+        // The target of a here-string is text, like any argument.
+        if oper.mode == RedirectionMode::HereString {
+            self.color_as_argument(&redir.target, false);
+            return;
+        }
+
         // Color the target part.
         // Check if the argument contains a command substitution. If so, highlight it as a param
         // even though it's a command redirection, and don't try to do any other validation.
@@ -1098,8 +1171,15 @@ fn visit_block_statement(&mut self, block: &BlockStatement) {
         self.visit(&block.args_or_redirs);
         let pending_variables_count = self.pending_variables.len();
         if let BlockStatementHeader::For(fh) = &block.header {
+            if let Some(index) = &fh.index {
+                self.pending_variables
+                    .push(index.var_name.source(self.buff));
+            }
             let var_name = fh.var_name.source(self.buff);
             self.pending_variables.push(var_name);
+            for node in &fh.more_var_names {
+                self.pending_variables.push(node.var_name.source(self.buff));
+            }
         }
         self.visit(&block.jobs);
         self.visit(&block.end);
@@ -1113,16 +1193,6 @@ fn visit_brace_statement(&mut self, brace_statement: &BraceStatement) {
     }
 }
 
-/// Return whether a string contains a command substitution.
-fn has_cmdsub(src: &wstr) -> bool {
-    let mut cursor = 0;
-    match locate_cmdsubst_range(src, &mut cursor, true, None, None) {
-        MaybeParentheses::Error => false,
-        MaybeParentheses::None => false,
-        MaybeParentheses::CommandSubstitution(_) => true,
-    }
-}
-
 fn contains_pending_variable(pending_variables: &[&wstr], haystack: &wstr) -> bool {
     for var_name in pending_variables {
         let mut nextpos = 0;
@@ -1809,6 +1879,35 @@ macro_rules! validate {
             (">", fg(HighlightRole::error)),
             ("echo", fg(HighlightRole::error)),
         );
+
+        // Heredocs and here-strings.
+        validate!(
+            ("cat", fg(HighlightRole::command)),
+            ("<<", fg(HighlightRole::redirection)),
+            ("EOF", fg(HighlightRole::redirection), ns),
+            ("\n", fg(HighlightRole::statement_terminator), ns),
+            ("hi", fg(HighlightRole::quote), ns),
+            ("$HOME", fg(HighlightRole::operat)),
+            ("\\$", fg(HighlightRole::escape)),
+            ("$(", fg(HighlightRole::operat)),
+            ("true", fg(HighlightRole::command), ns),
+            (")", fg(HighlightRole::operat), ns),
+            ("\n", fg(HighlightRole::quote), ns),
+            ("EOF", fg(HighlightRole::redirection), ns),
+        );
+        validate!(
+            ("cat", fg(HighlightRole::command)),
+            ("<<", fg(HighlightRole::redirection)),
+            ("'EOF'", fg(HighlightRole::redirection), ns),
+            ("\n", fg(HighlightRole::statement_terminator), ns),
+            ("$HOME $(true)\n", fg(HighlightRole::quote), ns),
+            ("EOF", fg(HighlightRole::redirection), ns),
+        );
+        validate!(
+            ("cat", fg(HighlightRole::command)),
+            ("<<<", fg(HighlightRole::redirection)),
+            ("$HOME", fg(HighlightRole::operat)),
+        );
     }
 
     /// Tests that trailing spaces after a command don't inherit the underline formatting of the
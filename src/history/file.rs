@@ -1,6 +1,7 @@
 //! Implementation of the YAML-like history file format.
 
 use std::{
+    collections::HashMap,
     fs::File,
     io::Read as _,
     ops::{Deref, DerefMut},
@@ -12,7 +13,8 @@
 
 use super::HistoryItem;
 use super::yaml_backend::{
-    decode_item_fish_2_0, escape_yaml_fish_2_0, offset_of_next_item_fish_2_0,
+    RecordKind, decode_item_fish_2_0, decode_outcome_fish_2_0, escape_yaml_fish_2_0,
+    offset_of_next_record_fish_2_0, record_command_fish_2_0,
 };
 use crate::{
     common::wcs2bytes,
@@ -169,17 +171,24 @@ pub fn decode_item(&self, offset: usize) -> Option<HistoryItem> {
         decode_item_fish_2_0(contents)
     }
 
-    /// Support for iterating item offsets.
+    /// Support for iterating record offsets.
     /// The cursor should initially be 0.
-    /// If cutoff is given, skip items whose timestamp is newer than cutoff.
-    /// Returns the offset of the next item, or [`None`] on end.
-    fn offset_of_next_item(&self, cursor: &mut usize, cutoff: Option<SystemTime>) -> Option<usize> {
-        offset_of_next_item_fish_2_0(self.contents(), cursor, cutoff)
+    /// If cutoff is given, skip records whose timestamp is newer than cutoff.
+    /// Returns the offset and kind of the next record, or [`None`] on end.
+    fn offset_of_next_record(
+        &self,
+        cursor: &mut usize,
+        cutoff: Option<SystemTime>,
+    ) -> Option<(usize, RecordKind)> {
+        offset_of_next_record_fish_2_0(self.contents(), cursor, cutoff)
     }
 
-    /// Returns an iterator over item offsets with an optional cutoff time.
-    /// If cutoff is given, skip items whose timestamp is newer than cutoff.
-    pub fn offsets(&self, cutoff: Option<SystemTime>) -> impl Iterator<Item = usize> + '_ {
+    /// Returns an iterator over record offsets with an optional cutoff time.
+    /// If cutoff is given, skip records whose timestamp is newer than cutoff.
+    fn records(
+        &self,
+        cutoff: Option<SystemTime>,
+    ) -> impl Iterator<Item = (usize, RecordKind)> + '_ {
         HistoryFileOffsetIter {
             contents: self,
             cursor: 0,
@@ -195,20 +204,48 @@ pub fn contents(&self) -> &[u8] {
     /// Decode this history file.
     /// If cutoff is given, skip items whose timestamp is newer than cutoff.
     pub fn decode(self, cutoff: Option<SystemTime>) -> HistoryFile {
-        let offsets = self.offsets(cutoff).collect();
+        let mut offsets = Vec::new();
+        let mut outcomes = HashMap::new();
+        for (offset, kind) in self.records(cutoff) {
+            match kind {
+                RecordKind::Item => offsets.push(offset),
+                RecordKind::Outcome => {
+                    // The outcome belongs to the most recent item with the same command. That is
+                    // usually the item just before it, unless other sessions wrote in between.
+                    let contents = self.contents();
+                    let cmd = record_command_fish_2_0(&contents[offset..]);
+                    if let Some(&item_offset) = offsets
+                        .iter()
+                        .rev()
+                        .take(OUTCOME_SEARCH_LIMIT)
+                        .find(|&&item_offset| {
+                            record_command_fish_2_0(&contents[item_offset..]) == cmd
+                        })
+                    {
+                        outcomes.insert(item_offset, offset);
+                    }
+                }
+            }
+        }
         HistoryFile {
             contents: Some(self),
             offsets,
+            outcomes,
         }
     }
 }
 
+/// How many items we look back to find the item an outcome record belongs to.
+const OUTCOME_SEARCH_LIMIT: usize = 1024;
+
 /// A combination of a history file and its offsets.
 pub struct HistoryFile {
     // Contents of the file. May be None if there was no file.
     contents: Option<RawHistoryFile>,
     // Offsets of items within the file. Always empty if contents is None.
     offsets: Vec<usize>,
+    // Offsets of outcome records, keyed by the offset of the item they apply to.
+    outcomes: HashMap<usize, usize>,
 }
 
 impl HistoryFile {
@@ -217,6 +254,7 @@ pub fn create_empty() -> Self {
         Self {
             contents: None,
             offsets: Vec::new(),
+            outcomes: HashMap::new(),
         }
     }
 
@@ -230,9 +268,19 @@ pub fn offsets(&self) -> &[usize] {
         &self.offsets
     }
 
-    /// Decode an item at a given offset.
+    /// Decode an item at a given offset, along with its outcome if one was recorded later.
     pub fn decode_item(&self, offset: usize) -> Option<HistoryItem> {
-        self.contents.as_ref()?.decode_item(offset)
+        let contents = self.contents.as_ref()?;
+        let mut item = contents.decode_item(offset)?;
+        if let Some(&outcome) = self.outcomes.get(&offset) {
+            if let Some((exit_status, duration)) =
+                decode_outcome_fish_2_0(&contents.contents()[outcome..])
+            {
+                item.set_exit_status(exit_status);
+                item.set_duration(duration);
+            }
+        }
+        Some(item)
     }
 }
 
@@ -247,11 +295,11 @@ struct HistoryFileOffsetIter<'a> {
 }
 
 impl<'a> Iterator for HistoryFileOffsetIter<'a> {
-    type Item = usize;
+    type Item = (usize, RecordKind);
 
     fn next(&mut self) -> Option<Self::Item> {
         self.contents
-            .offset_of_next_item(&mut self.cursor, self.cutoff)
+            .offset_of_next_record(&mut self.cursor, self.cutoff)
     }
 }
 
@@ -294,6 +342,19 @@ pub fn write_to(&self, writer: &mut impl std::io::Write) -> std::io::Result<()>
         writer.write_all(&cmd)?;
         writer.write_all(b"\n")?;
         writeln!(writer, "  when: {}", time_to_seconds(self.timestamp()))?;
+        if let Some(status) = self.exit_status() {
+            writeln!(writer, "  status: {status}")?;
+        }
+        if let Some(duration) = self.duration() {
+            writeln!(writer, "  duration: {}", duration.as_millis())?;
+        }
+        if let Some(cwd) = self.cwd() {
+            let mut cwd = wcs2bytes(cwd);
+            escape_yaml_fish_2_0(&mut cwd);
+            writer.write_all(b"  cwd: ")?;
+            writer.write_all(&cwd)?;
+            writer.write_all(b"\n")?;
+        }
 
         let paths = self.get_required_paths();
         if !paths.is_empty() {
@@ -308,6 +369,27 @@ pub fn write_to(&self, writer: &mut impl std::io::Write) -> std::io::Result<()>
         }
         Ok(())
     }
+
+    /// Write the outcome of this history item to some writer, for an item that was written
+    /// before its command finished.
+    pub fn write_outcome_to(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        assert!(self.should_write_to_disk(), "Item should not be persisted");
+
+        let mut cmd = wcs2bytes(self.str());
+        escape_yaml_fish_2_0(&mut cmd);
+        writer.write_all(b"- outcome: ")?;
+        writer.write_all(&cmd)?;
+        writer.write_all(b"\n")?;
+        // The timestamp of the command, so that the outcome is skipped along with its item.
+        writeln!(writer, "  when: {}", time_to_seconds(self.timestamp()))?;
+        if let Some(status) = self.exit_status() {
+            writeln!(writer, "  status: {status}")?;
+        }
+        if let Some(duration) = self.duration() {
+            writeln!(writer, "  duration: {}", duration.as_millis())?;
+        }
+        Ok(())
+    }
 }
 
 /// Check if we should mmap the file.
@@ -30,7 +30,7 @@
     collections::{BTreeMap, HashMap, HashSet},
     ffi::{CStr, CString},
     fs::File,
-    io::{BufRead, BufWriter, Read as _, Write as _},
+    io::{BufRead, BufWriter, Read, Write as _},
     mem::MaybeUninit,
     num::NonZeroUsize,
     ops::ControlFlow,
@@ -41,6 +41,7 @@
 use bitflags::bitflags;
 use lru::LruCache;
 use nix::{fcntl::OFlag, sys::stat::Mode};
+use pcre2::utf32::{Regex, RegexBuilder};
 use rand::Rng as _;
 
 use crate::{
@@ -82,6 +83,16 @@ pub enum SearchType {
     PrefixGlob,
     /// Search for commands containing the given string as a subsequence
     ContainsSubsequence,
+    /// Search for commands containing a match for the given regular expression.
+    Regex,
+}
+
+/// Compile the regular expression used by [`SearchType::Regex`].
+pub fn compile_search_regex(pattern: &wstr, case_sensitive: bool) -> Result<Regex, pcre2::Error> {
+    RegexBuilder::new()
+        .caseless(!case_sensitive)
+        .block_utf_pattern_directive(true)
+        .build(pattern.as_char_slice())
 }
 
 /// Ways that a history item may be written to disk (or omitted).
@@ -95,19 +106,168 @@ pub enum PersistenceMode {
     Ephemeral,
 }
 
+/// Formats that history can be imported from, and (for JSON Lines only) exported to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// One JSON object per line, holding everything we know about an item.
+    Jsonl,
+    /// Bash's history file.
+    Bash,
+    /// Zsh's history file, with the timestamps and durations written by `EXTENDED_HISTORY`.
+    ZshExtended,
+}
+
+impl HistoryFormat {
+    pub fn from_name(name: &wstr) -> Option<Self> {
+        match name {
+            _ if name == "jsonl" => Some(Self::Jsonl),
+            _ if name == "bash" => Some(Self::Bash),
+            _ if name == "zsh-extended" => Some(Self::ZshExtended),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static wstr {
+        match self {
+            Self::Jsonl => L!("jsonl"),
+            Self::Bash => L!("bash"),
+            Self::ZshExtended => L!("zsh-extended"),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SearchDirection {
     Forward,
     Backward,
 }
 
+/// Restrictions on the recorded circumstances of a history item, applied on top of matching its
+/// text. An empty filter matches every item.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryFilter {
+    /// If set, only match commands which are known to have succeeded (`true`) or failed (`false`).
+    pub success: Option<bool>,
+    /// If set, only match commands run in this directory or one of its subdirectories.
+    pub cwd: Option<WString>,
+    /// If set, only match items created at or after this time.
+    pub since: Option<SystemTime>,
+    /// If set, only match items created at or before this time.
+    pub until: Option<SystemTime>,
+    /// If set, only match commands known to have taken longer than this.
+    pub duration_over: Option<Duration>,
+}
+
+impl HistoryFilter {
+    /// Returns whether this filter places no restrictions on items.
+    pub fn is_empty(&self) -> bool {
+        self.success.is_none()
+            && self.cwd.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.duration_over.is_none()
+    }
+
+    /// Returns whether the given item satisfies this filter.
+    /// Items lacking the information a restriction needs do not match it.
+    pub fn matches(&self, item: &HistoryItem) -> bool {
+        if let Some(success) = self.success {
+            if item.exit_status().map(|status| status == 0) != Some(success) {
+                return false;
+            }
+        }
+        if self.cwd.as_ref().is_some_and(|dir| !item.ran_within(dir)) {
+            return false;
+        }
+        if self.since.is_some_and(|since| item.timestamp() < since) {
+            return false;
+        }
+        if self.until.is_some_and(|until| item.timestamp() > until) {
+            return false;
+        }
+        if let Some(min) = self.duration_over {
+            if !item.duration().is_some_and(|duration| duration > min) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Returns whether `path` is `dir` or lies below it. Both must be absolute.
+fn path_is_within(path: &wstr, dir: &wstr) -> bool {
+    fn trim_trailing_slashes(s: &wstr) -> &wstr {
+        let chars = s.as_char_slice();
+        s.slice_to(chars.len() - chars.iter().rev().take_while(|&&c| c == '/').count())
+    }
+    match trim_trailing_slashes(path).strip_prefix(trim_trailing_slashes(dir)) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
 use super::file::time_to_seconds;
+use super::jsonl::{decode_item_jsonl, encode_item_jsonl};
 
 /// This is the history session ID we use by default if the user has not set env var fish_history.
 const DFLT_FISH_HISTORY_SESSION_ID: &wstr = L!("fish");
 
 pub const VACUUM_FREQUENCY: usize = 25;
 
+/// When we rewrite the history, the number of items we keep unless configured otherwise.
+const HISTORY_SAVE_MAX: NonZeroUsize = NonZeroUsize::new(1024 * 256).unwrap();
+
+/// Limits on what is kept in the history file, configured by `$fish_history_max_items`,
+/// `$fish_history_max_age` and `$fish_history_ignore`.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryRetention {
+    /// The number of items kept when the file is vacuumed.
+    max_items: Option<NonZeroUsize>,
+    /// Items older than this are dropped when the file is vacuumed.
+    max_age: Option<Duration>,
+    /// Wildcard patterns. Commands matching any of them are not written to the file.
+    ignore_patterns: Vec<WString>,
+}
+
+impl HistoryRetention {
+    pub fn new(
+        max_items: Option<NonZeroUsize>,
+        max_age: Option<Duration>,
+        ignore_patterns: &[WString],
+    ) -> Self {
+        Self {
+            max_items,
+            max_age,
+            ignore_patterns: ignore_patterns
+                .iter()
+                .map(|pattern| unescape_wildcards(pattern))
+                .collect(),
+        }
+    }
+
+    /// Returns whether the item should not be written to the file because of its command.
+    fn ignores(&self, item: &HistoryItem) -> bool {
+        self.ignore_patterns
+            .iter()
+            .any(|pattern| wildcard_match(item.str(), pattern, false))
+    }
+}
+
+/// The retention settings for histories created from now on.
+static HISTORY_RETENTION: Mutex<HistoryRetention> = Mutex::new(HistoryRetention {
+    max_items: None,
+    max_age: None,
+    ignore_patterns: vec![],
+});
+
+/// Applies new retention settings to all histories.
+pub fn set_history_retention(retention: HistoryRetention) {
+    *HISTORY_RETENTION.lock().unwrap() = retention.clone();
+    for history in HISTORIES.lock().unwrap().values() {
+        history.set_retention(retention.clone());
+    }
+}
+
 struct TimeProfiler {
     what: &'static str,
     start: SystemTime,
@@ -155,6 +315,9 @@ fn add_item(&mut self, item: HistoryItem) {
         // and add it. Note that calling get_node promotes the node to the front.
         let key = item.str();
         if let Some(node) = self.get_mut(key) {
+            if item.timestamp() >= node.timestamp() {
+                node.take_command_info(&item);
+            }
             node.creation_timestamp = SystemTime::max(node.timestamp(), item.timestamp());
             // What to do about paths here? Let's just ignore them.
         } else {
@@ -175,6 +338,12 @@ pub struct HistoryItem {
     required_paths: Vec<WString>,
     /// Whether to write this item to disk.
     persist_mode: PersistenceMode,
+    /// The exit status of the command, if it finished while we were watching.
+    exit_status: Option<i32>,
+    /// How long the command took to run.
+    duration: Option<Duration>,
+    /// The working directory the command was run in.
+    cwd: Option<WString>,
 }
 
 impl HistoryItem {
@@ -190,6 +359,9 @@ pub fn new(
             creation_timestamp: when,
             required_paths: vec![],
             persist_mode,
+            exit_status: None,
+            duration: None,
+            cwd: None,
         }
     }
 
@@ -241,9 +413,18 @@ pub fn matches_search(&self, term: &wstr, typ: SearchType, case_sensitive: bool)
                 wildcard_match(content_to_match.as_ref(), &pat, false)
             }
             SearchType::ContainsSubsequence => subsequence_in_string(term, &content_to_match),
+            SearchType::Regex => compile_search_regex(term, case_sensitive)
+                .is_ok_and(|regex| self.matches_regex(&regex)),
         }
     }
 
+    /// Returns whether our contents contain a match for a regular expression.
+    pub fn matches_regex(&self, regex: &Regex) -> bool {
+        regex
+            .is_match(self.contents.as_char_slice())
+            .unwrap_or(false)
+    }
+
     /// Returns the timestamp for creating this history item.
     pub fn timestamp(&self) -> SystemTime {
         self.creation_timestamp
@@ -266,6 +447,54 @@ pub fn set_required_paths(&mut self, paths: Vec<WString>) {
         self.required_paths = paths;
     }
 
+    /// Returns the exit status of the command, if known.
+    pub fn exit_status(&self) -> Option<i32> {
+        self.exit_status
+    }
+
+    /// Set the exit status of the command.
+    pub fn set_exit_status(&mut self, status: Option<i32>) {
+        self.exit_status = status;
+    }
+
+    /// Returns the wall-clock time the command took to run, if known.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Set the wall-clock time the command took to run.
+    pub fn set_duration(&mut self, duration: Option<Duration>) {
+        self.duration = duration;
+    }
+
+    /// Returns the working directory the command was run in, if known.
+    pub fn cwd(&self) -> Option<&wstr> {
+        self.cwd.as_deref()
+    }
+
+    /// Set the working directory the command was run in.
+    pub fn set_cwd(&mut self, cwd: Option<WString>) {
+        self.cwd = cwd;
+    }
+
+    /// Returns whether the command is known to have been run in `dir` or one of its
+    /// subdirectories.
+    pub fn ran_within(&self, dir: &wstr) -> bool {
+        self.cwd().is_some_and(|cwd| path_is_within(cwd, dir))
+    }
+
+    /// Adopt the exit status, duration and working directory of a more recent run of the same
+    /// command, keeping our own values where the other item has none.
+    fn take_command_info(&mut self, newer: &HistoryItem) {
+        if newer.exit_status.is_some() {
+            self.exit_status = newer.exit_status;
+            self.duration = newer.duration;
+        }
+        if newer.cwd.is_some() {
+            self.cwd.clone_from(&newer.cwd);
+        }
+    }
+
     /// We can merge two items if they are the same command. We use the more recent timestamp, more
     /// recent identifier, and the longer list of required paths.
     fn merge(&mut self, item: &HistoryItem) -> bool {
@@ -275,6 +504,9 @@ fn merge(&mut self, item: &HistoryItem) -> bool {
         }
 
         // Ok, merge this item.
+        if item.creation_timestamp >= self.creation_timestamp {
+            self.take_command_info(item);
+        }
         self.creation_timestamp = self.creation_timestamp.max(item.creation_timestamp);
         if self.required_paths.len() < item.required_paths.len() {
             self.required_paths.clone_from(&item.required_paths);
@@ -307,6 +539,11 @@ struct HistoryImpl {
     /// Whether we have a pending item. If so, the most recently added item is ignored by
     /// item_at_index.
     has_pending_item: bool, // false
+    /// The command of the pending item while it runs, so that its exit status and duration can be
+    /// recorded once it finishes.
+    running_command: Option<WString>,
+    /// Items that were written before their command finished, whose outcome has not been written.
+    unwritten_outcomes: Vec<HistoryItem>,
     /// Whether we should disable saving to the file for a time.
     disable_automatic_save_counter: u32, // 0
     /// Deleted item contents, and the scope of the deletion.
@@ -322,6 +559,8 @@ struct HistoryImpl {
     boundary_timestamp: SystemTime,
     /// How many items we add until the next vacuum. Initially a random value.
     countdown_to_vacuum: Option<usize>,
+    /// Limits on what we keep in the history file.
+    retention: HistoryRetention,
     /// Thread pool for background operations.
     thread_pool: Arc<ThreadPool>,
 }
@@ -374,6 +613,10 @@ fn add(&mut self, item: HistoryItem, pending: bool, do_save: bool) {
             return;
         }
 
+        if pending && item.should_write_to_disk() {
+            self.running_command = Some(item.str().to_owned());
+        }
+
         // Try merging with the last item.
         if let Some(last) = self.new_items.last_mut() {
             if last.merge(&item) {
@@ -501,16 +744,23 @@ fn rewrite_to_temporary_file(
         // We are reading FROM existing_file and writing TO dst
 
         // Make an LRU cache to save only the last N elements.
-
-        /// When we rewrite the history, the number of items we keep.
-        const HISTORY_SAVE_MAX: NonZeroUsize = NonZeroUsize::new(1024 * 256).unwrap();
-        let mut lru = LruCache::new(HISTORY_SAVE_MAX);
+        let mut lru = LruCache::new(self.retention.max_items.unwrap_or(HISTORY_SAVE_MAX));
+
+        // Drop items that are ignored or too old.
+        let cutoff = self
+            .retention
+            .max_age
+            .and_then(|max_age| SystemTime::now().checked_sub(max_age));
+        let should_keep = |item: &HistoryItem| {
+            !self.retention.ignores(item) && cutoff.is_none_or(|cutoff| item.timestamp() >= cutoff)
+        };
 
         // Read in existing items (which may have changed out from underneath us, so don't trust our
         // old file contents).
         let file_id = file_id_for_file(existing_file);
         if let Ok(local_file) = RawHistoryFile::create(existing_file, file_id) {
-            for offset in local_file.offsets(None) {
+            let local_file = local_file.decode(None);
+            for &offset in local_file.offsets() {
                 // Try decoding an old item.
                 let Some(old_item) = local_file.decode_item(offset) else {
                     continue;
@@ -529,17 +779,19 @@ fn rewrite_to_temporary_file(
                         continue;
                     }
                 }
-                lru.add_item(old_item);
+                if should_keep(&old_item) {
+                    lru.add_item(old_item);
+                }
             }
         }
 
-        // Insert any unwritten new items
-        for item in self
-            .new_items
-            .iter()
-            .skip(self.first_unwritten_new_item_index)
-        {
-            if item.should_write_to_disk() {
+        // Insert any unwritten new items, and the outcomes of items that were already written.
+        for item in self.unwritten_outcomes.iter().chain(
+            self.new_items
+                .iter()
+                .skip(self.first_unwritten_new_item_index),
+        ) {
+            if item.should_write_to_disk() && should_keep(item) {
                 lru.add_item(item.clone());
             }
         }
@@ -591,6 +843,7 @@ fn save_internal_via_rewrite(&mut self, history_path: &wstr) -> std::io::Result<
 
         // We've saved everything, so we have no more unsaved items.
         self.first_unwritten_new_item_index = self.new_items.len();
+        self.unwritten_outcomes.clear();
 
         // We deleted our deleted items.
         self.deleted_items.clear();
@@ -644,16 +897,22 @@ fn save_internal_via_appending(&mut self, history_path: &wstr) -> std::io::Resul
         let mut new_first_index = self.first_unwritten_new_item_index;
         while new_first_index < self.new_items.len() {
             let item = &self.new_items[new_first_index];
-            if item.should_write_to_disk() {
+            if item.should_write_to_disk() && !self.retention.ignores(item) {
                 // Can't error writing to a buffer.
                 item.write_to(&mut buffer).unwrap();
             }
             // We wrote or skipped this item, hooray.
             new_first_index += 1;
         }
+        for item in &self.unwritten_outcomes {
+            if !self.retention.ignores(item) {
+                item.write_outcome_to(&mut buffer).unwrap();
+            }
+        }
         locked_history_file.get_mut().write_all(&buffer)?;
         fsync(locked_history_file.get())?;
         self.first_unwritten_new_item_index = new_first_index;
+        self.unwritten_outcomes.clear();
 
         // Since we just modified the file, update our history_file_id to match its current state
         // Otherwise we'll think the file has been changed by someone else the next time we go to
@@ -667,8 +926,10 @@ fn save_internal_via_appending(&mut self, history_path: &wstr) -> std::io::Resul
 
     /// Saves history.
     fn save(&mut self, vacuum: bool) {
-        // Nothing to do if there's no new items.
-        if self.first_unwritten_new_item_index >= self.new_items.len()
+        // Nothing to do if there's no new items, unless we were asked to vacuum.
+        if !vacuum
+            && self.first_unwritten_new_item_index >= self.new_items.len()
+            && self.unwritten_outcomes.is_empty()
             && self.deleted_items.is_empty()
         {
             return;
@@ -680,6 +941,7 @@ fn save(&mut self, vacuum: bool) {
         if self.name.is_empty() {
             // We're in the "incognito" mode. Pretend we've saved the history.
             self.first_unwritten_new_item_index = self.new_items.len();
+            self.unwritten_outcomes.clear();
             self.deleted_items.clear();
             self.clear_file_state();
             return;
@@ -754,12 +1016,15 @@ fn new(name: WString, custom_directory: Option<WString>) -> Self {
             new_items: vec![],
             first_unwritten_new_item_index: 0,
             has_pending_item: false,
+            running_command: None,
+            unwritten_outcomes: vec![],
             disable_automatic_save_counter: 0,
             deleted_items: HashMap::new(),
             file_contents: None,
             history_file_id: INVALID_FILE_ID,
             boundary_timestamp: SystemTime::now(),
             countdown_to_vacuum: None,
+            retention: HISTORY_RETENTION.lock().unwrap().clone(),
             // Up to 8 threads, no soft min.
             thread_pool: ThreadPool::new(0, 8),
         }
@@ -804,6 +1069,8 @@ fn remove(&mut self, str_to_remove: &wstr) {
         // Add to our list of deleted items.
         self.deleted_items
             .insert(str_to_remove.to_owned(), DeletionScope::AllSessions);
+        self.unwritten_outcomes
+            .retain(|item| item.str() != str_to_remove);
 
         for idx in (0..self.new_items.len()).rev() {
             let matched = self.new_items[idx].str() == str_to_remove;
@@ -823,6 +1090,32 @@ fn remove(&mut self, str_to_remove: &wstr) {
     /// Resolves any pending history items, so that they may be returned in history searches.
     fn resolve_pending(&mut self) {
         self.has_pending_item = false;
+        self.running_command = None;
+    }
+
+    /// Records the outcome of the command behind the pending item, then resolves it.
+    /// The item was usually written when the command started, so that it is not lost if we die
+    /// while it runs. In that case, only its exit status and duration are written now, as an
+    /// outcome record that the loader applies to the item.
+    fn resolve_pending_with_result(&mut self, exit_status: i32, duration: Duration) {
+        let running_command = self.running_command.take();
+        self.resolve_pending();
+        let Some(idx) = running_command
+            .and_then(|cmd| self.new_items.iter().rposition(|item| item.str() == cmd))
+        else {
+            return;
+        };
+
+        let item = &mut self.new_items[idx];
+        item.exit_status = Some(exit_status);
+        item.duration = Some(duration);
+        if idx < self.first_unwritten_new_item_index {
+            let item = item.clone();
+            self.unwritten_outcomes
+                .retain(|outcome| outcome.str() != item.str());
+            self.unwritten_outcomes.push(item);
+        }
+        self.save_unless_disabled();
     }
 
     /// Enable / disable automatic saving. Main thread only!
@@ -839,6 +1132,7 @@ fn enable_automatic_saving(&mut self) {
     /// Irreversibly clears history.
     fn clear(&mut self) {
         self.new_items.clear();
+        self.unwritten_outcomes.clear();
         self.deleted_items.clear();
         self.first_unwritten_new_item_index = 0;
         self.file_contents = None;
@@ -856,6 +1150,7 @@ fn clear_session(&mut self) {
         }
 
         self.new_items.clear();
+        self.unwritten_outcomes.clear();
         self.first_unwritten_new_item_index = 0;
     }
 
@@ -934,6 +1229,54 @@ fn populate_from_bash<R: BufRead>(&mut self, contents: R) {
         self.save_unless_disabled();
     }
 
+    /// Import a zsh command history file. With `EXTENDED_HISTORY` set, zsh writes each command as
+    /// `: <start time>:<elapsed seconds>;<command>`, otherwise just the command. Newlines in
+    /// multiline commands are escaped with a backslash. Like for bash, commands using syntax we
+    /// can't handle are skipped.
+    fn populate_from_zsh_extended<R: BufRead>(&mut self, contents: R) {
+        let now = self.timestamp_now();
+        let mut lines = contents.split(b'\n').map_while(Result::ok);
+        while let Some(mut entry) = lines.next() {
+            while entry.last() == Some(&b'\\') {
+                let Some(next) = lines.next() else {
+                    break;
+                };
+                entry.pop();
+                entry.push(b'\n');
+                entry.extend_from_slice(&next);
+            }
+            let entry = zsh_unmetafy(entry);
+            let (when, duration, command) = match parse_zsh_extended_entry(&entry) {
+                Some((when, duration, command)) => (when, Some(duration), command),
+                None => (now, None, &entry[..]),
+            };
+            let command = trim(bytes2wcstring(command), None);
+            if should_import_bash_history_line(&command) {
+                let mut item = HistoryItem::new(command, when, PersistenceMode::Disk);
+                item.set_duration(duration);
+                self.add(item, /*pending=*/ false, /*do_save=*/ false);
+            }
+        }
+        self.save_unless_disabled();
+    }
+
+    /// Import items written by `history export`. Malformed lines are skipped, and their number is
+    /// returned.
+    fn populate_from_jsonl(&mut self, contents: &[u8]) -> usize {
+        let mut malformed = 0;
+        for line in contents.split(|&c| c == b'\n') {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match decode_item_jsonl(line) {
+                Some(item) => self.add(item, /*pending=*/ false, /*do_save=*/ false),
+                None => malformed += 1,
+            }
+        }
+        self.save_unless_disabled();
+        malformed
+    }
+
     /// Incorporates the history of other shells into this history.
     fn incorporate_external_changes(&mut self) {
         // To incorporate new items, we simply update our timestamp to now, so that items from previous
@@ -955,8 +1298,15 @@ fn incorporate_external_changes(&mut self) {
             // TODO: this will drop items that had no_persist set, how can we avoid that while still
             // properly interleaving?
             self.save(false);
+            // Keep the item of a running command around, so that its outcome can still be
+            // recorded. It has been written, so we don't write it again until then.
+            let running_item = self.running_command.as_ref().and_then(|cmd| {
+                let idx = self.new_items.iter().rposition(|item| item.str() == cmd)?;
+                Some(self.new_items.swap_remove(idx))
+            });
             self.new_items.clear();
-            self.first_unwritten_new_item_index = 0;
+            self.first_unwritten_new_item_index = usize::from(running_item.is_some());
+            self.new_items.extend(running_item);
         }
     }
 
@@ -1093,6 +1443,7 @@ fn do_1_history_search(
     search_type: SearchType,
     search_string: WString,
     case_sensitive: bool,
+    filter: &HistoryFilter,
     mut func: impl FnMut(&HistoryItem) -> ControlFlow<(), ()>,
     cancel_check: &CancelChecker,
 ) {
@@ -1107,6 +1458,7 @@ fn do_1_history_search(
         },
         0,
     );
+    searcher.set_filter(filter.clone());
     while !cancel_check() && searcher.go_to_next_match(SearchDirection::Backward) {
         if let ControlFlow::Break(()) = func(searcher.current_item()) {
             break;
@@ -1201,6 +1553,50 @@ fn should_import_bash_history_line(line: &wstr) -> bool {
     errors.is_empty()
 }
 
+/// Undo zsh's "metafication" of its history file, where some bytes are written as a marker byte
+/// followed by the original byte xor 32.
+fn zsh_unmetafy(mut entry: Vec<u8>) -> Vec<u8> {
+    const ZSH_META: u8 = 0x83;
+    let mut read = 0;
+    let mut write = 0;
+    while read < entry.len() {
+        let mut byte = entry[read];
+        if byte == ZSH_META && read + 1 < entry.len() {
+            read += 1;
+            byte = entry[read] ^ 32;
+        }
+        entry[write] = byte;
+        read += 1;
+        write += 1;
+    }
+    entry.truncate(write);
+    entry
+}
+
+/// Parse a zsh extended history entry like `: 1700000000:12;make`, returning the start time, the
+/// duration, and the command.
+fn parse_zsh_extended_entry(entry: &[u8]) -> Option<(SystemTime, Duration, &[u8])> {
+    fn number(digits: &[u8]) -> Option<u64> {
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        std::str::from_utf8(digits).ok()?.parse().ok()
+    }
+    let rest = entry.strip_prefix(b": ")?;
+    let colon = rest.iter().position(|&c| c == b':')?;
+    let semicolon = rest.iter().position(|&c| c == b';')?;
+    if semicolon < colon {
+        return None;
+    }
+    let start = number(&rest[..colon])?;
+    let elapsed = number(&rest[colon + 1..semicolon])?;
+    Some((
+        UNIX_EPOCH + Duration::from_secs(start),
+        Duration::from_secs(elapsed),
+        &rest[semicolon + 1..],
+    ))
+}
+
 pub struct History(Mutex<HistoryImpl>);
 
 impl History {
@@ -1319,7 +1715,8 @@ pub fn add_pending_with_file_detection(
 
         // Make our history item.
         let when = imp.timestamp_now();
-        let item = HistoryItem::new(s.to_owned(), when, persist_mode);
+        let mut item = HistoryItem::new(s.to_owned(), when, persist_mode);
+        item.set_cwd(vars.get_unless_empty(L!("PWD")).map(|var| var.as_string()));
         let to_disk = persist_mode == PersistenceMode::Disk;
 
         if wants_file_detection {
@@ -1362,6 +1759,13 @@ pub fn resolve_pending(&self) {
         self.imp().resolve_pending();
     }
 
+    /// Resolves the pending history item, recording the exit status and wall-clock duration of its
+    /// command.
+    pub fn resolve_pending_with_result(&self, exit_status: i32, duration: Duration) {
+        self.imp()
+            .resolve_pending_with_result(exit_status, duration);
+    }
+
     /// Saves history.
     pub fn save(&self) {
         self.imp().save(false);
@@ -1375,6 +1779,7 @@ pub fn search(
         streams: &mut IoStreams,
         search_type: SearchType,
         search_args: &[&wstr],
+        filter: &HistoryFilter,
         show_time_format: Option<&str>,
         max_items: usize,
         case_sensitive: bool,
@@ -1422,6 +1827,7 @@ pub fn search(
                 SearchType::Contains,
                 WString::new(),
                 true,
+                filter,
                 &mut func,
                 cancel_check,
             );
@@ -1439,6 +1845,7 @@ pub fn search(
                     search_type,
                     search_string.to_owned(),
                     case_sensitive,
+                    filter,
                     &mut func,
                     cancel_check,
                 );
@@ -1463,6 +1870,17 @@ pub fn search(
         true
     }
 
+    /// Rewrites the history file, removing duplicates and applying the retention settings.
+    pub fn vacuum(&self) {
+        let _profiler = TimeProfiler::new("vacuum");
+        self.imp().save(true);
+    }
+
+    /// Changes the limits on what is kept in the history file.
+    pub fn set_retention(&self, retention: HistoryRetention) {
+        self.imp().retention = retention;
+    }
+
     /// Irreversibly clears history.
     pub fn clear(&self) {
         self.imp().clear();
@@ -1483,6 +1901,56 @@ pub fn populate_from_bash<R: BufRead>(&self, contents: R) {
         self.imp().populate_from_bash(contents);
     }
 
+    /// Imports the history in `contents`, which is in the given format. Returns the number of
+    /// entries that were skipped because they are malformed.
+    pub fn import(&self, format: HistoryFormat, mut contents: impl Read) -> std::io::Result<usize> {
+        // Read everything before taking the lock, since the contents may come from a pipe.
+        let mut data = vec![];
+        contents.read_to_end(&mut data)?;
+        let mut imp = self.imp();
+        let malformed = match format {
+            HistoryFormat::Jsonl => imp.populate_from_jsonl(&data),
+            HistoryFormat::Bash => {
+                imp.populate_from_bash(&data[..]);
+                0
+            }
+            HistoryFormat::ZshExtended => {
+                imp.populate_from_zsh_extended(&data[..]);
+                0
+            }
+        };
+        Ok(malformed)
+    }
+
+    /// Writes every item matching `filter` to `streams` as JSON Lines, oldest first.
+    pub fn export_jsonl(
+        self: &Arc<Self>,
+        streams: &mut IoStreams,
+        filter: &HistoryFilter,
+        cancel_check: &CancelChecker,
+    ) {
+        let mut searcher = HistorySearch::new_with(
+            Arc::clone(self),
+            WString::new(),
+            SearchType::Contains,
+            SearchFlags::NO_DEDUP,
+            0,
+        );
+        searcher.set_filter(filter.clone());
+        let mut items = vec![];
+        while !cancel_check() && searcher.go_to_next_match(SearchDirection::Backward) {
+            items.push(searcher.current_item().clone());
+        }
+        for item in items.iter().rev() {
+            if !streams
+                .out
+                .append(&bytes2wcstring(&encode_item_jsonl(item)))
+            {
+                break;
+            }
+        }
+    }
+
     /// Incorporates the history of other shells into this history.
     pub fn incorporate_external_changes(&self) {
         self.imp().incorporate_external_changes();
@@ -1538,6 +2006,8 @@ pub struct HistorySearch {
     canon_term: WString,
     /// Our search type.
     search_type: SearchType, // history_search_type_t::contains
+    /// The compiled search term, for regex searches. None if it failed to compile.
+    regex: Option<Regex>,
     /// Our flags.
     flags: SearchFlags, // 0
     /// The current history item.
@@ -1546,6 +2016,8 @@ pub struct HistorySearch {
     current_index: usize, // 0
     /// If deduping, the items we've seen.
     deduper: HashSet<WString>,
+    /// Restrictions on item metadata.
+    filter: HistoryFilter,
 }
 
 impl HistorySearch {
@@ -1574,19 +2046,34 @@ pub fn new_with(
             orig_term: s.clone(),
             canon_term: s,
             search_type,
+            regex: None,
             flags,
             current_item: None,
             current_index: starting_index,
             deduper: HashSet::new(),
+            filter: HistoryFilter::default(),
         };
 
-        if search.ignores_case() {
+        if search_type == SearchType::Regex {
+            // Lowercasing a regex would change its meaning (e.g. `\S`), so let PCRE2 ignore case.
+            search.regex = compile_search_regex(&search.orig_term, !search.ignores_case()).ok();
+        } else if search.ignores_case() {
             search.canon_term = search.canon_term.to_lowercase();
         }
 
         search
     }
 
+    /// Restricts matches to items satisfying the given filter.
+    pub fn set_filter(&mut self, filter: HistoryFilter) {
+        self.filter = filter;
+    }
+
+    /// Returns the filter applied to matches.
+    pub fn filter(&self) -> &HistoryFilter {
+        &self.filter
+    }
+
     /// Returns the original search term.
     pub fn original_term(&self) -> &wstr {
         &self.orig_term
@@ -1632,7 +2119,18 @@ pub fn go_to_next_match(&mut self, direction: SearchDirection) -> bool {
             };
 
             // Look for an item that matches and (if deduping) that we haven't seen before.
-            if !item.matches_search(&self.canon_term, self.search_type, !self.ignores_case()) {
+            let matched = if self.search_type == SearchType::Regex {
+                self.regex
+                    .as_ref()
+                    .is_some_and(|regex| item.matches_regex(regex))
+            } else {
+                item.matches_search(&self.canon_term, self.search_type, !self.ignores_case())
+            };
+            if !matched {
+                continue;
+            }
+
+            if !self.filter.matches(&item) {
                 continue;
             }
 
@@ -1803,10 +2301,11 @@ pub fn in_private_mode(vars: &dyn Environment) -> bool {
 #[cfg(test)]
 mod tests {
     use super::{
-        History, HistoryItem, HistorySearch, PathList, PersistenceMode, SearchDirection,
-        SearchFlags, SearchType, VACUUM_FREQUENCY,
+        History, HistoryFilter, HistoryFormat, HistoryItem, HistoryRetention, HistorySearch,
+        PathList, PersistenceMode, SearchDirection, SearchFlags, SearchType, VACUUM_FREQUENCY,
+        decode_item_jsonl, encode_item_jsonl,
     };
-    use crate::common::{ESCAPE_TEST_CHAR, osstr2wcstring, wcs2bytes};
+    use crate::common::{ESCAPE_TEST_CHAR, bytes2wcstring, osstr2wcstring, wcs2bytes};
     use crate::env::{EnvMode, EnvSetMode, EnvStack};
     use crate::fs::{LockedFile, WriteMethod};
     use crate::prelude::*;
@@ -1817,6 +2316,7 @@ mod tests {
     use std::collections::VecDeque;
     use std::ffi::OsString;
     use std::io::BufReader;
+    use std::num::NonZeroUsize;
     use std::sync::Arc;
     use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -1950,6 +2450,31 @@ macro_rules! test_history_matches {
         let expected = set_expected(|s| s.to_lowercase() == "alph");
         test_history_matches!(searcher, expected);
 
+        // Items matching the regex "^[Bb].t", case-sensitive.
+        let mut searcher = HistorySearch::new_with_type(
+            history.clone(),
+            L!("^[Bb].t").to_owned(),
+            SearchType::Regex,
+        );
+        let expected = set_expected(|s| s == "beta" || s == "Beta");
+        test_history_matches!(searcher, expected);
+
+        // Items matching the regex "PH$|^\w{3}$", case-insensitive. The pattern is not lowercased.
+        let mut searcher = HistorySearch::new_with(
+            history.clone(),
+            L!("PH$|^\\w{3}$").to_owned(),
+            SearchType::Regex,
+            nocase,
+            0,
+        );
+        let expected = set_expected(|s| s.len() == 3 || s.to_lowercase().ends_with("ph"));
+        test_history_matches!(searcher, expected);
+
+        // An invalid regex matches nothing.
+        let mut searcher =
+            HistorySearch::new_with_type(history.clone(), L!("(").to_owned(), SearchType::Regex);
+        test_history_matches!(searcher, vec![]);
+
         // Test item removal case-sensitive.
         let mut searcher = HistorySearch::new(history.clone(), L!("Alpha").to_owned());
         test_history_matches!(searcher, vec![L!("Alpha")]);
@@ -1980,6 +2505,15 @@ macro_rules! test_history_matches {
             // Record this item.
             let mut item = HistoryItem::new(value, SystemTime::now(), PersistenceMode::Disk);
             item.set_required_paths(paths);
+
+            // Maybe record how the command went.
+            if i % 2 == 0 {
+                item.set_exit_status(Some(i % 5));
+                item.set_duration(Some(Duration::from_millis(rng.random_range(0..100_000))));
+                item.set_cwd(Some(
+                    L!("/tmp/dir\\with\nnewline ").to_owned() + &random_string(&mut rng)[..],
+                ));
+            }
             before.push_back(item.clone());
             history.add(item, false);
         }
@@ -2000,6 +2534,9 @@ macro_rules! test_history_matches {
             assert_eq!(bef.str(), aft.str());
             assert_eq!(bef.timestamp(), aft.timestamp());
             assert_eq!(bef.get_required_paths(), aft.get_required_paths());
+            assert_eq!(bef.exit_status(), aft.exit_status());
+            assert_eq!(bef.duration(), aft.duration());
+            assert_eq!(bef.cwd(), aft.cwd());
         }
 
         // Items should be explicitly added to the history.
@@ -2010,6 +2547,205 @@ macro_rules! test_history_matches {
         history.clear();
     }
 
+    #[test]
+    fn test_history_filter() {
+        let tmpdir = fish_tempfile::new_dir().unwrap();
+        let hist_dir = osstr2wcstring(tmpdir.path());
+        let history = create_test_history(L!("test_history_filter"), &hist_dir);
+        history.clear();
+
+        let base = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let add = |text: &str, secs: u64, status: Option<i32>, millis: u64, cwd: Option<&str>| {
+            let mut item = HistoryItem::new(
+                text.into(),
+                base + Duration::from_secs(secs),
+                PersistenceMode::Disk,
+            );
+            item.set_exit_status(status);
+            item.set_duration(status.map(|_| Duration::from_millis(millis)));
+            item.set_cwd(cwd.map(WString::from));
+            history.add(item, false);
+        };
+        add("make", 0, Some(0), 100, Some("/src/proj"));
+        add("make test", 10, Some(2), 90_000, Some("/src/proj/sub"));
+        add("ls", 20, Some(0), 5, Some("/src/project"));
+        add("old", 30, None, 0, None);
+
+        let search = |filter: HistoryFilter| {
+            let mut searcher = HistorySearch::new(history.clone(), WString::new());
+            searcher.set_filter(filter);
+            let mut found = vec![];
+            while searcher.go_to_next_match(SearchDirection::Backward) {
+                found.push(searcher.current_string().to_owned());
+            }
+            found
+        };
+
+        assert_eq!(search(HistoryFilter::default()).len(), 4);
+        let filter = HistoryFilter {
+            success: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(vec![L!("ls"), L!("make")], search(filter));
+        let filter = HistoryFilter {
+            success: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(vec![L!("make test")], search(filter));
+        let filter = HistoryFilter {
+            cwd: Some(L!("/src/proj/").to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(vec![L!("make test"), L!("make")], search(filter));
+        let filter = HistoryFilter {
+            since: Some(base + Duration::from_secs(10)),
+            until: Some(base + Duration::from_secs(20)),
+            ..Default::default()
+        };
+        assert_eq!(vec![L!("ls"), L!("make test")], search(filter));
+        let filter = HistoryFilter {
+            duration_over: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        assert_eq!(vec![L!("make test")], search(filter));
+
+        history.clear();
+    }
+
+    #[test]
+    fn test_history_retention() {
+        let tmpdir = fish_tempfile::new_dir().unwrap();
+        let hist_dir = osstr2wcstring(tmpdir.path());
+        let name = L!("test_history_retention");
+        let history = create_test_history(name, &hist_dir);
+        history.clear();
+        history.set_retention(HistoryRetention::new(
+            NonZeroUsize::new(2),
+            Some(Duration::from_secs(60 * 60)),
+            &[L!("secret *").to_owned()],
+        ));
+
+        let now = SystemTime::now();
+        for (text, age) in [
+            ("ancient", 2 * 60 * 60),
+            ("one", 30),
+            ("secret stuff", 20),
+            ("two", 10),
+            ("three", 0),
+        ] {
+            let when = now - Duration::from_secs(age);
+            history.add(
+                HistoryItem::new(text.into(), when, PersistenceMode::Disk),
+                false,
+            );
+        }
+
+        // Ignored commands are still part of this session, but never reach the file.
+        assert!(history_contains(&history, L!("secret stuff")));
+        let reloaded = create_test_history(name, &hist_dir);
+        assert!(history_contains(&reloaded, L!("three")));
+        assert!(!history_contains(&reloaded, L!("secret stuff")));
+
+        // Vacuuming drops old items and keeps only the most recent ones.
+        history.vacuum();
+        let reloaded = create_test_history(name, &hist_dir);
+        assert_eq!(vec![L!("three"), L!("two")], reloaded.get_history());
+        history.clear();
+    }
+
+    #[test]
+    fn test_history_import() {
+        let tmpdir = fish_tempfile::new_dir().unwrap();
+        let hist_dir = osstr2wcstring(tmpdir.path());
+        let when = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+
+        // JSON Lines round-trips every field, including awkward strings.
+        let mut full = HistoryItem::new(
+            L!("echo \"quoted\" \\ tab\t\u{1}\nnext line \u{e9}").to_owned(),
+            when(1_700_000_000),
+            PersistenceMode::Disk,
+        );
+        full.set_exit_status(Some(-1));
+        full.set_duration(Some(Duration::from_millis(1520)));
+        full.set_cwd(Some(L!("/home/me/dir with \"quotes\"").to_owned()));
+        full.set_required_paths(vec![L!("a").to_owned(), L!("b\\c").to_owned()]);
+        let bare = HistoryItem::new(L!("ls").to_owned(), when(1), PersistenceMode::Disk);
+        for item in [&full, &bare] {
+            let encoded = encode_item_jsonl(item);
+            assert_eq!(encoded.last(), Some(&b'\n'));
+            assert!(!encoded[..encoded.len() - 1].contains(&b'\n'));
+            let decoded = decode_item_jsonl(&encoded).unwrap();
+            assert_eq!(decoded.str(), item.str());
+            assert_eq!(decoded.timestamp(), item.timestamp());
+            assert_eq!(decoded.exit_status(), item.exit_status());
+            assert_eq!(decoded.duration(), item.duration());
+            assert_eq!(decoded.cwd(), item.cwd());
+            assert_eq!(decoded.get_required_paths(), item.get_required_paths());
+        }
+        let decoded = decode_item_jsonl(
+            br#" { "extra": [1, {"x": null}], "cmd": "a\u00e9\ud83d\ude00", "status": null } "#,
+        )
+        .unwrap();
+        assert_eq!(decoded.str(), "a\u{e9}\u{1f600}");
+        assert_eq!(decoded.exit_status(), None);
+        // Bytes which are not valid UTF-8 still give valid JSON, and survive the round trip.
+        let invalid = HistoryItem::new(
+            bytes2wcstring(b"echo \xff\xc3 \xef\x98\x80"),
+            when(1),
+            PersistenceMode::Disk,
+        );
+        let encoded = encode_item_jsonl(&invalid);
+        assert!(std::str::from_utf8(&encoded).is_ok());
+        assert_eq!(decode_item_jsonl(&encoded).unwrap().str(), invalid.str());
+        assert!(decode_item_jsonl(br#"{"when": 5}"#).is_none());
+        assert!(decode_item_jsonl(br#"{"cmd": "x"} trailing"#).is_none());
+        assert!(decode_item_jsonl(b"").is_none());
+
+        let history = create_test_history(L!("test_history_import"), &hist_dir);
+        history.clear();
+        let mut jsonl = encode_item_jsonl(&full);
+        jsonl.extend_from_slice(b"not json\n");
+        jsonl.extend_from_slice(&encode_item_jsonl(&bare));
+        assert_eq!(history.import(HistoryFormat::Jsonl, &jsonl[..]).unwrap(), 1);
+        assert_eq!(vec![L!("ls"), full.str()], history.get_history());
+        assert_eq!(
+            history.item_at_index(2).unwrap().duration(),
+            Some(Duration::from_millis(1520))
+        );
+        history.clear();
+
+        // Zsh extended history, with a multiline command and a metafied character.
+        let zsh = b": 1700000000:12;make\n\
+                    : 1700000020:0;echo one\\\ntwo\n\
+                    : 1700000030:1;echo caf\xc3\x83\x89\n\
+                    : 1700000040:0;echo `backticks`\n\
+                    plain command\n";
+        history
+            .import(HistoryFormat::ZshExtended, &zsh[..])
+            .unwrap();
+        assert_eq!(
+            vec![
+                L!("plain command"),
+                L!("echo caf\u{e9}"),
+                L!("echo one\ntwo"),
+                L!("make"),
+            ],
+            history.get_history()
+        );
+        let make = history.item_at_index(4).unwrap();
+        assert_eq!(make.timestamp(), when(1_700_000_000));
+        assert_eq!(make.duration(), Some(Duration::from_secs(12)));
+        assert_eq!(history.item_at_index(1).unwrap().duration(), None);
+        history.clear();
+
+        // Bash history.
+        history
+            .import(HistoryFormat::Bash, &b"echo foo\necho bar\n"[..])
+            .unwrap();
+        assert_eq!(vec![L!("echo bar"), L!("echo foo")], history.get_history());
+        history.clear();
+    }
+
     // Wait until the next second.
     fn time_barrier() {
         let start = SystemTime::now();
@@ -2288,6 +3024,76 @@ fn test_history_merge() {
         everything.clear();
     }
 
+    #[test]
+    fn test_history_pending_result() {
+        let tmpdir = fish_tempfile::new_dir().unwrap();
+        let hist_dir = osstr2wcstring(tmpdir.path());
+        let name = L!("pending_result_test");
+        let test_vars = EnvStack::new();
+
+        let history = create_test_history(name, &hist_dir);
+        history.clear();
+        time_barrier();
+
+        // A running command is written right away, so other sessions see it and it survives our
+        // death.
+        history.add_pending_with_file_detection(
+            L!("long_running"),
+            &test_vars,
+            PersistenceMode::Disk,
+        );
+        time_barrier();
+        let other = create_test_history(name, &hist_dir);
+        let item = other.item_at_index(1).unwrap();
+        assert_eq!(item.str(), "long_running");
+        assert_eq!(item.exit_status(), None);
+
+        // Once it finishes, its outcome is written as well.
+        history.resolve_pending_with_result(3, Duration::from_millis(1500));
+        let item = history.item_at_index(1).unwrap();
+        assert_eq!(item.str(), "long_running");
+        assert_eq!(item.exit_status(), Some(3));
+        assert_eq!(item.duration(), Some(Duration::from_millis(1500)));
+        time_barrier();
+        let other = create_test_history(name, &hist_dir);
+        let item = other.item_at_index(1).unwrap();
+        assert_eq!(item.exit_status(), Some(3));
+        assert_eq!(item.duration(), Some(Duration::from_millis(1500)));
+        // The command itself is not written a second time, only its outcome.
+        assert_eq!(other.size(), 1);
+        let contents = std::fs::read(tmpdir.path().join("pending_result_test_history")).unwrap();
+        let records = |prefix: &[u8]| {
+            contents
+                .split(|&c| c == b'\n')
+                .filter(|line| line.starts_with(prefix))
+                .count()
+        };
+        assert_eq!(records(b"- cmd: long_running"), 1);
+        assert_eq!(records(b"- outcome: long_running"), 1);
+
+        // Running the same command again merges with the previous item, and records the new
+        // outcome.
+        history.add_pending_with_file_detection(
+            L!("long_running"),
+            &test_vars,
+            PersistenceMode::Disk,
+        );
+        history.resolve_pending_with_result(0, Duration::from_millis(10));
+        assert_eq!(history.imp().new_items.len(), 1);
+        let item = history.item_at_index(1).unwrap();
+        assert_eq!(item.exit_status(), Some(0));
+
+        // Compacting the file keeps only the latest outcome.
+        history.vacuum();
+        time_barrier();
+        let other = create_test_history(name, &hist_dir);
+        assert_eq!(other.size(), 1);
+        let item = other.item_at_index(1).unwrap();
+        assert_eq!(item.exit_status(), Some(0));
+        assert_eq!(item.duration(), Some(Duration::from_millis(10)));
+        history.clear();
+    }
+
     #[test]
     fn test_history_path_detection() {
         // Regression test for #7582.
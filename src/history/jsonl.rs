@@ -0,0 +1,320 @@
+//! Implementation of the JSON Lines format used by `history export` and `history import`.
+//!
+//! Each line holds one JSON object describing one item, oldest items first:
+//!
+//!   {"cmd":"make test","when":1700000000,"status":0,"duration":1520,"cwd":"/src","paths":["Makefile"]}
+//!
+//! Only "cmd" is required. As in the history file, "when" is in seconds since the epoch and
+//! "duration" is in milliseconds. Unknown keys are ignored, and missing or null values are left
+//! unset. Bytes which are not valid UTF-8 (e.g. in file names) are written as `\uXXXX` escapes of
+//! the private-use characters fish stores them as, so the output is valid JSON, and they are
+//! decoded back into the original bytes on import.
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::file::time_to_seconds;
+use super::yaml_backend::time_from_seconds;
+use super::{HistoryItem, PersistenceMode};
+use crate::common::bytes2wcstring;
+use crate::prelude::*;
+use fish_widestring::decode_byte_from_char;
+
+/// Append a JSON string literal for `s` to `out`.
+fn write_json_string(out: &mut Vec<u8>, s: &wstr) {
+    out.push(b'"');
+    for c in s.chars() {
+        match c {
+            '"' => out.extend_from_slice(b"\\\""),
+            '\\' => out.extend_from_slice(b"\\\\"),
+            '\n' => out.extend_from_slice(b"\\n"),
+            '\r' => out.extend_from_slice(b"\\r"),
+            '\t' => out.extend_from_slice(b"\\t"),
+            // Control characters, and bytes which are not valid UTF-8.
+            _ if c.is_ascii_control() || decode_byte_from_char(c).is_some() => {
+                out.extend_from_slice(format!("\\u{:04x}", u32::from(c)).as_bytes());
+            }
+            _ => {
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+    out.push(b'"');
+}
+
+/// Encode an item as a single line of JSON, including the trailing newline.
+pub fn encode_item_jsonl(item: &HistoryItem) -> Vec<u8> {
+    let mut out = b"{\"cmd\":".to_vec();
+    write_json_string(&mut out, item.str());
+    out.extend_from_slice(format!(",\"when\":{}", time_to_seconds(item.timestamp())).as_bytes());
+    if let Some(status) = item.exit_status() {
+        out.extend_from_slice(format!(",\"status\":{status}").as_bytes());
+    }
+    if let Some(duration) = item.duration() {
+        out.extend_from_slice(format!(",\"duration\":{}", duration.as_millis()).as_bytes());
+    }
+    if let Some(cwd) = item.cwd() {
+        out.extend_from_slice(b",\"cwd\":");
+        write_json_string(&mut out, cwd);
+    }
+    let paths = item.get_required_paths();
+    if !paths.is_empty() {
+        out.extend_from_slice(b",\"paths\":[");
+        for (i, path) in paths.iter().enumerate() {
+            if i > 0 {
+                out.push(b',');
+            }
+            write_json_string(&mut out, path);
+        }
+        out.push(b']');
+    }
+    out.extend_from_slice(b"}\n");
+    out
+}
+
+/// A minimal reader for the subset of JSON we need: an object of strings, numbers, null and arrays
+/// of strings. Other values are skipped but not interpreted.
+struct JsonReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Return the next non-whitespace byte without consuming it.
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(&c) = self.data.get(self.pos) {
+            if !matches!(c, b' ' | b'\t' | b'\r' | b'\n') {
+                return Some(c);
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    /// Consume `c` if it is the next non-whitespace byte.
+    fn eat(&mut self, c: u8) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, c: u8) -> Option<()> {
+        self.eat(c).then_some(())
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    /// Consume `literal` (like `null`) if it comes next.
+    fn eat_literal(&mut self, literal: &[u8]) -> bool {
+        self.peek();
+        let found = self.data[self.pos..].starts_with(literal);
+        if found {
+            self.pos += literal.len();
+        }
+        found
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.data.get(self.pos..self.pos + 4)?;
+        let value = u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+        self.pos += 4;
+        Some(value)
+    }
+
+    /// Read a string, returning its bytes, which are UTF-8 unless it encodes invalid bytes.
+    fn string(&mut self) -> Option<Vec<u8>> {
+        self.expect(b'"')?;
+        let mut result = vec![];
+        loop {
+            let c = *self.data.get(self.pos)?;
+            self.pos += 1;
+            match c {
+                b'"' => return Some(result),
+                b'\\' => {
+                    let escape = *self.data.get(self.pos)?;
+                    self.pos += 1;
+                    let unescaped = match escape {
+                        b'"' | b'\\' | b'/' => escape,
+                        b'b' => 0x08,
+                        b'f' => 0x0c,
+                        b'n' => b'\n',
+                        b'r' => b'\r',
+                        b't' => b'\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            // Combine surrogate pairs.
+                            if (0xd800..0xdc00).contains(&code)
+                                && self.data[self.pos..].starts_with(b"\\u")
+                            {
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                code = if (0xdc00..0xe000).contains(&low) {
+                                    0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+                                } else {
+                                    0xfffd
+                                };
+                            }
+                            let c = char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER);
+                            // Undo the encoding of bytes which are not valid UTF-8.
+                            if let Some(byte) = decode_byte_from_char(c) {
+                                result.push(byte);
+                                continue;
+                            }
+                            let mut buf = [0; 4];
+                            result.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                            continue;
+                        }
+                        _ => return None,
+                    };
+                    result.push(unescaped);
+                }
+                _ => result.push(c),
+            }
+        }
+    }
+
+    /// Read a number, truncating it to an integer.
+    fn integer(&mut self) -> Option<i64> {
+        self.peek();
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|c| matches!(c, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.pos += 1;
+        }
+        let number = std::str::from_utf8(&self.data[start..self.pos]).ok()?;
+        number.parse().ok().or_else(|| {
+            number
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .map(|n| n as i64)
+        })
+    }
+
+    /// Read a value with `read`, or return None (in the Some) if the value is `null`.
+    fn nullable<T>(&mut self, read: impl FnOnce(&mut Self) -> Option<T>) -> Option<Option<T>> {
+        if self.eat_literal(b"null") {
+            Some(None)
+        } else {
+            read(self).map(Some)
+        }
+    }
+
+    /// Skip over any value.
+    fn skip_value(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'"' => {
+                self.string()?;
+            }
+            open @ (b'[' | b'{') => {
+                self.pos += 1;
+                let close = if open == b'[' { b']' } else { b'}' };
+                if self.eat(close) {
+                    return Some(());
+                }
+                loop {
+                    if open == b'{' {
+                        self.string()?;
+                        self.expect(b':')?;
+                    }
+                    self.skip_value()?;
+                    if !self.eat(b',') {
+                        break;
+                    }
+                }
+                self.expect(close)?;
+            }
+            _ => {
+                if !(self.eat_literal(b"null")
+                    || self.eat_literal(b"true")
+                    || self.eat_literal(b"false"))
+                {
+                    self.integer()?;
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Decode an item from a single line of JSON. Returns None if the line is malformed or lacks a
+/// command.
+pub fn decode_item_jsonl(line: &[u8]) -> Option<HistoryItem> {
+    let mut reader = JsonReader::new(line);
+    reader.expect(b'{')?;
+
+    let mut cmd = None;
+    let mut when = UNIX_EPOCH;
+    let mut exit_status = None;
+    let mut duration = None;
+    let mut cwd = None;
+    let mut paths = Vec::new();
+    if !reader.eat(b'}') {
+        loop {
+            let key = reader.string()?;
+            reader.expect(b':')?;
+            match key.as_slice() {
+                b"cmd" => cmd = Some(bytes2wcstring(&reader.string()?)),
+                b"when" => {
+                    if let Some(seconds) = reader.nullable(JsonReader::integer)? {
+                        when = time_from_seconds(seconds);
+                    }
+                }
+                b"status" => {
+                    exit_status = reader
+                        .nullable(JsonReader::integer)?
+                        .and_then(|status| i32::try_from(status).ok());
+                }
+                b"duration" => {
+                    duration = reader
+                        .nullable(JsonReader::integer)?
+                        .and_then(|millis| u64::try_from(millis).ok())
+                        .map(Duration::from_millis);
+                }
+                b"cwd" => {
+                    cwd = reader
+                        .nullable(JsonReader::string)?
+                        .map(|s| bytes2wcstring(&s))
+                }
+                b"paths" => {
+                    reader.expect(b'[')?;
+                    if !reader.eat(b']') {
+                        loop {
+                            paths.push(bytes2wcstring(&reader.string()?));
+                            if !reader.eat(b',') {
+                                break;
+                            }
+                        }
+                        reader.expect(b']')?;
+                    }
+                }
+                _ => reader.skip_value()?,
+            }
+            if !reader.eat(b',') {
+                break;
+            }
+        }
+        reader.expect(b'}')?;
+    }
+    if !reader.at_end() {
+        return None;
+    }
+
+    let mut result = HistoryItem::new(cmd?, when, PersistenceMode::Disk);
+    result.set_required_paths(paths);
+    result.set_exit_status(exit_status);
+    result.set_duration(duration);
+    result.set_cwd(cwd);
+    Some(result)
+}
@@ -1,6 +1,7 @@
 mod file;
 #[allow(clippy::module_inception)]
 pub mod history;
+mod jsonl;
 mod yaml_backend;
 
 pub use history::*;
@@ -14,11 +14,27 @@
 //
 //   - cmd: ssh blah blah blah
 //     when: 2348237
+//     status: 0
+//     duration: 1520
+//     cwd: /home/me
 //     paths:
 //       - /path/to/something
 //       - /path/to/something_else
 //
 //   Newlines are replaced by \n. Backslashes are replaced by \\.
+//
+//   The status (exit status), duration (in milliseconds) and cwd keys are optional; files written
+//   by older versions lack them, and older versions ignore them.
+//
+//   Commands are written when they start, before their outcome is known. Once they finish, an
+//   outcome record is appended, which applies to the most recent item with the same command:
+//
+//   - outcome: ssh blah blah blah
+//     when: 2348240
+//     status: 0
+//     duration: 1520
+//
+//   Older versions skip outcome records like any other line they do not understand.
 
 /// Read one line, stripping off any newline, returning the number of bytes consumed.
 fn read_line(data: &[u8]) -> (usize, &[u8]) {
@@ -131,7 +147,7 @@ pub fn extract_prefix_and_unescape_yaml(line: &[u8]) -> Option<(Cow<'_, [u8]>, C
     Some((key, value))
 }
 
-fn time_from_seconds(offset: i64) -> SystemTime {
+pub fn time_from_seconds(offset: i64) -> SystemTime {
     if let Ok(n) = u64::try_from(offset) {
         UNIX_EPOCH + Duration::from_secs(n)
     } else {
@@ -155,6 +171,9 @@ pub fn decode_item_fish_2_0(mut data: &[u8]) -> Option<HistoryItem> {
     // Read the remaining lines.
     let mut indent = None;
     let mut when = UNIX_EPOCH;
+    let mut exit_status = None;
+    let mut duration = None;
+    let mut cwd = None;
     let mut paths = Vec::new();
     loop {
         let (advance, line) = read_line(data);
@@ -180,6 +199,17 @@ pub fn decode_item_fish_2_0(mut data: &[u8]) -> Option<HistoryItem> {
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(0),
             );
+        } else if *key == *b"status" {
+            exit_status = std::str::from_utf8(&value)
+                .ok()
+                .and_then(|s| s.parse().ok());
+        } else if *key == *b"duration" {
+            duration = std::str::from_utf8(&value)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .map(Duration::from_millis);
+        } else if *key == *b"cwd" {
+            cwd = Some(bytes2wcstring(&value));
         } else if *key == *b"paths" {
             // Read lines starting with " - " until we can't read any more.
             loop {
@@ -204,9 +234,66 @@ pub fn decode_item_fish_2_0(mut data: &[u8]) -> Option<HistoryItem> {
 
     let mut result = HistoryItem::new(cmd, when, PersistenceMode::Disk);
     result.set_required_paths(paths);
+    result.set_exit_status(exit_status);
+    result.set_duration(duration);
+    result.set_cwd(cwd);
     Some(result)
 }
 
+/// Return the (still escaped) command of the item or outcome record at the start of `data`.
+pub fn record_command_fish_2_0(data: &[u8]) -> &[u8] {
+    let (_advance, line) = read_line(data);
+    let line = trim_start(line);
+    match line.iter().position(|&c| c == b':') {
+        Some(colon) => trim_start(&line[colon + 1..]),
+        None => b"",
+    }
+}
+
+/// Decode the exit status and duration of an outcome record.
+pub fn decode_outcome_fish_2_0(data: &[u8]) -> Option<(Option<i32>, Option<Duration>)> {
+    let (advance, line) = read_line(data);
+    if !trim_start(line).starts_with(b"- outcome") {
+        return None;
+    }
+
+    let mut data = &data[advance..];
+    let mut exit_status = None;
+    let mut duration = None;
+    loop {
+        let (advance, line) = read_line(data);
+        let (indent, line) = trim_leading_spaces(line);
+        if indent == 0 {
+            break;
+        }
+        let Some((key, value)) = extract_prefix_and_unescape_yaml(line) else {
+            break;
+        };
+        data = &data[advance..];
+
+        if *key == *b"status" {
+            exit_status = std::str::from_utf8(&value)
+                .ok()
+                .and_then(|s| s.parse().ok());
+        } else if *key == *b"duration" {
+            duration = std::str::from_utf8(&value)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .map(Duration::from_millis);
+        }
+    }
+    Some((exit_status, duration))
+}
+
+/// The kind of a record in a history file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordKind {
+    /// A history item.
+    Item,
+    /// The outcome of an item that was written before it finished.
+    Outcome,
+}
+
 /// Parse a timestamp line that looks like this: spaces, "when:", spaces, timestamp, newline
 /// We know the string contains a newline, so stop when we reach it.
 fn parse_timestamp(s: &[u8]) -> Option<SystemTime> {
@@ -226,15 +313,15 @@ fn complete_lines(s: &[u8]) -> impl Iterator<Item = &[u8]> {
     lines
 }
 
-/// Support for iteratively locating the offsets of history items.
+/// Support for iteratively locating the offsets of history items and outcome records.
 /// Pass the file contents and a mutable reference to a `cursor`, initially 0.
-/// If `cutoff_timestamp` is given, skip items created at or after that timestamp.
+/// If `cutoff_timestamp` is given, skip records created at or after that timestamp.
 /// Returns [`None`] when done.
-pub fn offset_of_next_item_fish_2_0(
+pub fn offset_of_next_record_fish_2_0(
     contents: &[u8],
     cursor: &mut usize,
     cutoff_timestamp: Option<SystemTime>,
-) -> Option<usize> {
+) -> Option<(usize, RecordKind)> {
     let mut lines = complete_lines(&contents[*cursor..]).peekable();
     while let Some(mut line) = lines.next() {
         // Skip lines with a leading space, since these are in the interior of one of our items.
@@ -269,16 +356,20 @@ pub fn offset_of_next_item_fish_2_0(
             continue;
         }
 
-        if !line.starts_with(b"- cmd") {
+        let kind = if line.starts_with(b"- cmd") {
+            RecordKind::Item
+        } else if line.starts_with(b"- outcome") {
+            RecordKind::Outcome
+        } else {
             flog!(
                 history,
                 "ignoring corrupted history entry around offset",
                 *cursor
             );
             continue;
-        }
+        };
 
-        // At this point, we know `line` is at the beginning of an item. But maybe we want to
+        // At this point, we know `line` is at the beginning of a record. But maybe we want to
         // skip this item because of timestamps. A `None` cutoff means we don't care; if we do care,
         // then try parsing out a timestamp.
         if let Some(cutoff_timestamp) = cutoff_timestamp {
@@ -326,7 +417,7 @@ unsafe fn offset(from: &[u8], to: &[u8]) -> usize {
             None => contents.len(),
         };
 
-        return Some(unsafe { offset(contents, line) });
+        return Some((unsafe { offset(contents, line) }, kind));
     }
 
     None
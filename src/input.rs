@@ -169,6 +169,7 @@ const fn make_md(name: &'static wstr, code: ReadlineCmd) -> InputFunctionMetadat
     make_md(L!("history-pager"), ReadlineCmd::HistoryPager),
     #[allow(deprecated)]
     make_md(L!("history-pager-delete"), ReadlineCmd::HistoryPagerDelete),
+    make_md(L!("history-pager-toggle-regex"), ReadlineCmd::HistoryPagerToggleRegex),
     make_md(L!("history-prefix-search-backward"), ReadlineCmd::HistoryPrefixSearchBackward),
     make_md(L!("history-prefix-search-forward"), ReadlineCmd::HistoryPrefixSearchForward),
     make_md(L!("history-search-backward"), ReadlineCmd::HistorySearchBackward),
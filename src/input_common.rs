@@ -74,6 +74,7 @@ pub enum ReadlineCmd {
     HistoryPager,
     #[deprecated]
     HistoryPagerDelete,
+    HistoryPagerToggleRegex,
     DeleteChar,
     BackwardDeleteChar,
     KillLine,
@@ -18,7 +18,7 @@
 use nix::fcntl::OFlag;
 use nix::sys::stat::Mode;
 use std::fs::File;
-use std::io;
+use std::io::{self, Seek as _, Write as _};
 use std::os::fd::{AsFd as _, AsRawFd as _, BorrowedFd, OwnedFd, RawFd};
 use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
 
@@ -579,6 +579,16 @@ pub fn append_from_specs(&mut self, specs: &RedirectionSpecList, pwd: &wstr) ->
                         self.push(Arc::new(IoFd::new(spec.fd, target_fd)));
                     }
                 }
+                RedirectionMode::HereString | RedirectionMode::HereDoc => {
+                    match inline_text_file(&spec.target) {
+                        Ok(file) => self.push(Arc::new(IoFile::new(spec.fd, file))),
+                        Err(err) => {
+                            perror_io("heredoc", &err);
+                            self.push(Arc::new(IoClose::new(spec.fd)));
+                            have_error = true;
+                        }
+                    }
+                }
                 _ => {
                     // We have a path-based redirection. Resolve it to a file.
                     // Mark it as CLO_EXEC because we don't want it to be open in any child.
@@ -907,6 +917,16 @@ pub fn is_stdin_closed(&self) -> bool {
     }
 }
 
+/// Return a file to read the text of a heredoc or here-string from.
+/// The file is unlinked, so it goes away once it is closed.
+fn inline_text_file(text: &wstr) -> io::Result<File> {
+    let mut tmpfile = fish_tempfile::new_file()?;
+    let file = tmpfile.get_mut();
+    file.write_all(&wcs2bytes(text))?;
+    file.rewind()?;
+    file.try_clone()
+}
+
 /// File redirection error message.
 const FILE_ERROR: &wstr = L!("An error occurred while redirecting file '%s'");
 const NOCLOB_ERROR: &wstr = L!("The file '%s' already exists");
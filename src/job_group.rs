@@ -2,10 +2,12 @@
 use crate::prelude::*;
 use crate::proc::{JobGroupRef, Pid};
 use crate::signal::Signal;
+use crate::topic_monitor::{Topic, topic_monitor_principal};
+use nix::sys::signal::Signal as NixSignal;
 use nix::sys::termios::Termios;
 use std::cell::RefCell;
 use std::num::NonZeroU32;
-use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::atomic::{AtomicI32, AtomicU8, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 
 /// A job ID, corresponding to what is printed by `jobs`. 1 is the first valid job ID.
@@ -48,6 +50,39 @@ fn to_arg(self) -> fish_printf::Arg<'a> {
     }
 }
 
+/// How far the timeout of a job group has progressed.
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub enum TimeoutStage {
+    /// The time is not up yet.
+    #[default]
+    Running,
+    /// The time is up, and the processes in the group are sent the timeout's signal.
+    Expired,
+    /// The processes did not exit after the signal, and are sent SIGKILL.
+    Killing,
+}
+
+/// A time limit on a job group, set by the `timeout` builtin. A timer thread advances the stage,
+/// and the main thread signals the processes of the group's jobs while it waits for them.
+#[derive(Debug)]
+pub struct JobTimeout {
+    /// The signal sent once the time is up.
+    signal: NixSignal,
+    stage: AtomicU8,
+    /// The group the `timeout` builtin itself ran in, whose timeouts apply as well.
+    parent: Option<JobGroupRef>,
+}
+
+impl JobTimeout {
+    fn stage(&self) -> TimeoutStage {
+        match self.stage.load(Ordering::Relaxed) {
+            0 => TimeoutStage::Running,
+            1 => TimeoutStage::Expired,
+            _ => TimeoutStage::Killing,
+        }
+    }
+}
+
 /// `JobGroup` is conceptually similar to the idea of a process group. It represents data which
 /// is shared among all of the "subjobs" that may be spawned by a single job.
 /// For example, two fish functions in a pipeline may themselves spawn multiple jobs, but all will
@@ -83,6 +118,8 @@ pub struct JobGroup {
     /// The signal causing the group to cancel or `0` if none.
     /// Not using an `Option<Signal>` to be able to atomically load/store to this field.
     signal: AtomicI32,
+    /// The time limit on the group, if any.
+    timeout: Option<JobTimeout>,
 }
 
 // safety: all fields without interior mutabillity are only written to once
@@ -117,10 +154,15 @@ pub fn has_job_id(&self) -> bool {
         self.job_id.is_some()
     }
 
-    /// Gets the cancellation signal, if any.
+    /// Gets the cancellation signal, if any. A group with a timeout is also cancelled with the
+    /// group it is nested in.
     pub fn get_cancel_signal(&self) -> Option<Signal> {
         match self.signal.load(Ordering::Relaxed) {
-            0 => None,
+            0 => self
+                .timeout
+                .as_ref()
+                .and_then(|timeout| timeout.parent.as_ref())
+                .and_then(|parent| parent.get_cancel_signal()),
             s => Some(Signal::new(s)),
         }
     }
@@ -134,6 +176,36 @@ pub fn cancel_with_signal(&self, signal: Signal) {
             .ok();
     }
 
+    /// Return how far the timeout of this group, or of a group it is nested in, has progressed, with
+    /// the signal to send once it expired. Return None if there is no timeout.
+    pub fn timeout_stage(&self) -> Option<(TimeoutStage, NixSignal)> {
+        let timeout = self.timeout.as_ref()?;
+        let own = (timeout.stage(), timeout.signal);
+        let outer = timeout.parent.as_ref().and_then(|p| p.timeout_stage());
+        match outer {
+            Some(outer) if outer.0 > own.0 => Some(outer),
+            _ => Some(own),
+        }
+    }
+
+    /// Return whether the time of this group is up.
+    pub fn timed_out(&self) -> bool {
+        self.timeout_stage()
+            .is_some_and(|(stage, _)| stage != TimeoutStage::Running)
+    }
+
+    /// Move the timeout of this group to the given stage, and cancel the group. This is called
+    /// from the timer thread.
+    pub fn advance_timeout(&self, stage: TimeoutStage) {
+        let timeout = self.timeout.as_ref().expect("Job group has no timeout");
+        timeout.stage.store(stage as u8, Ordering::Relaxed);
+        self.cancel_with_signal(Signal::new(timeout.signal as i32));
+        // Wake up the main thread if it is waiting for processes, so it sends the signal. This
+        // looks like a process exited to it, and it goes back to waiting once it finds none did.
+        topic_monitor_principal().post(Topic::SigChld);
+        topic_monitor_principal().post(Topic::InternalExit);
+    }
+
     /// Set the pgid for this job group, latching it to this value. This should only be called if
     /// job control is active for this group. The pgid should not already have been set, and should
     /// be different from fish's pgid. Of course this does not keep the pgid alive by itself.
@@ -221,6 +293,7 @@ pub fn new(command: WString, id: MaybeJobId, job_control: bool, wants_term: bool
             signal: 0.into(),
             is_foreground: RelaxedAtomicBool::new(false),
             pgid: OnceLock::new(),
+            timeout: None,
         }
     }
 
@@ -251,6 +324,32 @@ pub fn create_with_job_control(command: WString, wants_term: bool) -> JobGroupRe
             wants_term,
         ))
     }
+
+    /// Return a new `JobGroup` for the `timeout` builtin, nested in the `parent` group that the
+    /// builtin runs in. It has a [`JobId`] so that the jobs run by the builtin join it, but no job
+    /// control. Its processes are sent `signal` once the timeout is advanced, see
+    /// [`JobGroup::advance_timeout`].
+    pub fn create_with_timeout(
+        command: WString,
+        signal: NixSignal,
+        parent: Option<JobGroupRef>,
+    ) -> JobGroupRef {
+        let mut group = JobGroup::new(
+            command,
+            MaybeJobId(Some(JobId::acquire())),
+            false, /* job_control */
+            false, /* wants_term */
+        );
+        group
+            .is_foreground
+            .store(parent.as_ref().is_none_or(|parent| parent.is_foreground()));
+        group.timeout = Some(JobTimeout {
+            signal,
+            stage: AtomicU8::new(TimeoutStage::Running as u8),
+            parent,
+        });
+        Arc::new(group)
+    }
 }
 
 impl Drop for JobGroup {
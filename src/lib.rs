@@ -14,6 +14,7 @@
 pub mod autoload;
 pub mod builtins;
 pub mod complete;
+pub mod debugger;
 pub mod editable_line;
 pub mod env;
 pub mod env_dispatch;
@@ -30,6 +31,7 @@
 pub mod function;
 pub mod future_feature_flags;
 pub mod global_safety;
+pub mod help_completions;
 pub mod highlight;
 pub mod history;
 pub mod input;
@@ -38,6 +40,7 @@
 pub mod job_group;
 pub mod key;
 pub mod kill;
+pub mod lint;
 pub mod locale;
 pub mod localization;
 pub mod nix;
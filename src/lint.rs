@@ -0,0 +1,486 @@
+//! Static checks for fish scripts, which find likely mistakes that are not syntax errors.
+//! This backs `fish_indent --lint`.
+
+use crate::ast::{self, BlockStatementHeader, Kind, Node, Statement};
+use crate::builtins::shared::{
+    BUILTIN_ERR_MISSING, BUILTIN_ERR_UNEXP_ARG, BUILTIN_ERR_UNKNOWN, builtin_options,
+};
+use crate::common::{valid_var_name, valid_var_name_char};
+use crate::parse_constants::{ParseErrorList, ParseTreeFlags, StatementDecoration};
+use crate::parse_util::detect_parse_errors;
+use crate::prelude::*;
+use fish_wgetopt::WGetopter;
+use std::collections::HashSet;
+
+/// A problem found in a script.
+pub struct LintDiagnostic {
+    /// The offset of the offending code in the script.
+    pub source_start: usize,
+    pub message: WString,
+}
+
+/// Check a script. Syntax errors are reported as they are by fish; only if there are none is the
+/// script checked further. The diagnostics are sorted by their position.
+pub fn lint(src: &wstr) -> Vec<LintDiagnostic> {
+    let mut errors = ParseErrorList::new();
+    if detect_parse_errors(src, Some(&mut errors), false).is_err() {
+        return errors
+            .into_iter()
+            .map(|err| LintDiagnostic {
+                source_start: err.source_start,
+                message: err.text,
+            })
+            .collect();
+    }
+
+    let ast = ast::parse(src, ParseTreeFlags::default(), None);
+    let mut linter = Linter {
+        src,
+        diagnostics: vec![],
+        local_only: HashSet::new(),
+        scopes: vec![],
+    };
+    linter.lint_function_body(ast.top(), vec![]);
+    linter.diagnostics.sort_by_key(|d| d.source_start);
+    linter.diagnostics
+}
+
+/// Stands in for arguments whose value is only known when the script runs.
+const UNKNOWN_ARG: &wstr = L!("_");
+
+/// Return the arguments of a command as option parsing sees them, with those that need expanding
+/// replaced by [`UNKNOWN_ARG`].
+fn literal_args<'s>(src: &'s wstr, stmt: &ast::DecoratedStatement) -> Vec<&'s wstr> {
+    let mut args = vec![stmt.command.source(src)];
+    for arg in stmt.args_or_redirs.iter().filter(|a| a.is_argument()) {
+        let arg = arg.argument().source(src);
+        if arg.chars().any(|c| "$()'\"\\*?{}~".contains(c)) {
+            args.push(UNKNOWN_ARG);
+        } else {
+            args.push(arg);
+        }
+    }
+    args
+}
+
+/// If a statement sets variables with `set` or `read`, return their names and whether they are
+/// set with `--local`.
+fn variable_definition(src: &wstr, stmt: &ast::DecoratedStatement) -> Option<(Vec<WString>, bool)> {
+    let cmd = stmt.command.source(src);
+    let (short, long) = match cmd.to_string().as_str() {
+        "set" | "read" => builtin_options(cmd).unwrap(),
+        _ => return None,
+    };
+    let mut args = literal_args(src, stmt);
+    let mut w = WGetopter::new(short, long, &mut args);
+    let mut local = false;
+    while let Some(c) = w.next_opt() {
+        match c {
+            'l' => local = true,
+            // Errors, and --help.
+            '?' | ':' | ';' | 'h' => return None,
+            // Not setting anything: --erase, --query, --names and --show.
+            'e' | 'q' | 'n' | 'S' if cmd == "set" => return None,
+            _ => (),
+        }
+    }
+    let names = &w.argv[w.wopt_index..];
+    let names = if cmd == "set" {
+        &names[..names.len().min(1)]
+    } else {
+        names
+    };
+    let names = names
+        .iter()
+        .map(|name| {
+            // `set -l list[1] value` sets an element.
+            let end = name.find_char('[').unwrap_or(name.len());
+            name[..end].to_owned()
+        })
+        .filter(|name| valid_var_name(name))
+        .collect();
+    Some((names, local))
+}
+
+/// Return the variables expanded in a token, with their offsets in it and whether the expansion
+/// is within double quotes.
+fn variable_expansions(token: &wstr) -> Vec<(usize, WString, bool)> {
+    let chars = token.as_char_slice();
+    let mut result = vec![];
+    let mut in_double_quotes = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '\'' if !in_double_quotes => {
+                i += 1;
+                while i < chars.len() && chars[i] != '\'' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            '"' => in_double_quotes = !in_double_quotes,
+            '$' => {
+                let start = i;
+                while i + 1 < chars.len() && chars[i + 1] == '$' {
+                    i += 1;
+                }
+                let name_start = i + 1;
+                while i + 1 < chars.len() && valid_var_name_char(chars[i + 1]) {
+                    i += 1;
+                }
+                if name_start <= i {
+                    let name = WString::from_chars(&chars[name_start..=i]);
+                    result.push((start, name, in_double_quotes));
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    result
+}
+
+struct Linter<'s> {
+    src: &'s wstr,
+    diagnostics: Vec<LintDiagnostic>,
+    /// Variables which the current function only ever sets with `--local`. These are the ones
+    /// whose uses can be checked, since other variables may come from the caller or be global.
+    local_only: HashSet<WString>,
+    /// The local variables set so far in each block of the current function, innermost last.
+    scopes: Vec<HashSet<WString>>,
+}
+
+impl<'s> Linter<'s> {
+    fn report(&mut self, source_start: usize, message: WString) {
+        self.diagnostics.push(LintDiagnostic {
+            source_start,
+            message,
+        });
+    }
+
+    /// Check a function body, or the top level of a script. `params` are the variables defined by
+    /// the function's options, like `--argument-names`.
+    fn lint_function_body(&mut self, jobs: &ast::JobList, params: Vec<WString>) {
+        let mut locals = HashSet::new();
+        let mut others: HashSet<WString> = params.into_iter().collect();
+        let mut traversal = ast::Traversal::new(jobs);
+        while let Some(node) = traversal.next() {
+            match node.kind() {
+                Kind::BlockStatement(block) => {
+                    // Nested functions have their own variables.
+                    if let BlockStatementHeader::Function(_) = block.header {
+                        traversal.skip_children(node);
+                    }
+                }
+                Kind::ForHeader(header) => {
+                    others.insert(header.var_name.source(self.src).to_owned());
+                    for var in header.more_var_names.iter() {
+                        others.insert(var.var_name.source(self.src).to_owned());
+                    }
+                    if let Some(index) = &header.index {
+                        others.insert(index.var_name.source(self.src).to_owned());
+                    }
+                }
+                Kind::DecoratedStatement(stmt) => {
+                    if let Some((names, local)) = variable_definition(self.src, stmt) {
+                        if local {
+                            locals.extend(names);
+                        } else {
+                            others.extend(names);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        let local_only = locals.difference(&others).cloned().collect();
+
+        let outer_local_only = std::mem::replace(&mut self.local_only, local_only);
+        let outer_scopes = std::mem::replace(&mut self.scopes, vec![HashSet::new()]);
+        self.lint_job_list(jobs);
+        self.local_only = outer_local_only;
+        self.scopes = outer_scopes;
+    }
+
+    fn lint_in_scope(&mut self, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashSet::new());
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn lint_job_list(&mut self, jobs: &ast::JobList) {
+        let mut exited_by = None;
+        let mut reported = false;
+        for job in jobs.iter() {
+            if let Some(cmd) = exited_by {
+                // Only report the first unreachable job.
+                if !reported {
+                    self.report(
+                        job.source_range().start(),
+                        wgettext_fmt!("Unreachable code after '%s'", cmd),
+                    );
+                    reported = true;
+                }
+            } else {
+                exited_by = self.exiting_command(job);
+            }
+            self.lint_job_conjunction(job);
+        }
+    }
+
+    /// If a job always leaves the current block, like `return`, return its command.
+    fn exiting_command(&self, job: &ast::JobConjunction) -> Option<&'s wstr> {
+        if job.decorator.is_some()
+            || !job.continuations.is_empty()
+            || !job.job.continuation.is_empty()
+            || job.job.bg.is_some()
+        {
+            return None;
+        }
+        let stmt = job.job.statement.as_decorated_statement()?;
+        if ![StatementDecoration::None, StatementDecoration::Builtin].contains(&stmt.decoration()) {
+            return None;
+        }
+        let cmd = stmt.command.source(self.src);
+        ["return", "exit", "break", "continue"]
+            .iter()
+            .any(|c| cmd == *c)
+            .then_some(cmd)
+    }
+
+    fn lint_job_conjunction(&mut self, job: &ast::JobConjunction) {
+        self.lint_job(&job.job);
+        for continuation in job.continuations.iter() {
+            self.lint_job(&continuation.job);
+        }
+    }
+
+    fn lint_job(&mut self, job: &ast::JobPipeline) {
+        for var in job.variables.iter() {
+            self.check_uses(var);
+        }
+        self.lint_statement(&job.statement);
+        for continuation in job.continuation.iter() {
+            for var in continuation.variables.iter() {
+                self.check_uses(var);
+            }
+            self.lint_statement(&continuation.statement);
+        }
+    }
+
+    fn lint_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Decorated(stmt) => self.lint_decorated_statement(stmt),
+            Statement::Not(stmt) => {
+                for var in stmt.variables.iter() {
+                    self.check_uses(var);
+                }
+                self.lint_statement(&stmt.contents);
+            }
+            Statement::Block(block) => {
+                match &block.header {
+                    BlockStatementHeader::Function(header) => {
+                        // Treat every word as a parameter name, which is close enough.
+                        let params = header
+                            .args
+                            .iter()
+                            .map(|arg| arg.source(self.src).to_owned())
+                            .filter(|arg| valid_var_name(arg))
+                            .collect();
+                        self.lint_function_body(&block.jobs, params);
+                    }
+                    BlockStatementHeader::For(header) => {
+                        for arg in header.args.iter() {
+                            self.check_uses(arg);
+                        }
+                        self.lint_in_scope(|l| l.lint_job_list(&block.jobs));
+                    }
+                    BlockStatementHeader::While(header) => self.lint_in_scope(|l| {
+                        l.lint_job_conjunction(&header.condition);
+                        for job in header.andor_tail.iter() {
+                            l.lint_job_conjunction(&job.job);
+                        }
+                        l.lint_job_list(&block.jobs);
+                    }),
+                    BlockStatementHeader::Begin(_) => {
+                        self.lint_in_scope(|l| l.lint_job_list(&block.jobs));
+                    }
+                }
+                self.check_redirections(&block.args_or_redirs);
+            }
+            Statement::Brace(brace) => {
+                self.lint_in_scope(|l| l.lint_job_list(&brace.jobs));
+                self.check_redirections(&brace.args_or_redirs);
+            }
+            Statement::If(stmt) => {
+                self.lint_in_scope(|l| {
+                    let clauses = std::iter::once(&stmt.if_clause)
+                        .chain(stmt.elseif_clauses.iter().map(|c| &c.if_clause));
+                    for clause in clauses {
+                        l.lint_job_conjunction(&clause.condition);
+                        for job in clause.andor_tail.iter() {
+                            l.lint_job_conjunction(&job.job);
+                        }
+                        l.lint_job_list(&clause.body);
+                    }
+                    if let Some(else_clause) = &stmt.else_clause {
+                        l.lint_job_list(&else_clause.body);
+                    }
+                });
+                self.check_redirections(&stmt.args_or_redirs);
+            }
+            Statement::Switch(stmt) => {
+                self.check_uses(&stmt.argument);
+                self.lint_in_scope(|l| {
+                    for case in stmt.cases.iter() {
+                        for arg in case.arguments.iter() {
+                            l.check_uses(arg);
+                        }
+                        l.lint_job_list(&case.body);
+                    }
+                });
+                self.check_redirections(&stmt.args_or_redirs);
+            }
+            Statement::Try(stmt) => {
+                self.lint_in_scope(|l| l.lint_job_list(&stmt.body));
+                if let Some(catch) = &stmt.catch_clause {
+                    self.lint_in_scope(|l| l.lint_job_list(&catch.body));
+                }
+                self.check_redirections(&stmt.args_or_redirs);
+            }
+        }
+    }
+
+    fn check_redirections(&mut self, args_or_redirs: &ast::ArgumentOrRedirectionList) {
+        for arg_or_redir in args_or_redirs.iter() {
+            self.check_uses(arg_or_redir);
+        }
+    }
+
+    /// Report uses of variables which the current function only sets locally, where none of
+    /// those local variables is in scope.
+    fn check_uses(&mut self, node: &dyn Node) {
+        let Some(range) = node.try_source_range() else {
+            return;
+        };
+        let token = &self.src[range.as_usize()];
+        for (offset, name, _) in variable_expansions(token) {
+            if self.local_only.contains(&name) && !self.scopes.iter().any(|s| s.contains(&name)) {
+                self.report(
+                    range.start() + offset,
+                    wgettext_fmt!(
+                        "$%s is not set here, it is only set locally in another block or further down",
+                        name
+                    ),
+                );
+            }
+        }
+    }
+
+    fn lint_decorated_statement(&mut self, stmt: &ast::DecoratedStatement) {
+        self.check_uses(&stmt.command);
+        for arg_or_redir in stmt.args_or_redirs.iter() {
+            self.check_uses(arg_or_redir);
+        }
+
+        let args: Vec<&ast::Argument> = stmt
+            .args_or_redirs
+            .iter()
+            .filter(|a| a.is_argument())
+            .map(|a| a.argument())
+            .collect();
+        for arg in &args {
+            if arg.source(self.src).starts_with("%self") {
+                self.report(
+                    arg.source_range().start(),
+                    wgettext!("%self is deprecated, use $fish_pid").to_owned(),
+                );
+            }
+        }
+
+        if [StatementDecoration::None, StatementDecoration::Builtin].contains(&stmt.decoration()) {
+            let cmd = stmt.command.source(self.src);
+            if cmd == "test" || cmd == "[" {
+                self.check_test_arguments(&args);
+            }
+            self.check_builtin_options(stmt);
+        }
+
+        if let Some((names, true)) = variable_definition(self.src, stmt) {
+            self.scopes.last_mut().unwrap().extend(names);
+        }
+    }
+
+    /// Report `test -n $var`, which succeeds if the variable is empty, since test then only sees
+    /// one argument. The same goes for `-z`.
+    fn check_test_arguments(&mut self, args: &[&ast::Argument]) {
+        for (i, pair) in args.windows(2).enumerate() {
+            let op = pair[0].source(self.src);
+            if op != "-n" && op != "-z" {
+                continue;
+            }
+            // The operator must start an expression, or `test "$a" = -n $b` would be reported.
+            if i > 0
+                && !["!", "-a", "-o"]
+                    .iter()
+                    .any(|s| args[i - 1].source(self.src) == *s)
+            {
+                continue;
+            }
+            let operand = pair[1].source(self.src);
+            if variable_expansions(operand)
+                .iter()
+                .any(|(_, _, quoted)| !quoted)
+            {
+                self.report(
+                    pair[1].source_range().start(),
+                    wgettext_fmt!(
+                        "Unquoted variable after 'test %s' is not one argument if it is empty or a list, quote it",
+                        op
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_builtin_options(&mut self, stmt: &ast::DecoratedStatement) {
+        let cmd = stmt.command.source(self.src);
+        let Some((short, long)) = builtin_options(cmd) else {
+            return;
+        };
+        let orig_args = literal_args(self.src, stmt);
+        let mut args = orig_args.clone();
+        let mut w = WGetopter::new(short, long, &mut args);
+        while let Some(c) = w.next_opt() {
+            let error = match c {
+                '?' => BUILTIN_ERR_UNKNOWN,
+                ':' => BUILTIN_ERR_MISSING,
+                ';' => BUILTIN_ERR_UNEXP_ARG,
+                _ => continue,
+            };
+            // For an unknown option in the middle of a group like `-lz`, the group is the
+            // current argument.
+            let idx = if w.remaining_text.is_empty() {
+                w.wopt_index - 1
+            } else {
+                w.wopt_index
+            };
+            let arg = w.argv[idx];
+            let Some(pos) = orig_args.iter().position(|a| std::ptr::eq(*a, arg)) else {
+                return;
+            };
+            let node = stmt
+                .args_or_redirs
+                .iter()
+                .filter(|a| a.is_argument())
+                .nth(pos - 1)
+                .unwrap();
+            self.report(node.source_range().start(), wgettext_fmt!(error, cmd, arg));
+            // Option parsing stops at the first error, like in the builtin.
+            return;
+        }
+    }
+}
@@ -12,7 +12,9 @@
 use crate::highlight::{HighlightRole, HighlightSpec, highlight_shell};
 use crate::operation_context::OperationContext;
 use crate::prelude::*;
-use crate::screen::{CharOffset, Line, ScreenData, wcswidth_rendered, wcwidth_rendered};
+use crate::screen::{
+    CharOffset, Line, ScreenData, escape_code_length, wcswidth_rendered, wcwidth_rendered,
+};
 use crate::termsize::Termsize;
 use fish_wcstringutil::string_fuzzy_match_string;
 use unicode_width::UnicodeWidthStr as _;
@@ -78,6 +80,9 @@ pub enum SelectionMotion {
 /// Width of the search field.
 const PAGER_SEARCH_FIELD_WIDTH: usize = 12;
 
+/// The maximum number of rows of the preview of the selected completion.
+const PAGER_MAX_PREVIEW_ROWS: usize = 10;
+
 localizable_consts!(
     /// Text we use for the search field.
     SEARCH_FIELD_PROMPT
@@ -118,6 +123,12 @@ pub struct Pager {
 
     // Extra text to display at the bottom of the pager.
     pub extra_progress_text: WString,
+
+    // Whether completions are still being computed.
+    loading: bool,
+
+    // The preview of the selected completion, shown below the completions.
+    preview: Vec<WString>,
 }
 
 impl Pager {
@@ -179,6 +190,13 @@ fn completion_try_print(
         let mut term_height = self.available_term_height
             // we always subtract 1 to make room for a comment row
                 - 1 - if self.search_field_shown { 1 } else { 0 };
+        // Make room for the preview, but leave at least half of the rows to the completions.
+        let preview_rows = self
+            .preview
+            .len()
+            .min(PAGER_MAX_PREVIEW_ROWS)
+            .min(term_height / 2);
+        term_height -= preview_rows;
         if !self.fully_disclosed {
             // We disclose between half and the entirety of the terminal height,
             // but at least 4 rows.
@@ -282,6 +300,8 @@ fn completion_try_print(
             // these are the "past the last value".
             progress_text =
                 wgettext_fmt!("rows %u to %u of %u", start_row + 1, stop_row, row_count);
+        } else if self.loading {
+            progress_text = wgettext_fmt!("Loading completions%s", get_ellipsis_str());
         } else if self.search_field_shown && self.completion_infos.is_empty() {
             // Everything is filtered.
             progress_text = wgettext!("(no matches)").to_owned();
@@ -306,6 +326,20 @@ fn completion_try_print(
             );
         }
 
+        // Add the preview of the selected completion.
+        for preview_line in &self.preview[..preview_rows] {
+            let line = rendering.screen_data.add_line();
+            let spec = HighlightSpec::with_both(HighlightRole::pager_description);
+            print_max(
+                CharOffset::None,
+                preview_line,
+                spec,
+                term_width,
+                /*has_more=*/ false,
+                line,
+            );
+        }
+
         if !self.search_field_shown {
             return true;
         }
@@ -1007,6 +1041,8 @@ pub fn clear(&mut self) {
         self.fully_disclosed = false;
         self.search_field_shown = false;
         self.extra_progress_text.clear();
+        self.loading = false;
+        self.preview.clear();
         self.suggested_row_start = 0;
     }
 
@@ -1030,6 +1066,28 @@ pub fn is_search_field_shown(&self) -> bool {
         self.search_field_shown
     }
 
+    // Sets whether completions are still being computed. The pager says so even if it has no
+    // completions yet.
+    pub fn set_loading(&mut self, flag: bool) {
+        if self.loading != flag {
+            self.loading = flag;
+            self.have_unrendered_completions = true;
+        }
+    }
+
+    // Sets the preview of the selected completion. Escape sequences are removed and tabs are
+    // expanded, since the lines are drawn like the rest of the pager.
+    pub fn set_preview(&mut self, lines: &[WString]) {
+        let preview: Vec<WString> = lines
+            .iter()
+            .map(|line| sanitize_preview_line(line))
+            .collect();
+        if preview != self.preview {
+            self.preview = preview;
+            self.have_unrendered_completions = true;
+        }
+    }
+
     // Indicates if we are navigating our contents.
     // It's possible we have no visual selection but are still navigating the contents, e.g. every
     // completion is filtered.
@@ -1169,6 +1227,27 @@ fn print_max(
     print_max_impl(offset_in_cmdline, s, |_| color, max, has_more, line)
 }
 
+/// Remove escape sequences from a line of preview, and expand its tabs.
+fn sanitize_preview_line(line: &wstr) -> WString {
+    let mut result = WString::new();
+    let mut i = 0;
+    while i < line.len() {
+        let c = line.char_at(i);
+        if let Some(len) = escape_code_length(&line[i..]) {
+            i += len;
+            continue;
+        }
+        if c == '\t' {
+            let width = usize::try_from(wcswidth_rendered(&result)).unwrap_or(0);
+            result.extend(std::iter::repeat_n(' ', 8 - width % 8));
+        } else {
+            result.push(c);
+        }
+        i += 1;
+    }
+    result
+}
+
 /// Trim leading and trailing whitespace, and compress other whitespace runs into a single space.
 fn mangle_1_completion_description(s: &mut WString) {
     let mut leading = 0;
@@ -1288,7 +1367,7 @@ fn process_completions_into_infos(lst: &[Completion]) -> Vec<PagerComp> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Pager, SelectionMotion};
+    use super::{PAGER_MAX_PREVIEW_ROWS, PageRendering, Pager, SelectionMotion};
     use crate::common::get_ellipsis_char;
     use crate::complete::{CompleteFlags, Completion};
     use crate::prelude::*;
@@ -1501,4 +1580,75 @@ macro_rules! validate {
         pager.set_completions(&c4s, true);
         validate!(&mut pager, 30, L!("{\\␊Hello")); // }
     }
+
+    #[test]
+    #[serial]
+    fn test_pager_loading() {
+        let _cleanup = test_init();
+        let mut pager = Pager::default();
+        pager.set_term_size(&Termsize::defaults());
+        let mut rendering = pager.render();
+        assert_eq!(rendering.screen_data.line_count(), 0);
+
+        // The loading state is shown without completions.
+        pager.set_loading(true);
+        assert!(pager.rendering_needs_update(&rendering));
+        pager.update_rendering(&mut rendering);
+        assert_eq!(rendering.screen_data.line_count(), 1);
+        let line = rendering.screen_data.line(0);
+        let text = WString::from(Vec::from_iter((0..line.len()).map(|i| line.char_at(i))));
+        let mut expected = L!("Loading completions").to_owned();
+        expected.push(get_ellipsis_char());
+        assert_eq!(text, expected);
+
+        pager.set_loading(false);
+        assert!(pager.rendering_needs_update(&rendering));
+        pager.update_rendering(&mut rendering);
+        assert_eq!(rendering.screen_data.line_count(), 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_pager_preview() {
+        let _cleanup = test_init();
+        let completions = vec![Completion::new(
+            L!("abc").to_owned(),
+            "".into(),
+            StringFuzzyMatch::exact_match(),
+            CompleteFlags::default(),
+        )];
+        let mut pager = Pager::default();
+        pager.set_completions(&completions, true);
+        pager.set_term_size(&Termsize::defaults());
+        let mut rendering = pager.render();
+        assert_eq!(rendering.screen_data.line_count(), 1);
+
+        let line_text = |rendering: &PageRendering, idx: usize| {
+            let line = rendering.screen_data.line(idx);
+            WString::from(Vec::from_iter((0..line.len()).map(|i| line.char_at(i))))
+        };
+
+        // The preview is shown below the completions, without escape sequences and with tabs
+        // expanded.
+        pager.set_preview(&[L!("\x1b[1mfirst\x1b[0m").to_owned(), L!("a\tb").to_owned()]);
+        assert!(pager.rendering_needs_update(&rendering));
+        pager.update_rendering(&mut rendering);
+        assert_eq!(rendering.screen_data.line_count(), 3);
+        assert_eq!(line_text(&rendering, 1), L!("first"));
+        assert_eq!(line_text(&rendering, 2), L!("a       b"));
+
+        // Long previews are cut off.
+        let long_preview = vec![L!("line").to_owned(); 50];
+        pager.set_preview(&long_preview);
+        pager.update_rendering(&mut rendering);
+        assert_eq!(
+            rendering.screen_data.line_count(),
+            1 + PAGER_MAX_PREVIEW_ROWS
+        );
+
+        pager.set_preview(&[]);
+        assert!(pager.rendering_needs_update(&rendering));
+        pager.update_rendering(&mut rendering);
+        assert_eq!(rendering.screen_data.line_count(), 1);
+    }
 }
@@ -1,5 +1,6 @@
 //! Constants used in the programmatic representation of fish code.
 
+use crate::future_feature_flags::{FeatureFlag, feature_test};
 use crate::prelude::*;
 use fish_fallback::{fish_wcswidth, fish_wcwidth};
 use std::ops::{BitOr, BitOrAssign};
@@ -110,6 +111,7 @@ pub enum ParseKeyword {
     Begin,
     Builtin,
     Case,
+    Catch,
     Command,
     Else,
     End,
@@ -123,6 +125,7 @@ pub enum ParseKeyword {
     Or,
     Switch,
     Time,
+    Try,
     While,
 }
 
@@ -152,11 +155,13 @@ pub enum ParseErrorCode {
     TokenizerUnterminatedSubshell,
     TokenizerUnterminatedSlice,
     TokenizerUnterminatedEscape,
+    TokenizerUnterminatedHeredoc,
     TokenizerOther,
 
     UnbalancingEnd,         // end outside of block
     UnbalancingElse,        // else outside of if
     UnbalancingCase,        // case outside of switch
+    UnbalancingCatch,       // catch outside of try
     UnbalancingBrace,       // } outside of {
     BareVariableAssignment, // a=b without command
     AndOrInPipeline,        // "and" or "or" after a pipe
@@ -244,6 +249,7 @@ pub fn to_wstr(self) -> &'static wstr {
             ParseKeyword::Begin => L!("begin"),
             ParseKeyword::Builtin => L!("builtin"),
             ParseKeyword::Case => L!("case"),
+            ParseKeyword::Catch => L!("catch"),
             ParseKeyword::Command => L!("command"),
             ParseKeyword::Else => L!("else"),
             ParseKeyword::End => L!("end"),
@@ -257,6 +263,7 @@ pub fn to_wstr(self) -> &'static wstr {
             ParseKeyword::Or => L!("or"),
             ParseKeyword::Switch => L!("switch"),
             ParseKeyword::Time => L!("time"),
+            ParseKeyword::Try => L!("try"),
             ParseKeyword::While => L!("while"),
             _ => L!("unknown_keyword"),
         }
@@ -279,6 +286,7 @@ fn from(s: &wstr) -> Self {
             'b' if s == L!("begin") => ParseKeyword::Begin,
             'b' if s == L!("builtin") => ParseKeyword::Builtin,
             'c' if s == L!("case") => ParseKeyword::Case,
+            'c' if s == L!("catch") && feature_test(FeatureFlag::TryCatch) => ParseKeyword::Catch,
             'c' if s == L!("command") => ParseKeyword::Command,
             'e' if s == L!("else") => ParseKeyword::Else,
             'e' if s == L!("end") => ParseKeyword::End,
@@ -291,6 +299,7 @@ fn from(s: &wstr) -> Self {
             'o' if s == L!("or") => ParseKeyword::Or,
             's' if s == L!("switch") => ParseKeyword::Switch,
             't' if s == L!("time") => ParseKeyword::Time,
+            't' if s == L!("try") && feature_test(FeatureFlag::TryCatch) => ParseKeyword::Try,
             'w' if s == L!("while") => ParseKeyword::While,
             _ => ParseKeyword::None,
         }
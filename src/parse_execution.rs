@@ -6,17 +6,18 @@
 };
 use crate::builtins;
 use crate::builtins::shared::{
-    BUILTIN_ERR_VARNAME, STATUS_CMD_ERROR, STATUS_CMD_OK, STATUS_CMD_UNKNOWN, STATUS_EXPAND_ERROR,
-    STATUS_ILLEGAL_CMD, STATUS_INVALID_ARGS, STATUS_NOT_EXECUTABLE, STATUS_UNMATCHED_WILDCARD,
-    builtin_exists,
+    BUILTIN_ERR_REGEX_COMPILE, BUILTIN_ERR_UNKNOWN, BUILTIN_ERR_VARNAME, STATUS_CMD_ERROR,
+    STATUS_CMD_OK, STATUS_CMD_UNKNOWN, STATUS_EXPAND_ERROR, STATUS_ILLEGAL_CMD,
+    STATUS_INVALID_ARGS, STATUS_NOT_EXECUTABLE, STATUS_UNMATCHED_WILDCARD, builtin_exists,
 };
 use crate::common::{
-    ScopeGuard, ScopeGuarding, ScopedRefCell, escape, truncate_at_nul, valid_var_name,
+    ScopeGuard, ScopeGuarding, ScopedRefCell, escape, str2wcstring, truncate_at_nul, valid_var_name,
 };
 use crate::complete::CompletionList;
+use crate::debugger::debugger_stop_if_requested;
 use crate::env::{EnvMode, EnvStackSetResult, EnvVar, EnvVarFlags, Environment as _, Statuses};
 use crate::event::{self, Event};
-use crate::exec::exec_job;
+use crate::exec::{add_process_substitutions, exec_job, finish_process_substitutions};
 use crate::expand::{
     ExpandFlags, ExpandResultCode, expand_one, expand_string, expand_to_command_and_args,
 };
@@ -33,17 +34,19 @@
 };
 use crate::parse_tree::{NodeRef, ParsedSourceRef};
 use crate::parse_util::{
-    MaybeParentheses::CommandSubstitution, locate_cmdsubst_range, unescape_wildcards,
+    MaybeParentheses::CommandSubstitution, heredoc_body_as_double_quoted, locate_cmdsubst_range,
+    unescape_wildcards,
 };
 use crate::parser::{
     Block, BlockData, BlockId, BlockType, LoopStatus, Parser, ParserEnvSetMode, ProfileItem,
+    TryFailure,
 };
 use crate::parser_keywords::parser_keywords_is_subcommand;
 use crate::path::{path_as_implicit_cd, path_try_get_path};
 use crate::prelude::*;
 use crate::proc::{
     ConcreteAssignment, Job, JobControl, JobProperties, JobRef, Process, ProcessType,
-    get_job_control_mode, job_reap, no_exec,
+    add_disowned_job, get_job_control_mode, job_reap, no_exec,
 };
 use crate::reader::fish_is_unwinding_for_exit;
 use crate::redirection::{RedirectionMode, RedirectionSpec, RedirectionSpecList};
@@ -53,8 +56,10 @@
 use crate::trace::{trace_if_enabled, trace_if_enabled_with_args};
 use crate::wildcard::wildcard_match;
 use fish_common::help_section;
-use fish_widestring::WExt as _;
+use fish_widestring::{ToWString as _, WExt as _};
 use libc::{ENOTDIR, EXIT_SUCCESS, STDERR_FILENO, STDOUT_FILENO, c_int};
+use pcre2::utf32::{Regex, RegexBuilder};
+use std::collections::HashMap;
 use std::io::ErrorKind;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -95,6 +100,17 @@ pub struct ExecutionContext<'a> {
 
     /// Hack to supress non-redirectable stderr in some unit tests.
     test_only_suppress_stderr: bool,
+
+    /// Whether we are running the condition of an if or while statement.
+    in_condition: bool,
+
+    /// Whether the status of the last job conjunction is tested, because it came from a job that
+    /// is followed by `&&` or `||`, or because the last job was skipped.
+    status_is_tested: bool,
+
+    /// The compiled regular expressions of `case --regex` items, by pattern. This way a switch
+    /// that runs in a loop compiles each of its expressions only once.
+    case_regexes: HashMap<WString, Rc<Regex>>,
 }
 
 // Report an error, setting $status to `status`. Always returns
@@ -143,6 +159,9 @@ pub fn new(
             pipeline_node,
             block_io,
             test_only_suppress_stderr,
+            in_condition: false,
+            status_is_tested: false,
+            case_regexes: HashMap::new(),
         }
     }
 
@@ -179,6 +198,7 @@ fn eval_statement(
             }
             Statement::If(ifstat) => self.run_if_statement(ctx, ifstat, associated_block),
             Statement::Switch(switchstat) => self.run_switch_statement(ctx, switchstat),
+            Statement::Try(trystat) => self.run_try_statement(ctx, trystat),
             Statement::Decorated(_) | Statement::Not(_) => panic!(),
         }
     }
@@ -242,6 +262,9 @@ fn check_end_execution(&self, ctx: &OperationContext<'_>) -> Option<EndExecution
         if ld.loop_status != LoopStatus::normals {
             return Some(EndExecutionReason::ControlFlow);
         }
+        if ld.try_failure.is_some() {
+            return Some(EndExecutionReason::ControlFlow);
+        }
         None
     }
 
@@ -572,6 +595,7 @@ fn job_is_simple_block(&self, job: &ast::JobPipeline) -> bool {
             Statement::Brace(stmt) => no_redirs(&stmt.args_or_redirs),
             Statement::Switch(stmt) => no_redirs(&stmt.args_or_redirs),
             Statement::If(stmt) => no_redirs(&stmt.args_or_redirs),
+            Statement::Try(stmt) => no_redirs(&stmt.args_or_redirs),
             Statement::Not(_) | Statement::Decorated(_) => {
                 // not block statements
                 false
@@ -687,9 +711,11 @@ fn populate_job_process(
             Statement::Not(not_statement) => {
                 self.populate_not_process(ctx, job, proc, not_statement)
             }
-            Statement::Block(_) | Statement::Brace(_) | Statement::If(_) | Statement::Switch(_) => {
-                self.populate_block_process(ctx, proc, statement)
-            }
+            Statement::Block(_)
+            | Statement::Brace(_)
+            | Statement::If(_)
+            | Statement::Switch(_)
+            | Statement::Try(_) => self.populate_block_process(ctx, proc, statement),
             Statement::Decorated(decorated_statement) => {
                 self.populate_plain_process(ctx, proc, decorated_statement)
             }
@@ -851,6 +877,7 @@ fn populate_block_process(
             Statement::Brace(brace_statement) => &brace_statement.args_or_redirs,
             Statement::If(if_statement) => &if_statement.args_or_redirs,
             Statement::Switch(switch_statement) => &switch_statement.args_or_redirs,
+            Statement::Try(try_statement) => &try_statement.args_or_redirs,
             _ => panic!("Unexpected block node type"),
         };
 
@@ -882,35 +909,85 @@ fn run_block_statement(
         }
     }
 
-    fn run_for_statement(
+    /// Expand and validate a variable name in a for loop header.
+    fn expand_for_var_name(
         &mut self,
         ctx: &OperationContext<'_>,
-        header: &'a ast::ForHeader,
-        block_contents: &'a ast::JobList,
-    ) -> EndExecutionReason {
-        // Get the variable name: `for var_name in ...`. We expand the variable name. It better result
-        // in just one.
-        let mut for_var_name = self.node_source_owned(&header.var_name);
-        if !expand_one(&mut for_var_name, ExpandFlags::default(), ctx, None) {
-            return report_error!(
+        node: &ast::String_,
+    ) -> Result<WString, EndExecutionReason> {
+        // We expand the variable name. It better result in just one.
+        let mut var_name = self.node_source_owned(node);
+        if !expand_one(&mut var_name, ExpandFlags::default(), ctx, None) {
+            return Err(report_error!(
                 self,
                 ctx,
                 STATUS_EXPAND_ERROR,
-                &header.var_name,
+                node,
                 FAILED_EXPANSION_VARIABLE_NAME_ERR_MSG,
-                for_var_name
-            );
+                var_name
+            ));
         }
 
-        if !valid_var_name(&for_var_name) {
-            return report_error!(
+        if !valid_var_name(&var_name) {
+            return Err(report_error!(
                 self,
                 ctx,
                 STATUS_INVALID_ARGS,
-                header.var_name,
+                node,
                 "%s",
-                varname_error(L!("for"), &for_var_name)
-            );
+                varname_error(L!("for"), &var_name)
+            ));
+        }
+
+        if EnvVar::flags_for(&var_name).contains(EnvVarFlags::READ_ONLY) {
+            return Err(report_error!(
+                self,
+                ctx,
+                STATUS_INVALID_ARGS,
+                node,
+                "%s: %s: cannot overwrite read-only variable",
+                "for",
+                var_name
+            ));
+        }
+        Ok(var_name)
+    }
+
+    fn run_for_statement(
+        &mut self,
+        ctx: &OperationContext<'_>,
+        header: &'a ast::ForHeader,
+        block_contents: &'a ast::JobList,
+    ) -> EndExecutionReason {
+        // Get the variable names: `for [-i index_var_name] var_name... in ...`.
+        let mut index_var_name = None;
+        if let Some(index) = &header.index {
+            let option = self.node_source(&index.option);
+            if option != "-i" && option != "--index" {
+                let option = option.to_owned();
+                return report_error!(
+                    self,
+                    ctx,
+                    STATUS_INVALID_ARGS,
+                    &index.option,
+                    BUILTIN_ERR_UNKNOWN,
+                    "for",
+                    option
+                );
+            }
+            match self.expand_for_var_name(ctx, &index.var_name) {
+                Ok(var_name) => index_var_name = Some(var_name),
+                Err(reason) => return reason,
+            }
+        }
+        let mut for_var_names = vec![];
+        let var_name_nodes = std::iter::once(&header.var_name)
+            .chain(header.more_var_names.iter().map(|node| &node.var_name));
+        for node in var_name_nodes {
+            match self.expand_for_var_name(ctx, node) {
+                Ok(var_name) => for_var_names.push(var_name),
+                Err(reason) => return reason,
+            }
         }
 
         // Get the contents to iterate over.
@@ -921,50 +998,64 @@ fn run_for_statement(
         if ret != EndExecutionReason::Ok {
             return ret;
         }
-        let var = ctx.parser().vars().get(&for_var_name);
-        if EnvVar::flags_for(&for_var_name).contains(EnvVarFlags::READ_ONLY) {
-            return report_error!(
-                self,
-                ctx,
-                STATUS_INVALID_ARGS,
-                header.var_name,
-                "%s: %s: cannot overwrite read-only variable",
-                "for",
-                for_var_name
+
+        for var_name in index_var_name.iter().chain(&for_var_names) {
+            let var = ctx.parser().vars().get(var_name);
+            let retval = ctx.parser().set_var(
+                var_name,
+                ParserEnvSetMode::user(EnvMode::LOCAL),
+                var.map_or(vec![], |var| var.as_list().to_owned()),
             );
+            assert_eq!(retval, EnvStackSetResult::Ok);
         }
 
-        let retval = ctx.parser().set_var(
-            &for_var_name,
-            ParserEnvSetMode::user(EnvMode::LOCAL),
-            var.map_or(vec![], |var| var.as_list().to_owned()),
-        );
-        assert_eq!(retval, EnvStackSetResult::Ok);
-
         trace_if_enabled_with_args(ctx.parser(), L!("for"), &arguments);
 
-        // We fire the same event over and over again, just construct it once.
-        let evt = Event::variable_set(for_var_name.clone());
+        // We fire the same events over and over again, just construct them once.
+        let index_evt = index_var_name
+            .as_ref()
+            .map(|var_name| (var_name, Event::variable_set(var_name.clone())));
+        let evts: Vec<_> = for_var_names
+            .iter()
+            .map(|var_name| Event::variable_set(var_name.clone()))
+            .collect();
 
-        // Now drive the for loop.
+        // Now drive the for loop, assigning as many values as we have variables on each iteration.
         let mut ret = EndExecutionReason::Ok;
-        for val in arguments {
+        for (i, vals) in arguments.chunks(for_var_names.len()).enumerate() {
             if let Some(reason) = self.check_end_execution(ctx) {
                 ret = reason;
                 break;
             }
 
-            let retval = ctx.parser().set_var(
-                &for_var_name,
-                ParserEnvSetMode::user(EnvMode::empty()),
-                vec![val],
-            );
-            assert_eq!(
-                retval,
-                EnvStackSetResult::Ok,
-                "for loop variable should have been successfully set"
-            );
-            event::fire(ctx.parser(), evt.clone());
+            if let Some((index_var_name, evt)) = &index_evt {
+                let retval = ctx.parser().set_var(
+                    index_var_name,
+                    ParserEnvSetMode::user(EnvMode::empty()),
+                    vec![(i + 1).to_wstring()],
+                );
+                assert_eq!(
+                    retval,
+                    EnvStackSetResult::Ok,
+                    "for loop index variable should have been successfully set"
+                );
+                event::fire(ctx.parser(), evt.clone());
+            }
+
+            // If the values run out in the last iteration, the remaining variables are empty.
+            for (j, (var_name, evt)) in for_var_names.iter().zip(&evts).enumerate() {
+                let retval = ctx.parser().set_var(
+                    var_name,
+                    ParserEnvSetMode::user(EnvMode::empty()),
+                    vals.get(j).cloned().into_iter().collect(),
+                );
+                assert_eq!(
+                    retval,
+                    EnvStackSetResult::Ok,
+                    "for loop variable should have been successfully set"
+                );
+                event::fire(ctx.parser(), evt.clone());
+            }
 
             ctx.parser().libdata_mut().loop_status = LoopStatus::normals;
 
@@ -1016,11 +1107,13 @@ fn run_if_statement(
             // An if condition has a job and a "tail" of andor jobs, e.g. "foo ; and bar; or baz".
             // Check the condition and the tail. We treat end_execution_reason_t::error here as failure,
             // in accordance with historic behavior.
+            let outer_in_condition = std::mem::replace(&mut self.in_condition, true);
             let mut cond_ret =
                 self.run_job_conjunction(ctx, &if_clause.condition, associated_block);
             if cond_ret == EndExecutionReason::Ok {
                 cond_ret = self.run_andor_job_list(ctx, &if_clause.andor_tail, associated_block);
             }
+            self.in_condition = outer_in_condition;
             let take_branch = cond_ret == EndExecutionReason::Ok
                 && ctx.parser().get_last_status() == EXIT_SUCCESS;
 
@@ -1152,7 +1245,14 @@ fn run_switch_statement(
             // Expand arguments. A case item list may have a wildcard that fails to expand to
             // anything. We also report case errors, but don't stop execution; i.e. a case item that
             // contains an unexpandable process will report and then fail to match.
-            let arg_nodes = Self::get_argument_nodes(&case_item.arguments);
+            let mut arg_nodes = Self::get_argument_nodes(&case_item.arguments);
+            // An unquoted `--regex` as the first argument makes the rest regular expressions.
+            let is_regex = arg_nodes
+                .first()
+                .is_some_and(|node| self.node_source(*node) == "--regex");
+            if is_regex {
+                arg_nodes.remove(0);
+            }
             let mut case_args = vec![];
             let case_result = self.expand_arguments_from_nodes(
                 ctx,
@@ -1160,7 +1260,11 @@ fn run_switch_statement(
                 &mut case_args,
                 Globspec::failglob,
             );
-            if case_result == EndExecutionReason::Ok {
+            if case_result == EndExecutionReason::Ok && is_regex {
+                if self.case_regex_matches(ctx, case_item, &switch_value_expanded, &case_args) {
+                    matching_case_item = Some(case_item);
+                }
+            } else if case_result == EndExecutionReason::Ok {
                 for arg in case_args {
                     // Unescape wildcards so they can be expanded again.
                     let unescaped_arg = unescape_wildcards(&arg);
@@ -1187,6 +1291,88 @@ fn run_switch_statement(
         result
     }
 
+    /// Match a value against the regular expressions of a `case --regex` item. The named capture
+    /// groups of the first expression that matches are set as variables, like `string match -r`,
+    /// and `$case_match` is set to the whole match followed by every capture group.
+    fn case_regex_matches(
+        &mut self,
+        ctx: &OperationContext<'_>,
+        case_item: &ast::CaseItem,
+        value: &wstr,
+        patterns: &[WString],
+    ) -> bool {
+        for pattern in patterns {
+            let regex = if let Some(regex) = self.case_regexes.get(pattern) {
+                Rc::clone(regex)
+            } else {
+                match RegexBuilder::new()
+                    .block_utf_pattern_directive(true)
+                    .build(pattern.as_char_slice())
+                {
+                    Ok(regex) => {
+                        let regex = Rc::new(regex);
+                        self.case_regexes.insert(pattern.clone(), Rc::clone(&regex));
+                        regex
+                    }
+                    Err(e) => {
+                        report_error!(
+                            self,
+                            ctx,
+                            STATUS_INVALID_ARGS,
+                            &case_item.arguments,
+                            BUILTIN_ERR_REGEX_COMPILE,
+                            "case",
+                            WString::from(e.error_message())
+                        );
+                        return false;
+                    }
+                }
+            };
+
+            let names: Vec<_> = regex.capture_names().iter().flatten().collect();
+            for name in &names {
+                let name = str2wcstring(name);
+                if EnvVar::flags_for(&name).contains(EnvVarFlags::READ_ONLY) {
+                    report_error!(
+                        self,
+                        ctx,
+                        STATUS_INVALID_ARGS,
+                        &case_item.arguments,
+                        "%s: %s: cannot overwrite read-only variable",
+                        "case",
+                        name
+                    );
+                    return false;
+                }
+            }
+
+            let Ok(Some(captures)) = regex.captures(value.as_char_slice()) else {
+                continue;
+            };
+            // Groups that did not participate in the match are empty, to keep the numbering.
+            let case_match = (0..captures.len())
+                .map(|i| {
+                    captures
+                        .get(i)
+                        .map(|m| WString::from(m.as_bytes()))
+                        .unwrap_or_default()
+                })
+                .collect();
+            ctx.parser()
+                .set_var(L!("case_match"), ParserEnvSetMode::default(), case_match);
+            for name in names {
+                let vals = captures
+                    .name(name)
+                    .map(|m| vec![WString::from(m.as_bytes())])
+                    .unwrap_or_default();
+                ctx.parser()
+                    .set_var(&str2wcstring(name), ParserEnvSetMode::default(), vals);
+            }
+            return true;
+        }
+        false
+    }
+
     fn run_while_statement(
         &mut self,
         ctx: &OperationContext<'_>,
@@ -1221,10 +1407,12 @@ fn run_while_statement(
             first_cond_check = false;
 
             // Check the condition.
+            let outer_in_condition = std::mem::replace(&mut self.in_condition, true);
             let mut cond_ret = self.run_job_conjunction(ctx, &header.condition, associated_block);
             if cond_ret == EndExecutionReason::Ok {
                 cond_ret = self.run_andor_job_list(ctx, &header.andor_tail, associated_block);
             }
+            self.in_condition = outer_in_condition;
 
             // If the loop condition failed to execute, then exit the loop without modifying the exit
             // status. If the loop condition executed with a failure status, restore the status and then
@@ -1328,11 +1516,41 @@ fn run_begin_statement(
             .parser()
             .push_block(Block::scope_block(BlockType::begin));
         let ret = self.run_job_list(ctx, contents, Some(sb));
+        ctx.parser().run_deferred_commands(sb, &self.block_io);
         ctx.parser().pop_block(sb);
         trace_if_enabled(ctx.parser(), L!("end begin"));
         ret
     }
 
+    fn run_try_statement(
+        &mut self,
+        ctx: &OperationContext<'_>,
+        statement: &'a ast::TryStatement,
+    ) -> EndExecutionReason {
+        trace_if_enabled(ctx.parser(), L!("try"));
+        let tb = ctx.parser().push_block(Block::try_block());
+        let mut result = self.run_job_list(ctx, &statement.body, Some(tb));
+        ctx.parser().pop_block(tb);
+
+        // A failed job unwinds up to here, where we switch to the catch clause, if any.
+        // Without one, the try block simply stops with the status of the failed job.
+        let failure = ctx.parser().libdata_mut().try_failure.take();
+        if let Some(failure) = failure {
+            ctx.parser().set_last_statuses(failure.statuses);
+            result = EndExecutionReason::Ok;
+            if let Some(catch_clause) = &statement.catch_clause {
+                trace_if_enabled(ctx.parser(), L!("catch"));
+                let cb = ctx
+                    .parser()
+                    .push_block(Block::catch_block(failure.commandline));
+                result = self.run_job_list(ctx, &catch_clause.body, Some(cb));
+                ctx.parser().pop_block(cb);
+            }
+        }
+        trace_if_enabled(ctx.parser(), L!("end try"));
+        result
+    }
+
     fn get_argument_nodes(args: &ast::ArgumentList) -> AstArgsList<'_> {
         let mut result = AstArgsList::new();
         for arg in args {
@@ -1444,20 +1662,49 @@ fn determine_redirections(
                 }
             };
 
+            // The target of a heredoc is its delimiter, the text comes from its body.
+            let heredoc = if oper.mode == RedirectionMode::HereDoc {
+                let delimiter = redir_node.target.try_source_range();
+                self.pstree()
+                    .ast
+                    .extras
+                    .heredocs
+                    .iter()
+                    .find(|heredoc| Some(heredoc.delimiter) == delimiter)
+                    .copied()
+            } else {
+                None
+            };
+            let mut target = match heredoc {
+                Some(heredoc) if heredoc.expand => {
+                    heredoc_body_as_double_quoted(&self.pstree().src[heredoc.body.as_usize()])
+                }
+                Some(heredoc) => self.pstree().src[heredoc.body.as_usize()].to_owned(),
+                None => self.node_source_owned(&redir_node.target),
+            };
+
             // PCA: I can't justify this skip_variables flag. It was like this when I got here.
-            let mut target = self.node_source_owned(&redir_node.target);
-            let target_expanded = expand_one(
-                &mut target,
-                if no_exec() {
-                    ExpandFlags::SKIP_VARIABLES
-                } else {
-                    ExpandFlags::default()
-                },
-                ctx,
-                None,
-            );
+            let target_expanded = heredoc.is_some_and(|heredoc| !heredoc.expand)
+                || expand_one(
+                    &mut target,
+                    if no_exec() {
+                        ExpandFlags::SKIP_VARIABLES
+                    } else {
+                        ExpandFlags::default()
+                    },
+                    ctx,
+                    None,
+                );
+            let target_valid = match oper.mode {
+                RedirectionMode::HereDoc => heredoc.is_some(),
+                RedirectionMode::HereString => {
+                    target.push('\n');
+                    true
+                }
+                _ => !target.is_empty(),
+            };
 
-            if !target_expanded || target.is_empty() {
+            if !target_expanded || !target_valid {
                 // TODO: Improve this error message.
                 let error_ret = report_error!(
                     self,
@@ -1465,7 +1712,11 @@ fn determine_redirections(
                     STATUS_INVALID_ARGS,
                     redir_node,
                     "Invalid redirection target: %s",
-                    target
+                    if heredoc.is_some() {
+                        self.node_source(&redir_node.target)
+                    } else {
+                        &target
+                    }
                 );
                 if oper.mode == RedirectionMode::Input && {
                     let redir_unexpanded = self.node_source(redir_node);
@@ -1533,13 +1784,32 @@ fn run_1_job(
             return EndExecutionReason::Ok;
         }
 
+        // Process substitutions made for this job are closed once it is done.
+        let outer_process_substitutions =
+            std::mem::take(&mut ctx.parser().libdata_mut().process_substitutions);
+        let _process_substitutions = ScopeGuard::new(outer_process_substitutions, |outer| {
+            let parser = ctx.parser();
+            let psubs = std::mem::replace(&mut parser.libdata_mut().process_substitutions, outer);
+            finish_process_substitutions(parser, psubs);
+        });
+
+        // The command of a process substitution may run in the background, but the jobs it runs
+        // in turn may not.
+        let is_process_substitution = ctx.parser().scope().is_process_substitution;
+
         // Increment the eval_level for the duration of this command.
-        let _saved_eval_level = ctx.parser().push_scope(|s| s.eval_level += 1);
+        let _saved_eval_level = ctx.parser().push_scope(|s| {
+            s.eval_level += 1;
+            s.is_process_substitution = false;
+        });
 
         // Save the executing node.
         let executing_node = NodeRef::new(Arc::clone(self.pstree()), job_node);
         let _saved_node = self.pipeline_node.scoped_replace(Some(executing_node));
 
+        // Stop at the debug prompt if there is a breakpoint here, or we are stepping.
+        debugger_stop_if_requested(ctx.parser(), &self.block_io);
+
         // Profiling support.
         let profile_item_id = ctx.parser().create_profile_item();
         let start_time = if profile_item_id.is_some() {
@@ -1590,6 +1860,7 @@ fn run_1_job(
                     }
                     Statement::If(ifstmt) => self.run_if_statement(ctx, ifstmt, associated_block),
                     Statement::Switch(switchstmt) => self.run_switch_statement(ctx, switchstmt),
+                    Statement::Try(trystmt) => self.run_try_statement(ctx, trystmt),
                     // Other types should be impossible due to the
                     // statement_is_redirectable_block check.
                     Statement::Not(_) | Statement::Decorated(_) => {
@@ -1613,7 +1884,8 @@ fn run_1_job(
         }
 
         let mut props = JobProperties {
-            initial_background: job_is_background,
+            initial_background: job_is_background || is_process_substitution,
+            is_process_substitution,
             ..Default::default()
         };
         {
@@ -1654,7 +1926,9 @@ fn run_1_job(
                 parser.job_add(job.clone());
 
                 // Actually execute the job.
-                if !exec_job(parser, &job, self.block_io.clone()) {
+                let mut block_io = self.block_io.clone();
+                add_process_substitutions(&mut block_io, &parser.libdata().process_substitutions);
+                if !exec_job(parser, &job, block_io) {
                     // No process in the job successfully launched.
                     // Ensure statuses are set (#7540).
                     if let Some(statuses) = job.get_statuses() {
@@ -1662,6 +1936,11 @@ fn run_1_job(
                         parser.libdata_mut().status_count += 1;
                     }
                     remove_job(parser, &job);
+                } else if job.is_process_substitution() {
+                    // The command of a process substitution is not a job that can be listed or
+                    // waited for. Only make sure its processes are reaped.
+                    remove_job(parser, &job);
+                    add_disowned_job(&job);
                 }
 
                 // Update universal variables on external commands.
@@ -1718,6 +1997,7 @@ fn test_and_run_1_job_conjunction(
         }
         // Skipping is treated as success.
         if skip {
+            self.status_is_tested = true;
             EndExecutionReason::Ok
         } else {
             self.run_job_conjunction(ctx, jc, associated_block)
@@ -1734,7 +2014,8 @@ fn run_job_conjunction(
             return reason;
         }
         let mut result = self.run_1_job(ctx, &job_expr.job, associated_block);
-        for jc in &job_expr.continuations {
+        self.status_is_tested = !job_expr.continuations.is_empty();
+        for (i, jc) in job_expr.continuations.iter().enumerate() {
             if result != EndExecutionReason::Ok {
                 return result;
             }
@@ -1756,6 +2037,7 @@ fn run_job_conjunction(
             };
             if !skip {
                 result = self.run_1_job(ctx, &jc.job, associated_block);
+                self.status_is_tested = i + 1 < job_expr.continuations.len();
             }
         }
         result
@@ -1768,13 +2050,51 @@ fn run_job_list(
         associated_block: Option<BlockId>,
     ) -> EndExecutionReason {
         let mut result = EndExecutionReason::Ok;
-        for jc in job_list_node {
+        let mut jobs = job_list_node.iter().peekable();
+        while let Some(jc) = jobs.next() {
             result = self.test_and_run_1_job_conjunction(ctx, jc, associated_block);
+            // A job followed by an 'and' or 'or' job is tested as well.
+            let is_tested =
+                self.status_is_tested || jobs.peek().is_some_and(|next| next.decorator.is_some());
+            if !is_tested && !self.in_condition {
+                self.check_try_failure(ctx, jc, &result);
+            }
         }
         // Returns the result of the last job executed or skipped.
         result
     }
 
+    /// If the given job conjunction failed in a try block, start unwinding to the catch clause.
+    fn check_try_failure(
+        &self,
+        ctx: &OperationContext<'_>,
+        jc: &ast::JobConjunction,
+        result: &EndExecutionReason,
+    ) {
+        // Errors like unknown commands are failures too, but control flow and cancellation are not.
+        if !matches!(result, EndExecutionReason::Ok | EndExecutionReason::Error) || no_exec() {
+            return;
+        }
+        let parser = ctx.parser();
+        let statuses = parser.get_last_statuses();
+        // A failure in a nested block has already been recorded.
+        if statuses.status == EXIT_SUCCESS
+            || parser.libdata().try_failure.is_some()
+            || !parser.is_in_try_block()
+        {
+            return;
+        }
+        // A negated job is tested too.
+        let job = jc.continuations.last().map_or(&jc.job, |c| &c.job);
+        if matches!(job.statement, Statement::Not(_)) {
+            return;
+        }
+        parser.libdata_mut().try_failure = Some(TryFailure {
+            commandline: self.node_source_owned(job),
+            statuses,
+        });
+    }
+
     fn run_andor_job_list(
         &mut self,
         ctx: &OperationContext<'_>,
@@ -1915,7 +2235,11 @@ enum Globspec {
 fn statement_is_redirectable_block(node: &ast::Statement) -> bool {
     match node {
         Statement::Decorated(_) | Statement::Not(_) => false,
-        Statement::Block(_) | Statement::Brace(_) | Statement::If(_) | Statement::Switch(_) => true,
+        Statement::Block(_)
+        | Statement::Brace(_)
+        | Statement::If(_)
+        | Statement::Switch(_)
+        | Statement::Try(_) => true,
     }
 }
 
@@ -1941,6 +2265,7 @@ fn profiling_cmd_name_for_redirectable_block(
         Statement::Brace(brace_statement) => brace_statement.left_brace.source_range().start(),
         Statement::If(ifstmt) => ifstmt.if_clause.condition.job.source_range().end(),
         Statement::Switch(switchstmt) => switchstmt.semi_nl.source_range().start(),
+        Statement::Try(trystmt) => trystmt.kw_try.source_range().end(),
         _ => {
             panic!("Not a redirectable block_type");
         }
@@ -92,6 +92,7 @@ fn from(err: TokenizerError) -> Self {
             TokenizerError::UnterminatedSubshell => ParseErrorCode::TokenizerUnterminatedSubshell,
             TokenizerError::UnterminatedSlice => ParseErrorCode::TokenizerUnterminatedSlice,
             TokenizerError::UnterminatedEscape => ParseErrorCode::TokenizerUnterminatedEscape,
+            TokenizerError::UnterminatedHeredoc => ParseErrorCode::TokenizerUnterminatedHeredoc,
             // To-do: maybe also unbalancing brace?
             _ => ParseErrorCode::TokenizerOther,
         }
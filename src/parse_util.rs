@@ -159,6 +159,16 @@ pub fn locate_cmdsubst_range(
     ret
 }
 
+/// Return whether a string contains a command substitution.
+pub fn has_cmdsub(src: &wstr) -> bool {
+    let mut cursor = 0;
+    match locate_cmdsubst_range(src, &mut cursor, true, None, None) {
+        MaybeParentheses::Error => false,
+        MaybeParentheses::None => false,
+        MaybeParentheses::CommandSubstitution(_) => true,
+    }
+}
+
 /// Find the beginning and end of the command substitution under the cursor. If no subshell is
 /// found, the entire string is returned. If the current command substitution is not ended, i.e. the
 /// closing parenthesis is missing, then the string from the beginning of the substitution to the
@@ -752,6 +762,49 @@ pub fn escape_string_with_quote(
     result
 }
 
+/// Convert the body of an expanding heredoc to a double-quoted string with the same meaning.
+/// Variables and `$(...)` command substitutions are expanded, and backslashes only escape `$`, `\`
+/// and newlines. Everything else, including double quotes, is literal.
+pub fn heredoc_body_as_double_quoted(body: &wstr) -> WString {
+    let chars = body.as_char_slice();
+    let mut result = WString::with_capacity(body.len() + 2);
+    result.push('"');
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied().unwrap_or('\0');
+        match c {
+            '\\' if matches!(next, '$' | '\\' | '\n') => {
+                result.push(c);
+                result.push(next);
+                i += 2;
+                continue;
+            }
+            '\\' | '"' => {
+                result.push('\\');
+                result.push(c);
+            }
+            '$' if next == '(' => {
+                // Copy the command substitution as-is, it is parsed like any other.
+                let end = match locate_cmdsubst_range(&body[i + 1..], &mut 0, true, None, None) {
+                    MaybeParentheses::CommandSubstitution(parens) => i + 1 + parens.end(),
+                    _ => chars.len(),
+                };
+                result.push_utfstr(&body[i..end]);
+                i = end;
+                continue;
+            }
+            '$' if next != '$' && !valid_var_name_char(next) => {
+                result.push_str("\\$");
+            }
+            _ => result.push(c),
+        }
+        i += 1;
+    }
+    result.push('"');
+    result
+}
+
 /// Given a string, parse it as fish code and then return the indents. The return value has the same
 /// size as the string.
 pub fn compute_indents(src: &wstr) -> Vec<i32> {
@@ -821,6 +874,11 @@ fn compute_indents_from(src: &wstr, initial_indent: i32) -> Vec<i32> {
         }
     }
 
+    // Heredoc bodies must be kept as they are, so don't indent any of their lines.
+    for heredoc in &ast.extras.heredocs {
+        indents[heredoc.body.start() - 1..heredoc.terminator.end()].fill(0);
+    }
+
     indents
 }
 
@@ -1067,10 +1125,13 @@ fn visit(&mut self, node: &'a dyn Node) {
             Kind::Token(node) => {
                 let token_type = node.token_type();
                 let parent_kind = self.parent.unwrap().kind();
-                if matches!(parent_kind, Kind::BeginHeader(_)) && token_type == ParseTokenType::End
+                if matches!(
+                    parent_kind,
+                    Kind::BeginHeader(_) | Kind::TryStatement(_) | Kind::CatchClause(_)
+                ) && token_type == ParseTokenType::End
                 {
-                    // The newline after "begin" is optional, so it is part of the header.
-                    // The header is not in the indented block, so indent the newline here.
+                    // The newline after "begin", "try" or "catch" is optional, so it is not part
+                    // of the indented block. Indent the newline here.
                     if node.source(self.src) == "\n" {
                         inc_dec = (1, 1);
                     }
@@ -1146,6 +1207,7 @@ pub fn detect_parse_errors(
             if [
                 ParseErrorCode::TokenizerUnterminatedQuote,
                 ParseErrorCode::TokenizerUnterminatedSubshell,
+                ParseErrorCode::TokenizerUnterminatedHeredoc,
             ]
             .contains(&parse_error.code)
             {
@@ -1296,6 +1358,31 @@ pub fn detect_parse_errors_in_ast(
                     &mut out_errors,
                 );
             }
+            Kind::ForIndex(index) => {
+                // The only option to a for loop is the index variable.
+                let option = index.option.source(buff_src);
+                if option != "-i" && option != "--index" {
+                    let r = index.option.source_range();
+                    issue.error |= append_syntax_error!(
+                        out_errors,
+                        r.start(),
+                        r.length(),
+                        "for: %s: unknown option",
+                        option
+                    );
+                }
+            }
+            Kind::TryStatement(trys) => {
+                // If our 'end' had no source, we are unsourced.
+                if !trys.end.has_source() {
+                    has_unclosed_block = true;
+                }
+                issue.error |= detect_errors_in_block_redirection_list(
+                    node,
+                    &trys.args_or_redirs,
+                    &mut out_errors,
+                );
+            }
             _ => {}
         }
     }
@@ -1963,9 +2050,11 @@ pub fn expand_variable_error(
 mod tests {
     use super::{
         BOOL_AFTER_BACKGROUND_ERROR_MSG, compute_indents, detect_parse_errors,
-        escape_string_with_quote, get_cmdsubst_extent, get_process_extent, slice_length,
+        escape_string_with_quote, get_cmdsubst_extent, get_process_extent,
+        heredoc_body_as_double_quoted, slice_length,
     };
     use crate::common::EscapeFlags;
+    use crate::future_feature_flags::{FeatureFlag, scoped_test};
     use crate::parse_constants::{
         ERROR_BAD_VAR_CHAR1, ERROR_BRACKETED_VARIABLE_QUOTED1, ERROR_BRACKETED_VARIABLE1,
         ERROR_NO_VAR_NAME, ERROR_NOT_ARGV_AT, ERROR_NOT_ARGV_COUNT, ERROR_NOT_ARGV_STAR,
@@ -2166,6 +2255,21 @@ macro_rules! validate_no_quoted {
         validate_no_quoted!("foo\\\\bar", Some('"'), false, "foo\\\\\\\\bar");
     }
 
+    #[test]
+    fn test_heredoc_body_as_double_quoted() {
+        macro_rules! validate {
+            ($body:literal, $expected:literal) => {
+                assert_eq!(heredoc_body_as_double_quoted(L!($body)), L!($expected));
+            };
+        }
+        validate!("hello\n", "\"hello\n\"");
+        validate!("say \"hi\"\n", "\"say \\\"hi\\\"\n\"");
+        validate!("$HOME \\$HOME\n", "\"$HOME \\$HOME\n\"");
+        validate!("a\\b \\\\ c\\\n", "\"a\\\\b \\\\ c\\\n\"");
+        validate!("$ 5, 5$\n", "\"\\$ 5, 5\\$\n\"");
+        validate!("$(echo \"a)\") (b)", "\"$(echo \"a)\") (b)\"");
+    }
+
     #[test]
     #[serial]
     fn test_indents() {
@@ -2293,6 +2397,13 @@ macro_rules! validate {
                 1, "\n", // "begin" is special because this newline belongs to the block header
                 1, "\n"
             );
+            scoped_test(FeatureFlag::TryCatch, true, || {
+                validate!(
+                    0, "try",
+                    1, "\n",
+                    1, "\n"
+                );
+            });
 
             // Continuation lines.
             validate!(
@@ -7,11 +7,13 @@
     ScopedCell, ScopedRefCell, escape_string, wcs2bytes,
 };
 use crate::complete::CompletionList;
+use crate::debugger::Debugger;
 use crate::env::{
     EnvMode, EnvSetMode, EnvStack, EnvStackSetResult, Environment, FISH_TERMINAL_COLOR_THEME_VAR,
     Statuses,
 };
 use crate::event::{self, Event};
+use crate::exec::ProcessSubstitution;
 use crate::expand::{
     ExpandFlags, ExpandResultCode, expand_string, replace_home_directory_with_tilde,
 };
@@ -30,7 +32,7 @@
 use crate::portable_atomic::AtomicU64;
 use crate::prelude::*;
 use crate::proc::{JobGroupRef, JobList, JobRef, Pid, ProcStatus, job_reap};
-use crate::signal::{Signal, signal_check_cancel, signal_clear_cancel};
+use crate::signal::{Signal, signal_check_cancel, signal_clear_cancel, signal_set_cancel};
 use crate::wait_handle::WaitHandleStore;
 use crate::wutil::perror;
 use crate::{flog, flogf, function};
@@ -39,6 +41,7 @@
 use fish_widestring::WExt as _;
 use libc::c_int;
 use std::cell::{Ref, RefCell, RefMut};
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::Write as _;
@@ -60,6 +63,10 @@ pub enum BlockData {
         /// The sourced file
         file: Arc<WString>,
     },
+    Catch {
+        /// The command line of the job whose failure is handled
+        commandline: WString,
+    },
 }
 
 /// block_t represents a block of commands.
@@ -82,6 +89,9 @@ pub struct Block {
 
     /// The node containing this block, for lazy line number computation.
     src_node: Option<NodeRef<ast::JobPipeline>>,
+
+    /// Commands registered with `defer`, to run when this block exits.
+    pub deferred_commands: Vec<WString>,
 }
 
 impl Block {
@@ -118,6 +128,8 @@ pub fn description(&self) -> WString {
             BlockType::if_block => L!("if"),
             BlockType::function_call { .. } => L!("function_call"),
             BlockType::switch_block => L!("switch"),
+            BlockType::try_block => L!("try"),
+            BlockType::catch_block => L!("catch"),
             BlockType::subst => L!("substitution"),
             BlockType::top => L!("top"),
             BlockType::begin => L!("begin"),
@@ -174,6 +186,14 @@ pub fn while_block() -> Block {
     pub fn switch_block() -> Block {
         Block::new(BlockType::switch_block)
     }
+    pub fn try_block() -> Block {
+        Block::new(BlockType::try_block)
+    }
+    pub fn catch_block(commandline: WString) -> Block {
+        let mut b = Block::new(BlockType::catch_block);
+        b.data = Some(Box::new(BlockData::Catch { commandline }));
+        b
+    }
     pub fn scope_block(typ: BlockType) -> Block {
         assert!(
             [BlockType::begin, BlockType::top, BlockType::subst].contains(&typ),
@@ -191,6 +211,30 @@ pub fn variable_assignment_block() -> Block {
 
 type Microseconds = i64;
 
+/// How profiling information is written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ProfileFormat {
+    /// A table with the time of every command, nested like the commands.
+    #[default]
+    Text,
+    /// Like `Text`, with the file and line of every command in an extra column.
+    Located,
+    /// The time spent in each stack of functions, one stack per line, as read by flamegraph
+    /// tools.
+    Folded,
+}
+
+impl ProfileFormat {
+    pub fn from_name(name: &wstr) -> Option<Self> {
+        match name {
+            _ if name == "text" => Some(Self::Text),
+            _ if name == "located" => Some(Self::Located),
+            _ if name == "folded" => Some(Self::Folded),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct ProfileItem {
     /// Time spent executing the command, including nested blocks.
@@ -205,6 +249,15 @@ pub struct ProfileItem {
 
     /// The command string.
     pub cmd: WString,
+
+    /// The file containing the command, if any.
+    pub file: Option<FilenameRef>,
+
+    /// The function the command is run in, if any.
+    pub function: Option<WString>,
+
+    /// The line of the command in its file, if any.
+    pub line: Option<NonZeroU32>,
 }
 
 impl ProfileItem {
@@ -229,6 +282,9 @@ pub struct ScopedData {
     /// Whether we are running a subshell command.
     pub is_subshell: bool,
 
+    /// Whether the next job is the command of a process substitution that runs in the background.
+    pub is_process_substitution: bool,
+
     /// Whether we are running an event handler.
     pub is_event: bool,
 
@@ -258,6 +314,7 @@ fn default() -> Self {
         Self {
             eval_level: -1,
             is_subshell: false,
+            is_process_substitution: false,
             is_event: false,
             readonly_commandline: false,
             is_interactive: false,
@@ -317,6 +374,17 @@ pub struct LibraryData {
     /// This is set by the 'return' command.
     pub returning: bool,
 
+    /// A job that failed in a try block. While this is set, we unwind to the catch clause of the
+    /// block.
+    pub try_failure: Option<TryFailure>,
+
+    /// Process substitutions made while expanding the arguments of the current job. They are
+    /// kept open until that job is done.
+    pub process_substitutions: Vec<ProcessSubstitution>,
+
+    /// Breakpoints and the stepping state of the debugger.
+    pub debugger: Debugger,
+
     /// Whether we should stop executing.
     /// This is set by the 'exit' command, and unset after 'reader_read'.
     /// Note this only exits up to the "current script boundary." That is, a call to exit within a
@@ -333,6 +401,14 @@ pub fn new() -> Self {
     }
 }
 
+/// A job that failed in a try block.
+pub struct TryFailure {
+    /// The command line of the job.
+    pub commandline: WString,
+    /// The statuses of the job, which are restored for the catch clause.
+    pub statuses: Statuses,
+}
+
 /// Status variables set by the main thread as jobs are parsed and read by various consumers.
 #[derive(Default)]
 pub struct StatusVars {
@@ -500,6 +576,98 @@ pub fn is_function(&self) -> bool {
             .any(|b| b.is_function_call())
     }
 
+    /// Return whether a failing job jumps to the catch clause of a try block. Try blocks only
+    /// apply to the jobs written inside of them, and not to functions, command substitutions or
+    /// anything else that is evaluated separately.
+    pub fn is_in_try_block(&self) -> bool {
+        self.blocks_iter_rev()
+            .take_while(|b| {
+                !b.is_function_call()
+                    && ![
+                        BlockType::top,
+                        BlockType::subst,
+                        BlockType::source,
+                        BlockType::event,
+                    ]
+                    .contains(&b.typ())
+            })
+            .any(|b| b.typ() == BlockType::try_block)
+    }
+
+    /// Return the command line of the job whose failure is handled by the innermost catch clause.
+    pub fn failed_commandline(&self) -> Option<WString> {
+        self.blocks_iter_rev().find_map(|b| match b.data() {
+            Some(BlockData::Catch { commandline }) => Some(commandline.clone()),
+            _ => None,
+        })
+    }
+
+    /// Register a command to run when the innermost function or begin block exits.
+    /// Return false if there is no such block. The search does not go past a command
+    /// substitution, a sourced file, an event handler or an `eval`, so `defer` there never
+    /// attaches to a block of the caller.
+    pub fn defer_command(&self, cmd: WString) -> bool {
+        let mut block_list = self.block_list.borrow_mut();
+        let mut target = None;
+        for (i, b) in block_list.iter().enumerate().rev() {
+            match b.typ() {
+                BlockType::function_call { .. } | BlockType::begin => {
+                    target = Some(i);
+                    break;
+                }
+                BlockType::subst | BlockType::source | BlockType::event => break,
+                // A function body is evaluated in a top block right inside the function block.
+                // Any other top block comes from `eval` or the commandline.
+                BlockType::top if i == 0 || !block_list[i - 1].is_function_call() => break,
+                _ => (),
+            }
+        }
+        let Some(i) = target else {
+            return false;
+        };
+        block_list[i].deferred_commands.push(cmd);
+        true
+    }
+
+    /// Run the commands deferred in the given block, most recent first. This needs to happen
+    /// before the block is popped, however its execution ended. Deferred commands don't change
+    /// the status, and a `return`, `break`, `exit` or failure that is unwinding the block
+    /// continues afterwards. They also run if the block was cancelled, e.g. by ctrl-c; the
+    /// cancellation is set aside while they run.
+    pub fn run_deferred_commands(&self, block: BlockId, io: &IoChain) {
+        let pop_deferred = || {
+            self.block_list.borrow_mut()[block.0]
+                .deferred_commands
+                .pop()
+        };
+        while let Some(cmd) = pop_deferred() {
+            let saved_statuses = self.get_last_statuses();
+            let (returning, loop_status, exit_current_script, try_failure) = {
+                let mut ld = self.libdata_mut();
+                (
+                    std::mem::take(&mut ld.returning),
+                    std::mem::take(&mut ld.loop_status),
+                    std::mem::take(&mut ld.exit_current_script),
+                    ld.try_failure.take(),
+                )
+            };
+            let cancel_signal = signal_check_cancel();
+            signal_clear_cancel();
+            self.eval(&cmd, io);
+            if cancel_signal != 0 {
+                signal_set_cancel(cancel_signal);
+            }
+            {
+                let mut ld = self.libdata_mut();
+                ld.returning = returning;
+                ld.loop_status = loop_status;
+                ld.exit_current_script = exit_current_script;
+                ld.try_failure = try_failure;
+            }
+            self.set_last_statuses(saved_statuses);
+        }
+    }
+
     /// Return whether we are currently evaluating a command substitution.
     pub fn is_command_substitution(&self) -> bool {
         self.blocks_iter_rev()
@@ -1129,8 +1297,14 @@ pub fn job_get_with_index_from_pid(&self, pid: Pid) -> Option<(usize, JobRef)> {
     /// If profiling is not active, this returns None.
     pub fn create_profile_item(&self) -> Option<usize> {
         if PROFILING_ACTIVE.load() {
+            let item = ProfileItem {
+                file: self.current_filename(),
+                function: self.get_function_name(1),
+                line: self.get_lineno(),
+                ..ProfileItem::new()
+            };
             let mut profile_items = self.profile_items.borrow_mut();
-            profile_items.push(ProfileItem::new());
+            profile_items.push(item);
             return Some(profile_items.len() - 1);
         }
         None
@@ -1146,7 +1320,7 @@ pub fn clear_profiling(&self) {
     }
 
     /// Output profiling data to the given filename.
-    pub fn emit_profiling(&self, path: &OsStr) {
+    pub fn emit_profiling(&self, path: &OsStr, format: ProfileFormat) {
         // Save profiling information. OK to not use CLO_EXEC here because this is called while fish is
         // exiting (and hence will not fork).
         let mut f = match std::fs::File::create(path) {
@@ -1163,7 +1337,13 @@ pub fn emit_profiling(&self, path: &OsStr) {
                 return;
             }
         };
-        print_profile(&self.profile_items.borrow(), &mut f);
+        let items = self.profile_items.borrow();
+        let vars = self.vars();
+        match format {
+            ProfileFormat::Text => print_profile(&items, None, &mut f),
+            ProfileFormat::Located => print_profile(&items, Some(vars), &mut f),
+            ProfileFormat::Folded => print_profile_folded(&items, vars, &mut f),
+        }
     }
 
     pub fn get_backtrace(&self, src: &wstr, errors: &ParseErrorList) -> WString {
@@ -1313,14 +1493,71 @@ fn user_presentable_path(path: &wstr, vars: &dyn Environment) -> WString {
     replace_home_directory_with_tilde(path, vars)
 }
 
+/// Return the time spent in the item at `idx` itself, that is its total time minus the total
+/// time consumed by subsequent items exactly one eval level deeper.
+fn profile_self_time(items: &[ProfileItem], idx: usize) -> Microseconds {
+    let item = &items[idx];
+    let mut self_time = item.duration;
+    for nested_item in items[idx + 1..].iter() {
+        if nested_item.skipped {
+            continue;
+        }
+
+        // If the eval level is not larger, then we have exhausted nested items.
+        if nested_item.level <= item.level {
+            break;
+        }
+
+        // If the eval level is exactly one more than our level, it is a directly nested item.
+        if nested_item.level == item.level + 1 {
+            self_time -= nested_item.duration;
+        }
+    }
+    self_time
+}
+
+/// Return the file and line of a profile item, like "~/.config/fish/config.fish:12".
+fn profile_location(item: &ProfileItem, vars: &dyn Environment) -> WString {
+    let mut location = match &item.file {
+        Some(file) => user_presentable_path(file, vars),
+        None => L!("-").to_owned(),
+    };
+    if let Some(lineno) = item.line {
+        location.push_utfstr(&sprintf!(":%d", lineno.get()));
+    }
+    location
+}
+
 /// Print profiling information to the specified stream.
-fn print_profile(items: &[ProfileItem], out: &mut File) {
+/// If `vars` is given, a column with the location of each command is added.
+fn print_profile(items: &[ProfileItem], vars: Option<&dyn Environment>, out: &mut File) {
     let col_width = 10;
+    let locations: Option<Vec<WString>> = vars.map(|vars| {
+        items
+            .iter()
+            .map(|item| profile_location(item, vars))
+            .collect()
+    });
+    let location_width = locations.as_ref().map_or(0, |locations| {
+        locations
+            .iter()
+            .map(|location| location.len())
+            .max()
+            .unwrap_or(0)
+            .max("Location".len())
+    });
     let _ = out.write_all(
-        format!(
-            "{:^col_width$} {:^col_width$} Command\n",
-            "Time (μs)", "Sum (μs)",
-        )
+        if locations.is_some() {
+            format!(
+                "{:^col_width$} {:^col_width$} {:<location_width$} Command\n",
+                "Time (μs)", "Sum (μs)", "Location",
+            )
+        } else {
+            format!(
+                "{:^col_width$} {:^col_width$} Command\n",
+                "Time (μs)", "Sum (μs)",
+            )
+        }
         .as_bytes(),
     );
     for (idx, item) in items.iter().enumerate() {
@@ -1329,35 +1566,18 @@ fn print_profile(items: &[ProfileItem], out: &mut File) {
         }
 
         let total_time = item.duration;
-
-        // Compute the self time as the total time, minus the total time consumed by subsequent
-        // items exactly one eval level deeper.
-        let mut self_time = item.duration;
-        for nested_item in items[idx + 1..].iter() {
-            if nested_item.skipped {
-                continue;
-            }
-
-            // If the eval level is not larger, then we have exhausted nested items.
-            if nested_item.level <= item.level {
-                break;
-            }
-
-            // If the eval level is exactly one more than our level, it is a directly nested item.
-            if nested_item.level == item.level + 1 {
-                self_time -= nested_item.duration;
-            }
-        }
+        let self_time = profile_self_time(items, idx);
 
         let level = item.level.unsigned_abs().saturating_add(1);
-        let _ = out.write_all(
-            format!(
-                "{:>col_width$} {:>col_width$} {:->level$} ",
-                self_time, total_time, '>'
-            )
-            .as_bytes(),
-        );
-        let indentation_level = col_width + 1 + col_width + 1 + level + 1;
+        let _ = out
+            .write_all(format!("{:>col_width$} {:>col_width$} ", self_time, total_time).as_bytes());
+        let mut indentation_level = col_width + 1 + col_width + 1 + level + 1;
+        if let Some(locations) = &locations {
+            let _ = out
+                .write_all(format!("{:<location_width$} ", locations[idx].to_string()).as_bytes());
+            indentation_level += location_width + 1;
+        }
+        let _ = out.write_all(format!("{:->level$} ", '>').as_bytes());
         let indented_cmd = item.cmd.replace(
             L!("\n"),
             &(WString::from("\n") + &wstr::repeat(L!(" "), indentation_level)[..]),
@@ -1367,6 +1587,54 @@ fn print_profile(items: &[ProfileItem], out: &mut File) {
     }
 }
 
+/// Print profiling information in the "folded" format used by flamegraph tools: each line is a
+/// stack of functions separated by semicolons, followed by the time spent directly in it. Code
+/// outside of any function is attributed to its file.
+fn print_profile_folded(items: &[ProfileItem], vars: &dyn Environment, out: &mut File) {
+    let mut times: BTreeMap<WString, Microseconds> = BTreeMap::new();
+    // The enclosing items of the current one, with their eval level and frame name.
+    let mut ancestors: Vec<(isize, WString)> = vec![];
+    for (idx, item) in items.iter().enumerate() {
+        if item.skipped {
+            continue;
+        }
+        while ancestors
+            .last()
+            .is_some_and(|(level, _)| *level >= item.level)
+        {
+            ancestors.pop();
+        }
+        let frame = match (&item.function, &item.file) {
+            (Some(function), _) => function.clone(),
+            (None, Some(file)) => user_presentable_path(file, vars),
+            (None, None) => L!("fish").to_owned(),
+        };
+        ancestors.push((item.level, frame.replace(L!(";"), L!(":"))));
+
+        // Nested blocks and command substitutions run in the same function as their parent,
+        // so only a change of frame starts a new entry in the stack.
+        let mut stack = WString::new();
+        let mut last_frame: Option<&WString> = None;
+        for (_, frame) in &ancestors {
+            if last_frame == Some(frame) {
+                continue;
+            }
+            if last_frame.is_some() {
+                stack.push(';');
+            }
+            stack.push_utfstr(frame);
+            last_frame = Some(frame);
+        }
+        *times.entry(stack).or_default() += profile_self_time(items, idx);
+    }
+
+    for (stack, time) in times {
+        if time > 0 {
+            let _ = out.write_all(&wcs2bytes(&sprintf!("%s %d\n", stack, time)));
+        }
+    }
+}
+
 /// Append stack trace info for the block `b` to `trace`.
 fn append_block_description_to_stack_trace(
     parser: &Parser,
@@ -1432,8 +1700,20 @@ fn append_block_description_to_stack_trace(
             trace.push('\n');
             print_source_location = true;
         }
+        BlockType::catch_block => {
+            let Some(BlockData::Catch { commandline }) = b.data() else {
+                unreachable!()
+            };
+            trace.push_utfstr(&wgettext_fmt!(
+                "in catch block for failed command '%s'",
+                commandline
+            ));
+            trace.push('\n');
+            print_source_location = true;
+        }
         BlockType::top
         | BlockType::begin
+        | BlockType::try_block
         | BlockType::switch_block
         | BlockType::while_block
         | BlockType::for_block
@@ -1468,6 +1748,10 @@ pub enum BlockType {
     function_call { shadows: bool },
     /// Switch block
     switch_block,
+    /// Try block
+    try_block,
+    /// Catch block, which handles a failure in a try block
+    catch_block,
     /// Command substitution scope
     subst,
     /// Outermost block
@@ -1504,6 +1788,7 @@ mod tests {
     use crate::common::str2wcstring;
     use crate::env::EnvStack;
     use crate::expand::ExpandFlags;
+    use crate::future_feature_flags::{FeatureFlag, scoped_test};
     use crate::io::{IoBufferfill, IoChain};
     use crate::parse_constants::{
         ParseErrorCode, ParseIssue, ParseTokenType, ParseTreeFlags, StatementDecoration,
@@ -2125,6 +2410,11 @@ macro_rules! validate {
         validate!("case", ParseErrorCode::UnbalancingCase);
         validate!("if true ; case ; end", ParseErrorCode::UnbalancingCase);
 
+        scoped_test(FeatureFlag::TryCatch, true, || {
+            validate!("catch", ParseErrorCode::UnbalancingCatch);
+            validate!("if true ; catch ; end", ParseErrorCode::UnbalancingCatch);
+        });
+
         validate!("begin ; }", ParseErrorCode::UnbalancingBrace);
 
         validate!("true | and", ParseErrorCode::AndOrInPipeline);
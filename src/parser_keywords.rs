@@ -1,11 +1,14 @@
 //! Functions having to do with parser keywords, like testing if a function is a block command.
 
+use crate::future_feature_flags::{FeatureFlag, feature_test};
 use crate::prelude::*;
 
 struct ReservedWord {
     text: &'static wstr,
     is_reserved: bool,
     is_super_command: bool,
+    /// The feature flag that makes this a keyword, if any.
+    feature: Option<FeatureFlag>,
 }
 
 macro_rules! rw {
@@ -14,6 +17,7 @@ macro_rules! rw {
             text: L!($text),
             is_reserved: true,
             is_super_command: false,
+            feature: None,
         }
     };
     ( ( $text:literal, [subcommand] ) ) => {
@@ -21,6 +25,7 @@ macro_rules! rw {
             text: L!($text),
             is_reserved: true,
             is_super_command: true,
+            feature: None,
         }
     };
     ( ( $text:literal, [subcommand], not reserved ) ) => {
@@ -28,6 +33,15 @@ macro_rules! rw {
             text: L!($text),
             is_reserved: false,
             is_super_command: true,
+            feature: None,
+        }
+    };
+    ( ( $text:literal, [subcommand], feature $flag:ident ) ) => {
+        ReservedWord {
+            text: L!($text),
+            is_reserved: true,
+            is_super_command: true,
+            feature: Some(FeatureFlag::$flag),
         }
     };
 }
@@ -48,6 +62,7 @@ macro_rules! reserved_words {
     ("break"),
     ("builtin", [subcommand]),
     ("case"),
+    ("catch", [subcommand], feature TryCatch),
     ("command", [subcommand]),
     ("continue"),
     ("else", [subcommand]),
@@ -67,6 +82,7 @@ macro_rules! reserved_words {
     ("switch"),
     ("test"),
     ("time", [subcommand]),
+    ("try", [subcommand], feature TryCatch),
     ("while", [subcommand]),
 );
 
@@ -74,6 +90,7 @@ fn reserved_word(cmd: &wstr) -> Option<&'static ReservedWord> {
     RESERVED_WORDS
         .iter()
         .find(|reserved_word| reserved_word.text == cmd)
+        .filter(|reserved_word| reserved_word.feature.is_none_or(feature_test))
 }
 
 /// Tests if the specified command's parameters should be interpreted as another command.
@@ -531,6 +531,31 @@ pub fn path_apply_working_directory(path: &wstr, working_directory: &wstr) -> WS
     new_path
 }
 
+/// Names of the version control directories that mark the root of a project.
+const PROJECT_ROOT_MARKERS: [&wstr; 3] = [L!(".git"), L!(".hg"), L!(".jj")];
+
+/// Returns the closest directory at or above `dir` that contains a version control directory, or
+/// None if there is none. `dir` must be absolute.
+pub fn path_get_project_root(dir: &wstr) -> Option<WString> {
+    if !dir.starts_with('/') {
+        return None;
+    }
+    let mut dir = normalize_path(dir, false);
+    loop {
+        for marker in PROJECT_ROOT_MARKERS {
+            let mut candidate = dir.clone();
+            append_path_component(&mut candidate, marker);
+            if wstat(&candidate).is_ok() {
+                return Some(dir);
+            }
+        }
+        if dir == "/" {
+            return None;
+        }
+        dir = wdirname(&dir).to_owned();
+    }
+}
+
 /// The following type wraps up a user's "base" directories, corresponding (conceptually if not
 /// actually) to XDG spec.
 struct BaseDirectory {
@@ -735,7 +760,11 @@ pub fn append_path_component(path: &mut WString, component: &wstr) {
 
 #[cfg(test)]
 mod tests {
-    use super::{path_apply_working_directory, path_make_canonical, paths_are_equivalent};
+    use super::{
+        path_apply_working_directory, path_get_project_root, path_make_canonical,
+        paths_are_equivalent,
+    };
+    use crate::common::osstr2wcstring;
     use crate::prelude::*;
 
     #[test]
@@ -790,4 +819,22 @@ fn test_path() {
         assert!(path_apply_working_directory(L!(""), L!("/def/")).is_empty());
         assert_eq!(path_apply_working_directory(L!("abc"), L!("")), L!("abc"));
     }
+
+    #[test]
+    fn test_path_get_project_root() {
+        let tempdir = fish_tempfile::new_dir().unwrap();
+        let nested = tempdir.path().join("project/src/module");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(tempdir.path().join("project/.git")).unwrap();
+
+        let root = osstr2wcstring(tempdir.path().join("project"));
+        assert_eq!(
+            path_get_project_root(&osstr2wcstring(&nested)),
+            Some(root.clone())
+        );
+        let mut with_slash = root.clone();
+        with_slash.push('/');
+        assert_eq!(path_get_project_root(&with_slash), Some(root));
+        assert_eq!(path_get_project_root(L!("relative/dir")), None);
+    }
 }
@@ -9,7 +9,7 @@
 use crate::flog::{flog, flogf};
 use crate::global_safety::RelaxedAtomicBool;
 use crate::io::IoChain;
-use crate::job_group::{JobGroup, MaybeJobId};
+use crate::job_group::{JobGroup, MaybeJobId, TimeoutStage};
 use crate::parse_tree::NodeRef;
 use crate::parser::{Block, Parser};
 use crate::portable_atomic::AtomicU64;
@@ -562,6 +562,11 @@ pub struct JobProperties {
 
     /// Whether this job was created as part of an event handler.
     pub from_event_handler: bool,
+
+    /// Whether this job runs the command of a process substitution in the background. Such a job
+    /// is not kept in the job list and does not set $last_pid; its processes are reaped like
+    /// those of disowned jobs.
+    pub is_process_substitution: bool,
 }
 
 /// Flags associated with the job.
@@ -583,6 +588,9 @@ pub struct JobFlags {
 
     // Indicates that we are the "group root." Any other jobs using this tree are nested.
     pub is_group_root: bool,
+
+    /// The stage of the group's timeout whose signal was last sent to this job's processes.
+    pub timeout_stage: TimeoutStage,
 }
 
 /// A struct representing a job. A job is a pipeline of one or more processes.
@@ -718,6 +726,11 @@ pub fn is_initially_background(&self) -> bool {
         self.properties.initial_background
     }
 
+    /// Return whether this job runs the command of a process substitution.
+    pub fn is_process_substitution(&self) -> bool {
+        self.properties.is_process_substitution
+    }
+
     /// Mark this job as constructed. The job must not have previously been marked as constructed.
     pub fn mark_constructed(&self) {
         assert!(!self.is_constructed(), "Job was already constructed");
@@ -1175,10 +1188,39 @@ fn reap_disowned_pids() {
 /// we exit. Poll these from time-to-time to prevent zombie processes from happening (#5342).
 static DISOWNED_PIDS: Mutex<Vec<Pid>> = Mutex::new(Vec::new());
 
+/// Send the signals of expired timeouts to the processes of the jobs in their groups, see the
+/// `timeout` builtin.
+fn signal_timed_out_jobs(parser: &Parser) {
+    for j in parser.jobs().iter() {
+        let Some((stage, signal)) = j.group().timeout_stage() else {
+            continue;
+        };
+        if stage <= j.flags().timeout_stage || !j.is_constructed() || j.is_completed() {
+            continue;
+        }
+        j.mut_flags().timeout_stage = stage;
+        let signal = if stage == TimeoutStage::Killing {
+            NixSignal::SIGKILL
+        } else {
+            signal
+        };
+        flogf!(
+            proc_job_run,
+            "Job %d (%s) timed out, sending %s",
+            j.job_id(),
+            j.command(),
+            signal.as_str()
+        );
+        j.signal(signal);
+    }
+}
+
 /// See if any reapable processes have exited, and mark them accordingly.
 /// \param block_ok if no reapable processes have exited, block until one is (or until we receive a
 /// signal).
 fn process_mark_finished_children(parser: &Parser, block_ok: bool, block_io: Option<&IoChain>) {
+    signal_timed_out_jobs(parser);
+
     // Get the exit and signal generations of all reapable processes.
     // The exit generation tells us if we have an exit; the signal generation allows for detecting
     // SIGHUP and SIGINT.
@@ -1379,9 +1421,10 @@ fn proc_wants_summary(j: &Job, p: &Process) -> bool {
         return false;
     }
 
-    // Does the job want to suppress notifications?
+    // Does the job want to suppress notifications, or did it time out and got killed by us?
     // Note we always report crashes.
-    if j.skip_notification() && !CRASHSIGNALS.contains(&s.signal_code()) {
+    if (j.skip_notification() || j.group().timed_out()) && !CRASHSIGNALS.contains(&s.signal_code())
+    {
         return false;
     }
 
@@ -26,6 +26,10 @@ pub(super) struct Debouncers {
     // Debounce history pager computations. This holds a callback, not a single value,
     // both to demonstrate the technique and because the callback can capture local variables.
     pub history_pager: Debounce<Callback>,
+    // Debounce tab completion computations.
+    pub completions: Debounce<reader::CompletionResult>,
+    // Debounce the completion preview, so it only runs once the pager selection settles.
+    pub completion_preview: Debounce<Callback>,
 }
 
 impl Debouncers {
@@ -37,10 +41,14 @@ pub fn new() -> Self {
         const HIGHLIGHT_TIMEOUT: Duration = Duration::from_millis(500);
         const HISTORY_PAGER_TIMEOUT: Duration = Duration::from_millis(500);
         const AUTOSUGGEST_TIMEOUT: Duration = Duration::from_millis(500);
+        const COMPLETION_TIMEOUT: Duration = Duration::from_millis(500);
+        const COMPLETION_PREVIEW_TIMEOUT: Duration = Duration::from_millis(500);
         Self {
             autosuggestions: Debounce::new(&pool, &event_signaller, AUTOSUGGEST_TIMEOUT),
             highlight: Debounce::new(&pool, &event_signaller, HIGHLIGHT_TIMEOUT),
             history_pager: Debounce::new(&pool, &event_signaller, HISTORY_PAGER_TIMEOUT),
+            completions: Debounce::new(&pool, &event_signaller, COMPLETION_TIMEOUT),
+            completion_preview: Debounce::new(&pool, &event_signaller, COMPLETION_PREVIEW_TIMEOUT),
             event_signaller,
         }
     }
@@ -27,20 +27,21 @@
 use crate::builtins::shared::STATUS_CMD_OK;
 use crate::common::ScopeGuarding;
 use crate::common::{
-    EscapeFlags, EscapeStringStyle, ScopeGuard, bytes2wcstring, escape, escape_string,
-    exit_without_destructors, get_ellipsis_char, get_obfuscation_read_char, get_program_name,
-    restore_term_foreground_process_group_for_exit, shell_modes, write_loop,
+    EscapeFlags, EscapeStringStyle, ScopeGuard, UnescapeFlags, UnescapeStringStyle, bytes2wcstring,
+    escape, escape_string, exit_without_destructors, get_ellipsis_char, get_obfuscation_read_char,
+    get_program_name, restore_term_foreground_process_group_for_exit, shell_modes, unescape_string,
+    write_loop,
 };
 use crate::complete::{
     CompleteFlags, Completion, CompletionList, CompletionRequestOptions, complete, complete_load,
-    sort_and_prioritize,
+    complete_without_parser, sort_and_prioritize,
 };
 use crate::editable_line::{Edit, EditableLine, line_at_cursor, range_of_line_at_cursor};
 use crate::env::EnvStack;
 use crate::env::{EnvMode, Environment, Statuses};
 use crate::env_dispatch::MIDNIGHT_COMMANDER_SID;
 use crate::env_dispatch::guess_emoji_width;
-use crate::exec::exec_subshell;
+use crate::exec::{exec_subshell, exec_subshell_for_expand};
 use crate::expand::expand_one;
 use crate::expand::{ExpandFlags, ExpandResultCode, expand_string, expand_tilde};
 use crate::fd_readable_set::poll_fd_readable;
@@ -66,10 +67,11 @@
     QueryResultEvent, ReadlineCmd, RecurrentQuery, TerminalQuery, stop_query,
 };
 use crate::io::IoChain;
+use crate::job_group::{JobGroup, TimeoutStage};
 use crate::key::ViewportPosition;
 use crate::kill::{kill_add, kill_replace, kill_yank, kill_yank_rotate};
 use crate::nix::{getpid, isatty};
-use crate::operation_context::{OperationContext, get_bg_context};
+use crate::operation_context::{EXPANSION_LIMIT_DEFAULT, OperationContext, get_bg_context};
 use crate::pager::{PageRendering, Pager, SelectionMotion};
 use crate::panic::AT_EXIT;
 use crate::parse_constants::SourceRange;
@@ -81,6 +83,7 @@
     get_token_extent, lineno, locate_cmdsubst_range,
 };
 use crate::parser::{BlockType, EvalRes, Parser, ParserEnvSetMode};
+use crate::path::path_get_project_root;
 use crate::portable_atomic::AtomicU64;
 use crate::prelude::*;
 use crate::proc::{
@@ -102,7 +105,7 @@
 use crate::terminal::{BufferedOutputter, Outputter};
 use crate::termsize::{safe_termsize_invalidate_tty, termsize_last, termsize_update};
 use crate::text_face::{TextFace, parse_text_face};
-use crate::threads::{assert_is_background_thread, assert_is_main_thread};
+use crate::threads::{self, assert_is_background_thread, assert_is_main_thread};
 use crate::tokenizer::{
     TOK_ACCEPT_UNFINISHED, TOK_SHOW_COMMENTS, TokenType, Tokenizer, quote_end, tok_command,
     variable_assignment_equals_pos,
@@ -113,7 +116,7 @@
     TtyHandoff, get_tty_protocols_active, initialize_tty_protocols, safe_deactivate_tty_protocols,
 };
 use crate::wildcard::wildcard_has;
-use crate::wutil::{fstat, perror, write_to_fd, wstat};
+use crate::wutil::{FileId, file_id_for_path, fstat, perror, write_to_fd, wstat};
 use crate::{abbrs, event, function};
 use assert_matches::assert_matches;
 use errno::{Errno, errno};
@@ -151,6 +154,7 @@
     sync::{
         Arc, LazyLock, Mutex, MutexGuard, OnceLock,
         atomic::{AtomicI32, AtomicU8, AtomicU32, Ordering},
+        mpsc::{self, RecvTimeoutError},
     },
     time::{Duration, Instant},
 };
@@ -700,6 +704,8 @@ pub struct ReaderData {
     history_search: ReaderHistorySearch,
     /// In-pager history search.
     history_pager: Option<Range<usize>>,
+    /// Whether the history pager interprets the search field as a regular expression.
+    history_pager_regex: bool,
 
     /// The cursor selection mode.
     cursor_selection_mode: CursorSelectionMode,
@@ -718,6 +724,9 @@ pub struct ReaderData {
     cycle_command_line: WString,
     cycle_cursor_pos: usize,
 
+    /// The candidate and description that the completion preview was last computed for.
+    completion_preview_source: Option<(WString, WString)>,
+
     /// If set, a key binding or the 'exit' command has asked us to exit our read loop.
     exit_loop_requested: bool,
     /// If this is true, exit reader even if there are running jobs. This happens if we press e.g.
@@ -739,6 +748,9 @@ pub struct ReaderData {
     /// If these differs from the text of the command line, then we must kick off a new request.
     in_flight_highlight_request: WString,
     in_flight_autosuggest_request: WString,
+    /// The generation count of the command line whose tab completions are being computed, if any.
+    /// Clearing it cancels the request.
+    in_flight_complete_request: Option<u32>,
 
     rls: Option<ReadlineLoopState>,
 
@@ -785,6 +797,12 @@ pub(super) fn service_debounced_results(&mut self) {
         if let Some(cb) = self.debouncers.history_pager.take_result() {
             cb(self);
         }
+        if let Some(cb) = self.debouncers.completion_preview.take_result() {
+            cb(self);
+        }
+        if let Some(r) = self.debouncers.completions.take_result() {
+            self.completions_completed(r);
+        }
     }
 }
 
@@ -860,8 +878,12 @@ fn read_i(parser: &Parser) {
         data.update_buff_pos(EditableLineTag::Commandline, None);
         BufferedOutputter::new(Outputter::stdoutput()).write_command(Osc133CommandStart(&command));
         event::fire_generic(parser, L!("fish_preexec").to_owned(), vec![command.clone()]);
-        let eval_res = reader_run_command(parser, &command);
+        let (eval_res, duration) = reader_run_command(parser, &command);
         signal_clear_cancel();
+        // Stepping through a script lasts until the command that started it is done.
+        if !parser.is_breakpoint() {
+            parser.libdata_mut().debugger.stop_stepping();
+        }
         if !eval_res.no_status {
             STATUS_COUNT.fetch_add(1, Ordering::Relaxed);
         }
@@ -870,12 +892,14 @@ fn read_i(parser: &Parser) {
         data.exit_loop_requested |= parser.libdata().exit_current_script;
         parser.libdata_mut().exit_current_script = false;
 
-        BufferedOutputter::new(Outputter::stdoutput()).write_command(Osc133CommandFinished {
-            exit_status: parser.get_last_status(),
-        });
+        let exit_status = parser.get_last_status();
+        BufferedOutputter::new(Outputter::stdoutput())
+            .write_command(Osc133CommandFinished { exit_status });
         event::fire_generic(parser, L!("fish_postexec").to_owned(), vec![command]);
-        // Allow any pending history items to be returned in the history array.
-        data.history.resolve_pending();
+        // Record how the command went, and allow any pending history items to be returned in the
+        // history array.
+        data.history
+            .resolve_pending_with_result(exit_status, duration);
 
         // Make cursor visible. Every even vaguely used terminal agrees on this sequence.
         data.screen.write_command(DecsetShowCursor);
@@ -1305,6 +1329,15 @@ pub fn reader_status_count() -> u64 {
 /// The name of the function for getting the input mode indicator.
 const MODE_PROMPT_FUNCTION_NAME: &wstr = L!("fish_mode_prompt");
 
+/// The name of the function that prints a preview of the selected completion.
+const COMPLETE_PREVIEW_FUNCTION_NAME: &wstr = L!("fish_complete_preview");
+
+/// How long the pager selection must stay put before fish_complete_preview runs.
+const COMPLETE_PREVIEW_DELAY: Duration = Duration::from_millis(100);
+
+/// How often to check for typing while fish_complete_preview runs.
+const COMPLETE_PREVIEW_INPUT_POLL: Duration = Duration::from_millis(10);
+
 /// The default title for the reader. This is used by reader_readline.
 const DEFAULT_TITLE: &wstr = L!("echo (status current-command) \" \" $PWD");
 
@@ -1324,6 +1357,10 @@ pub fn read_generation_count() -> u32 {
 /// performing a no-io syntax highlighting. See #7418, #5912.
 const HIGHLIGHT_TIMEOUT_FOR_EXECUTION: Duration = Duration::from_millis(250);
 
+/// Tab completions are computed on a background thread. Most are quick, so we wait this long for
+/// them before showing that they are loading, which would otherwise flash on every tab press.
+const COMPLETION_LOADING_DELAY: Duration = Duration::from_millis(100);
+
 /// The readers interrupt signal handler. Cancels all currently running blocks.
 /// This is called from a signal handler!
 pub fn reader_handle_sigint() {
@@ -1390,6 +1427,7 @@ fn new(
             history,
             history_search: Default::default(),
             history_pager: None,
+            history_pager_regex: false,
             cursor_selection_mode: CursorSelectionMode::Exclusive,
             cursor_end_mode: CursorEndMode::Exclusive,
             selection: Default::default(),
@@ -1398,6 +1436,7 @@ fn new(
             right_prompt_buff: Default::default(),
             cycle_command_line: Default::default(),
             cycle_cursor_pos: Default::default(),
+            completion_preview_source: Default::default(),
             exit_loop_requested: Default::default(),
             did_warn_for_bg_jobs: Default::default(),
             kill_item: Default::default(),
@@ -1407,6 +1446,7 @@ fn new(
             last_jump_precision: JumpPrecision::To,
             in_flight_highlight_request: Default::default(),
             in_flight_autosuggest_request: Default::default(),
+            in_flight_complete_request: None,
             rls: None,
             debouncers: Debouncers::new(),
         }))
@@ -2752,6 +2792,7 @@ fn color_suggest_repaint_now(&mut self) {
         if self.conf.inputfd == STDIN_FILENO {
             self.update_autosuggestion();
             self.super_highlight_me_plenty();
+            self.update_completion_preview();
         }
         if self.is_repaint_needed(None) {
             self.layout_and_repaint(L!("toplevel"));
@@ -3497,6 +3538,7 @@ fn handle_readline_command(&mut self, c: ReadlineCmd) {
                 self.cycle_cursor_pos = self.command_line.position();
 
                 self.history_pager = Some(0..1);
+                self.history_pager_regex = false;
                 // Update the pager data.
                 self.pager.set_search_field_shown(true);
                 self.pager.set_prefix(Cow::Borrowed(L!("► ")), false);
@@ -3520,6 +3562,23 @@ fn handle_readline_command(&mut self, c: ReadlineCmd) {
                 };
                 self.insert_string(EditableLineTag::SearchField, &search_string);
             }
+            rl::HistoryPagerToggleRegex => {
+                if self.history_pager.is_none() {
+                    return;
+                }
+                self.history_pager_regex = !self.history_pager_regex;
+                let prefix = if self.history_pager_regex {
+                    L!("regex► ")
+                } else {
+                    L!("► ")
+                };
+                self.pager.set_prefix(Cow::Borrowed(prefix), false);
+                self.fill_history_pager(
+                    HistoryPagerInvocation::Anew,
+                    Some(SelectionMotion::Next),
+                    SearchDirection::Backward,
+                );
+            }
             #[allow(deprecated)]
             rl::HistoryDelete | rl::HistoryPagerDelete => {
                 // Also applies to ordinary history search.
@@ -4571,6 +4630,8 @@ impl ReaderData {
     // Ensure we have no pager contents.
     fn clear_pager(&mut self) {
         self.pager.clear();
+        self.completion_preview_source = None;
+        self.in_flight_complete_request = None;
         self.history_pager = None;
         self.clear(EditableLineTag::SearchField);
         self.command_line_transient_edit = None;
@@ -5113,6 +5174,121 @@ fn exec_prompt_cmd(&self, prompt_cmd: &wstr, final_prompt: bool) -> Vec<WString>
         output
     }
 
+    /// Return the selected completion as it would appear as argument, along with its description.
+    fn selected_completion_candidate(&self) -> Option<(WString, WString)> {
+        let completion = self
+            .pager
+            .selected_completion(&self.current_page_rendering)?;
+        if completion.replaces_line() {
+            return None;
+        }
+        let mut candidate = if completion.replaces_token() {
+            WString::new()
+        } else {
+            // The completion is appended to the token it completes.
+            let token = get_token_extent(&self.cycle_command_line, self.cycle_cursor_pos).0;
+            let prefix = &self.cycle_command_line[token.start..self.cycle_cursor_pos];
+            unescape_string(
+                prefix,
+                UnescapeStringStyle::Script(UnescapeFlags::INCOMPLETE),
+            )?
+        };
+        candidate.push_utfstr(&completion.completion);
+        expand_tilde(&mut candidate, self.vars());
+        Some((candidate, completion.description.clone()))
+    }
+
+    /// Schedule fish_complete_preview for the selected completion.
+    /// The function only runs once the selection has settled, so quickly moving through the pager
+    /// does not wait for it on every step.
+    fn update_completion_preview(&mut self) {
+        let source = if function::exists(COMPLETE_PREVIEW_FUNCTION_NAME, self.parser) {
+            self.selected_completion_candidate()
+        } else {
+            None
+        };
+        if source == self.completion_preview_source {
+            return;
+        }
+        // Don't keep showing the preview of a previous selection.
+        self.pager.set_preview(&[]);
+        if source.is_some() && self.has_pending_input() {
+            // Leave the source unset, so we try again once the pending keys are handled.
+            self.completion_preview_source = None;
+            return;
+        }
+        self.completion_preview_source = source.clone();
+        let Some(source) = source else {
+            return;
+        };
+        let performer = move || -> iothreads::Callback {
+            std::thread::sleep(COMPLETE_PREVIEW_DELAY);
+            Box::new(move |r: &mut Reader| r.run_completion_preview(source))
+        };
+        self.debouncers.completion_preview.perform(performer);
+    }
+
+    /// Return if there are keys that have not been handled yet.
+    fn has_pending_input(&self) -> bool {
+        self.has_lookahead() || poll_fd_readable(self.conf.inputfd)
+    }
+
+    /// Run fish_complete_preview for `source`, and show its output in the pager.
+    /// Typing stops the function, like the `timeout` builtin stops its command once the time is up,
+    /// and the preview is tried again once the keys are handled.
+    fn run_completion_preview(&mut self, source: (WString, WString)) {
+        if self.completion_preview_source.as_ref() != Some(&source) {
+            return; // The selection has changed since.
+        }
+        if self.has_pending_input() {
+            self.completion_preview_source = None;
+            return;
+        }
+        let (candidate, description) = source;
+        let mut output = vec![];
+        {
+            // Like prompts, the preview is run non-interactively and without fish_trace.
+            let _suppress_trace = self.parser.push_scope(|s| s.suppress_fish_trace = true);
+            let _noninteractive = self.parser.push_scope(|s| s.is_interactive = false);
+            let mut scoped_tty = TtyHandoff::new(reader_save_screen_state);
+            scoped_tty.disable_tty_protocols();
+
+            // Allow the function to use `commandline`.
+            self.update_commandline_state();
+            let last_statuses = self.parser.get_last_statuses();
+            let cmd = sprintf!(
+                "%s %s %s 2>/dev/null",
+                COMPLETE_PREVIEW_FUNCTION_NAME,
+                escape(&candidate),
+                escape(&description)
+            );
+
+            let group = JobGroup::create_with_timeout(cmd.clone(), Signal::SIGTERM, None);
+            let (finished, watcher) = mpsc::channel::<()>();
+            let watched_group = group.clone();
+            let inputfd = self.conf.inputfd;
+            threads::spawn(move || {
+                while watcher.recv_timeout(COMPLETE_PREVIEW_INPUT_POLL)
+                    == Err(RecvTimeoutError::Timeout)
+                {
+                    if poll_fd_readable(inputfd) {
+                        watched_group.advance_timeout(TimeoutStage::Expired);
+                        return;
+                    }
+                }
+            });
+            let _ = exec_subshell_for_expand(&cmd, self.parser, Some(&group), &mut output);
+            drop(finished);
+            self.parser.set_last_statuses(last_statuses);
+            if group.timed_out() {
+                self.completion_preview_source = None;
+                return;
+            }
+        }
+        self.pager.set_preview(&output);
+        self.layout_and_repaint(L!("complete-preview"));
+    }
+
     /// Execute prompt commands based on the provided arguments. The output is inserted into prompt_buff.
     fn exec_prompt(&mut self, full_prompt: bool, final_prompt: bool) {
         // Suppress fish_trace while in the prompt.
@@ -5260,6 +5436,43 @@ fn search_string(&self) -> &wstr {
     }
 }
 
+/// Once a history match run elsewhere has been found, the number of further history items that are
+/// searched for one that was run in the current directory or project.
+const AUTOSUGGEST_LOCAL_SEARCH_LIMIT: usize = 500;
+
+/// Returns the directory whose history items are preferred for autosuggestions: the project
+/// containing `working_directory`, or else `working_directory` itself. Finding the project checks
+/// every parent directory, so the result is reused until the working directory changes, or until
+/// entries are added to or removed from it or the project root (e.g. by `git init`).
+fn autosuggest_local_directory(working_directory: &wstr) -> WString {
+    struct LocalDirectory {
+        dir: WString,
+        dir_id: FileId,
+        local_directory: WString,
+        local_directory_id: FileId,
+    }
+    static LAST_LOCAL_DIRECTORY: Mutex<Option<LocalDirectory>> = Mutex::new(None);
+    let dir_id = file_id_for_path(working_directory);
+    let mut last = LAST_LOCAL_DIRECTORY.lock().unwrap();
+    if let Some(last) = &*last {
+        if last.dir == working_directory
+            && last.dir_id == dir_id
+            && last.local_directory_id == file_id_for_path(&last.local_directory)
+        {
+            return last.local_directory.clone();
+        }
+    }
+    let local_directory =
+        path_get_project_root(working_directory).unwrap_or_else(|| working_directory.to_owned());
+    *last = Some(LocalDirectory {
+        dir: working_directory.to_owned(),
+        dir_id,
+        local_directory_id: file_id_for_path(&local_directory),
+        local_directory: local_directory.clone(),
+    });
+    local_directory
+}
+
 // Returns a function that can be invoked (potentially
 // on a background thread) to determine the autosuggestion
 fn get_autosuggestion_performer(
@@ -5282,6 +5495,9 @@ fn get_autosuggestion_performer(
         // Only to be used if no case-sensitive suggestions are found.
         let mut icase_history_result = None;
 
+        // Prefer commands that were run in the current project (or directory, outside of one).
+        let local_directory = autosuggest_local_directory(&working_directory);
+
         let line_range = range_of_line_at_cursor(&command_line, cursor_pos);
         // Search history for a matching item unless this line is not a continuation line or quoted.
         for (search_type, range) in [
@@ -5314,8 +5530,20 @@ fn get_autosuggestion_performer(
                 0,
             );
 
+            // The most recent valid match, used if no match was run locally.
+            let mut global_history_result = None;
+            let mut local_search_budget = AUTOSUGGEST_LOCAL_SEARCH_LIMIT;
+
             while !ctx.check_cancel() && searcher.go_to_next_match(SearchDirection::Backward) {
+                // Don't go through all of history looking for a local match.
+                if global_history_result.is_some() {
+                    if local_search_budget == 0 {
+                        break;
+                    }
+                    local_search_budget -= 1;
+                }
                 let item = searcher.current_item();
+                let is_local = item.ran_within(&local_directory);
 
                 let full = item.str();
                 let (suggested_range, icase) = if search_type == SearchType::Prefix {
@@ -5368,6 +5596,11 @@ fn get_autosuggestion_performer(
                     continue;
                 };
 
+                // Once we have a global fallback, only a local case-sensitive match can beat it.
+                if global_history_result.is_some() && (icase || !is_local) {
+                    continue;
+                }
+
                 if autosuggest_validate_from_history(
                     full,
                     suggested_range.clone(),
@@ -5386,11 +5619,20 @@ fn get_autosuggestion_performer(
                     );
                     if icase {
                         icase_history_result = Some(result);
-                    } else {
+                    } else if is_local {
                         return result;
+                    } else {
+                        global_history_result = Some(result);
                     }
                 }
             }
+
+            if ctx.check_cancel() {
+                return nothing;
+            }
+            if let Some(result) = global_history_result {
+                return result;
+            }
         }
 
         // Maybe cancel here.
@@ -5807,6 +6049,7 @@ fn history_pager_search(
     motion: Option<SelectionMotion>,
     history_index: usize,
     search_string: &wstr,
+    regex: bool,
 ) -> HistoryPagerResult {
     // Limit the number of elements to half the screen like we do for completions
     // Note that this is imperfect because we could have a multi-column layout.
@@ -5825,8 +6068,12 @@ fn history_pager_search(
             history_index,
         )
     };
-    let mut search = new_search(SearchType::ContainsGlob);
-    if !search.go_to_next_match(direction) && !contains_wildcards(search_string) {
+    let mut search = new_search(if regex {
+        SearchType::Regex
+    } else {
+        SearchType::ContainsGlob
+    });
+    if !search.go_to_next_match(direction) && !regex && !contains_wildcards(search_string) {
         // If there were no matches, and the user is not intending for
         // wildcard search, try again with subsequence search.
         search = new_search(SearchType::ContainsSubsequence);
@@ -5859,6 +6106,7 @@ fn history_pager_search(
             Some(SelectionMotion::Prev),
             history.size() + 1,
             search_string,
+            regex,
         )
     } else {
         HistoryPagerResult {
@@ -5900,11 +6148,13 @@ fn fill_history_pager(
             }
         }
         let search_term = self.pager.search_field_line.text().to_owned();
+        let regex = self.history_pager_regex;
         // Get a performer that produces the history pager result.
         let history = self.history.clone();
         let search_term = search_term.clone();
         let performer = move || -> iothreads::Callback {
-            let result = history_pager_search(&history, direction, motion, index, &search_term);
+            let result =
+                history_pager_search(&history, direction, motion, index, &search_term, regex);
             Box::new(move |r: &mut Reader| {
                 r.fill_history_pager_complete(result, why, old_pager_index);
             })
@@ -6261,6 +6511,7 @@ fn command_ends_history_search(c: ReadlineCmd) -> bool {
             | rl::HistoryLastTokenSearchForward
             | rl::HistoryDelete
             | rl::HistoryPagerDelete
+            | rl::HistoryPagerToggleRegex
             | rl::BeginningOfHistory
             | rl::EndOfHistory
             | rl::ScrollbackPush
@@ -6321,7 +6572,8 @@ fn check_for_orphaned_process(loop_count: usize, shell_pgid: libc::pid_t) -> boo
 
 /// Run the specified command with the correct terminal modes, and while taking care to perform job
 /// notification, set the title, etc.
-fn reader_run_command(parser: &Parser, cmd: &wstr) -> EvalRes {
+/// Returns the evaluation result and how long the command took.
+fn reader_run_command(parser: &Parser, cmd: &wstr) -> (EvalRes, Duration) {
     assert!(
         !get_tty_protocols_active(),
         "TTY protocols should not be active"
@@ -6345,12 +6597,11 @@ fn reader_run_command(parser: &Parser, cmd: &wstr) -> EvalRes {
     let time_before = Instant::now();
     let eval_res = parser.eval(cmd, &IoChain::new());
     job_reap(parser, true, None);
+    let duration = Instant::now().duration_since(time_before);
 
     // Update the execution duration iff a command is requested for execution
     // issue - #4926
     if !ft.is_empty() {
-        let time_after = Instant::now();
-        let duration = time_after.duration_since(time_before);
         parser.set_one(
             ENV_CMD_DURATION,
             ParserEnvSetMode::new(EnvMode::UNEXPORT),
@@ -6375,7 +6626,7 @@ fn reader_run_command(parser: &Parser, cmd: &wstr) -> EvalRes {
         proc_update_jiffies(parser);
     }
 
-    eval_res
+    (eval_res, duration)
 }
 
 fn reader_shell_test(parser: &Parser, bstr: &wstr) -> Result<(), ParseIssue> {
@@ -6857,6 +7108,56 @@ fn reader_can_replace(s: &wstr, flags: CompleteFlags) -> bool {
         .any(|c| matches!(c, '$' | '*' | '?' | '(' | '{' | '}' | ')'))
 }
 
+/// A tab completion request.
+pub(super) struct CompletionRequest {
+    /// The generation count of the command line when completing. If it changed, the request is
+    /// stale.
+    generation_count: u32,
+    /// The cursor position when completing.
+    cursor_pos: usize,
+    /// The command line from the beginning of the command substitution up to the end of the token
+    /// to complete.
+    cmdsub: WString,
+    /// The range of the token to complete in the command line.
+    token_range: Range<usize>,
+    /// Whether to show the pager's search field.
+    search: bool,
+}
+
+/// The result of computing tab completions on a background thread.
+pub(super) struct CompletionResult {
+    request: CompletionRequest,
+    /// The completions, or None if they need to run completion scripts, which only works on the
+    /// main thread.
+    completions: Option<Vec<Completion>>,
+}
+
+// Returns a function that can be invoked (potentially
+// on a background thread) to compute tab completions.
+fn get_completion_performer(
+    parser: &Parser,
+    request: CompletionRequest,
+) -> impl FnOnce() -> CompletionResult + use<> {
+    let vars = parser.vars().snapshot();
+    move || {
+        assert_is_background_thread();
+        // Like get_bg_context(), but without the lower expansion limit, since we show all
+        // completions.
+        let generation_count = request.generation_count;
+        let ctx = OperationContext::background_with_cancel_checker(
+            &vars,
+            Box::new(move || generation_count != read_generation_count()),
+            EXPANSION_LIMIT_DEFAULT,
+        );
+        let completions =
+            complete_without_parser(&request.cmdsub, CompletionRequestOptions::normal(), &ctx);
+        CompletionResult {
+            request,
+            completions,
+        }
+    }
+}
+
 impl<'a> Reader<'a> {
     /// Compute completions and update the pager and/or commandline as needed.
     fn compute_and_apply_completions(&mut self, c: ReadlineCmd) {
@@ -6866,6 +7167,11 @@ fn compute_and_apply_completions(&mut self, c: ReadlineCmd) {
             "should not be called with TTY protocols active"
         );
 
+        // Do nothing if we are already computing completions for this command line.
+        if self.in_flight_complete_request == Some(read_generation_count()) {
+            return;
+        }
+
         // Remove a trailing backslash. This may trigger an extra repaint, but this is
         // rare.
         let el = &self.command_line;
@@ -6925,19 +7231,99 @@ fn compute_and_apply_completions(&mut self, c: ReadlineCmd) {
             }
         }
 
-        // Construct a copy of the string from the beginning of the command substitution
-        // up to the end of the token we're completing.
-        let cmdsub = &el.text()[cmdsub_range.start..token_range.end];
+        let request = CompletionRequest {
+            generation_count: read_generation_count(),
+            cursor_pos: el.position(),
+            // Construct a copy of the string from the beginning of the command substitution
+            // up to the end of the token we're completing.
+            cmdsub: el.text()[cmdsub_range.start..token_range.end].to_owned(),
+            token_range,
+            search: c == ReadlineCmd::CompleteAndSearch,
+        };
+
+        // Kick off the completions in the background, where typing cancels them.
+        self.rls_mut().completion_action = None;
+        self.data.in_flight_complete_request = Some(request.generation_count);
+        let performer = get_completion_performer(self.parser, request);
+        self.debouncers.completions.perform(performer);
+
+        let mut now = Instant::now();
+        let deadline = now + COMPLETION_LOADING_DELAY;
+        while now < deadline {
+            let timeout = deadline - now;
+            if let Some(result) = self
+                .debouncers
+                .completions
+                .take_result_with_timeout(timeout)
+            {
+                self.completions_completed(result);
+            }
+            if self.in_flight_complete_request.is_none() {
+                return;
+            }
+            now = Instant::now();
+        }
+        self.pager.set_loading(true);
+    }
+
+    // Called after tab completions have been computed on a background thread.
+    fn completions_completed(&mut self, result: CompletionResult) {
+        assert_is_main_thread();
+        let CompletionResult {
+            request,
+            completions,
+        } = result;
+        if self.in_flight_complete_request != Some(request.generation_count) {
+            // This request was cancelled, or superseded by another one.
+            return;
+        }
+        self.data.in_flight_complete_request = None;
+
+        if request.generation_count == read_generation_count()
+            && request.cursor_pos == self.command_line.position()
+        {
+            // If the completions need completion scripts, run them here.
+            let completions = completions.or_else(|| self.complete_with_parser(&request.cmdsub));
+            if let Some(completions) = completions {
+                self.apply_completions(request, completions);
+            }
+        }
+        self.pager.set_loading(false);
+        self.color_suggest_repaint_now();
+    }
+
+    /// Compute completions which run completion scripts, like functions and command substitutions.
+    /// This is only possible on the main thread, so it blocks. Return None if it was cancelled by
+    /// control-C or by pending input, which is checked between completion scripts.
+    fn complete_with_parser(&mut self, cmdsub: &wstr) -> Option<Vec<Completion>> {
+        // Typing cancels completions, including keys that are already waiting.
+        if self.has_pending_input() {
+            return None;
+        }
 
-        let (mut comp, _needs_load) = complete(
-            cmdsub,
-            CompletionRequestOptions::normal(),
-            &self.parser.context(),
+        // Disable tty protocols while we run completion scripts, so that control-C
+        // triggers SIGINT (suppressed by CSI-U).
+        let mut tty = TtyHandoff::new(reader_save_screen_state);
+        tty.disable_tty_protocols();
+        let inputfd = self.conf.inputfd;
+        let ctx = OperationContext::foreground(
+            self.parser,
+            Box::new(move || signal_check_cancel() != 0 || poll_fd_readable(inputfd)),
+            EXPANSION_LIMIT_DEFAULT,
         );
+        let (completions, _needs_load) = complete(cmdsub, CompletionRequestOptions::normal(), &ctx);
+        let cancelled = ctx.check_cancel();
+        drop(tty);
+
+        (!cancelled).then_some(completions)
+    }
 
+    /// Update the pager and/or commandline with the completions of a tab completion request.
+    fn apply_completions(&mut self, request: CompletionRequest, mut comp: Vec<Completion>) {
         let el = &self.command_line;
         // User-supplied completions may have changed the commandline - prevent buffer
         // overflow.
+        let mut token_range = request.token_range;
         token_range.start = std::cmp::min(token_range.start, el.text().len());
         token_range.end = std::cmp::min(token_range.end, el.text().len());
 
@@ -6957,7 +7343,7 @@ fn compute_and_apply_completions(&mut self, c: ReadlineCmd) {
         };
 
         // Show the search field if requested and if we printed a list of completions.
-        if c == ReadlineCmd::CompleteAndSearch && !inserted_unique && !self.pager.is_empty() {
+        if request.search && !inserted_unique && !self.pager.is_empty() {
             self.pager.set_search_field_shown(true);
             self.select_completion_in_direction(SelectionMotion::Next, false);
         }
@@ -8,12 +8,14 @@
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum RedirectionMode {
-    Overwrite, // normal redirection: > file.txt
-    Append,    // appending redirection: >> file.txt
-    Input,     // input redirection: < file.txt
-    TryInput,  // try-input redirection: <? file.txt
-    Fd,        // fd redirection: 2>&1
-    NoClob,    // noclobber redirection: >? file.txt
+    Overwrite,  // normal redirection: > file.txt
+    Append,     // appending redirection: >> file.txt
+    Input,      // input redirection: < file.txt
+    TryInput,   // try-input redirection: <? file.txt
+    Fd,         // fd redirection: 2>&1
+    NoClob,     // noclobber redirection: >? file.txt
+    HereString, // here-string: <<< "text"
+    HereDoc,    // heredoc: <<EOF, with the body on the following lines
 }
 
 /// A type that represents the action dup2(src, target).
@@ -60,6 +62,7 @@ pub struct RedirectionSpec {
     /// The target of the redirection.
     /// For example in "3>&1", this will be "1".
     /// In "< file.txt" this will be "file.txt".
+    /// For heredocs and here-strings, this is the text fed to the fd.
     pub target: WString,
 }
 
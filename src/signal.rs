@@ -48,6 +48,11 @@ pub fn signal_clear_cancel() {
     CANCELLATION_SIGNAL.store(0, Ordering::Relaxed);
 }
 
+/// Set the cancellation signal, e.g. to restore it after it was cleared temporarily.
+pub fn signal_set_cancel(sig: i32) {
+    CANCELLATION_SIGNAL.store(sig, Ordering::Relaxed);
+}
+
 /// Return the most recent cancellation signal received by the fish process.
 /// Currently only SIGINT is considered a cancellation signal.
 /// This is thread safe.
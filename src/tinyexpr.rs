@@ -78,6 +78,46 @@ pub fn call(&self, args: &[f64]) -> f64 {
     }
 }
 
+/// A function in integer mode. These report errors like overflow instead of returning infinity
+/// or NaN.
+#[derive(Clone, Copy)]
+enum IntFunction {
+    Fn1(fn(i64) -> Result<i64, ErrorKind>),
+    Fn2(fn(i64, i64) -> Result<i64, ErrorKind>),
+    FnN(fn(&[i64]) -> Result<i64, ErrorKind>),
+}
+
+impl Debug for IntFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let variant = match self {
+            IntFunction::Fn1(_) => "Fn1",
+            IntFunction::Fn2(_) => "Fn2",
+            IntFunction::FnN(_) => "FnN",
+        };
+
+        write!(f, "IntFunction::{variant}(_)")
+    }
+}
+
+impl IntFunction {
+    pub fn arity(&self) -> Option<usize> {
+        match self {
+            IntFunction::Fn1(_) => Some(1),
+            IntFunction::Fn2(_) => Some(2),
+            IntFunction::FnN(_) => None,
+        }
+    }
+
+    pub fn call(&self, args: &[i64]) -> Result<i64, ErrorKind> {
+        match (self, args) {
+            (IntFunction::Fn1(f), [a]) => f(*a),
+            (IntFunction::Fn2(f), [a, b]) => f(*a, *b),
+            (IntFunction::FnN(f), args) => f(args),
+            (_, _) => panic!("Incorrect number of arguments for function call"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorKind {
     UnknownFunction,
@@ -86,9 +126,15 @@ pub enum ErrorKind {
     TooManyArgs,
     MissingOperator,
     UnexpectedToken,
-    LogicalOperator,
+    IntegerOperator,
+    SingleEquals,
+    MissingColon,
     DivByZero,
     NumberTooLarge,
+    Overflow,
+    NotAnInteger,
+    OutOfRange,
+    FloatOnlyFunction,
     Unknown,
 }
 
@@ -101,11 +147,17 @@ pub fn describe_wstr(&self) -> &'static wstr {
             ErrorKind::TooManyArgs => wgettext!("Too many arguments"),
             ErrorKind::MissingOperator => wgettext!("Missing operator"),
             ErrorKind::UnexpectedToken => wgettext!("Unexpected token"),
-            ErrorKind::LogicalOperator => {
-                wgettext!("Logical operations are not supported, use `test` instead")
-            }
+            ErrorKind::IntegerOperator => wgettext!("Bitwise operators require --int"),
+            ErrorKind::SingleEquals => wgettext!("Use '==' to compare numbers"),
+            ErrorKind::MissingColon => wgettext!("Missing ':' after '?'"),
             ErrorKind::DivByZero => wgettext!("Division by zero"),
             ErrorKind::NumberTooLarge => wgettext!("Number is too large"),
+            ErrorKind::Overflow => wgettext!("Integer overflow"),
+            ErrorKind::NotAnInteger => wgettext!("Not an integer"),
+            ErrorKind::OutOfRange => wgettext!("Argument is out of range"),
+            ErrorKind::FloatOnlyFunction => {
+                wgettext!("Function is not available in integer mode")
+            }
             ErrorKind::Unknown => wgettext!("Expression is bogus"),
         }
     }
@@ -126,9 +178,38 @@ pub enum Operator {
     Div,
     Pow,
     Rem,
+    // The following are only available in integer mode.
+    Shl,
+    Shr,
+    BitAnd,
+    BitOr,
+    // The following evaluate to 1 or 0.
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
 }
 
 impl Operator {
+    /// Whether the result is a truth value.
+    pub fn is_condition(&self) -> bool {
+        matches!(
+            self,
+            Operator::Eq
+                | Operator::Ne
+                | Operator::Lt
+                | Operator::Le
+                | Operator::Gt
+                | Operator::Ge
+                | Operator::And
+                | Operator::Or
+        )
+    }
+
     pub fn eval(&self, a: f64, b: f64) -> f64 {
         match self {
             Operator::Add => a + b,
@@ -137,27 +218,266 @@ pub fn eval(&self, a: f64, b: f64) -> f64 {
             Operator::Div => a / b,
             Operator::Pow => a.powf(b),
             Operator::Rem => a % b,
+            Operator::Shl | Operator::Shr | Operator::BitAnd | Operator::BitOr => {
+                unreachable!("bitwise operators are only parsed in integer mode")
+            }
+            Operator::Eq => f64::from(a == b),
+            Operator::Ne => f64::from(a != b),
+            Operator::Lt => f64::from(a < b),
+            Operator::Le => f64::from(a <= b),
+            Operator::Gt => f64::from(a > b),
+            Operator::Ge => f64::from(a >= b),
+            Operator::And => f64::from(a != 0.0 && b != 0.0),
+            Operator::Or => f64::from(a != 0.0 || b != 0.0),
+        }
+    }
+
+    pub fn eval_int(&self, a: i64, b: i64) -> Result<i64, ErrorKind> {
+        match self {
+            Operator::Add => a.checked_add(b).ok_or(ErrorKind::Overflow),
+            Operator::Sub => a.checked_sub(b).ok_or(ErrorKind::Overflow),
+            Operator::Mul => a.checked_mul(b).ok_or(ErrorKind::Overflow),
+            Operator::Div if b == 0 => Err(ErrorKind::DivByZero),
+            // Like C, this truncates towards zero.
+            Operator::Div => a.checked_div(b).ok_or(ErrorKind::Overflow),
+            Operator::Rem if b == 0 => Err(ErrorKind::DivByZero),
+            // The only case where this wraps is i64::MIN % -1, which is 0.
+            Operator::Rem => Ok(a.wrapping_rem(b)),
+            Operator::Pow => int_pow(a, b),
+            Operator::Shl => {
+                if b < 0 {
+                    return Err(ErrorKind::OutOfRange);
+                }
+                if a == 0 {
+                    return Ok(0);
+                }
+                let result = a.checked_shl(u32::try_from(b).unwrap_or(u32::MAX));
+                // Shifting out any significant bits (including the sign bit) is an overflow.
+                result
+                    .filter(|&result| result >> b == a)
+                    .ok_or(ErrorKind::Overflow)
+            }
+            Operator::Shr => {
+                if b < 0 {
+                    return Err(ErrorKind::OutOfRange);
+                }
+                Ok(a >> b.min(63))
+            }
+            Operator::BitAnd => Ok(a & b),
+            Operator::BitOr => Ok(a | b),
+            Operator::Eq => Ok(i64::from(a == b)),
+            Operator::Ne => Ok(i64::from(a != b)),
+            Operator::Lt => Ok(i64::from(a < b)),
+            Operator::Le => Ok(i64::from(a <= b)),
+            Operator::Gt => Ok(i64::from(a > b)),
+            Operator::Ge => Ok(i64::from(a >= b)),
+            Operator::And => Ok(i64::from(a != 0 && b != 0)),
+            Operator::Or => Ok(i64::from(a != 0 || b != 0)),
         }
     }
 }
 
+/// A type that expressions can be evaluated in.
+trait Number: Copy + Debug {
+    type Function: Copy + Debug;
+
+    /// The value of a subexpression which failed to evaluate. Like NaN, this is not zero, so that
+    /// dividing by it is not reported as a division by zero.
+    const ERROR: Self;
+
+    /// Whether the bitwise operators (`<<`, `>>`, `&` and `|`) are available.
+    const HAS_BITWISE_OPERATORS: bool;
+
+    /// Parse a number at the start of `chars`, which starts with a digit or ".". Errors are
+    /// positioned relative to the start of `chars`.
+    fn parse(chars: &[char], consumed: &mut usize) -> Result<Self, Error>;
+
+    fn find_function(name: &wstr) -> Result<Self::Function, ErrorKind>;
+    fn arity(f: &Self::Function) -> Option<usize>;
+    fn call(f: &Self::Function, args: &[Self]) -> Result<Self, ErrorKind>;
+
+    fn is_zero(self) -> bool;
+    fn from_bool(b: bool) -> Self;
+    fn negate(self) -> Result<Self, ErrorKind>;
+    fn apply(op: Operator, a: Self, b: Self) -> Result<Self, ErrorKind>;
+}
+
+impl Number for f64 {
+    type Function = Function;
+
+    const ERROR: Self = f64::NAN;
+    const HAS_BITWISE_OPERATORS: bool = false;
+
+    fn parse(chars: &[char], consumed: &mut usize) -> Result<Self, Error> {
+        match wcstod_underscores(chars, consumed) {
+            Ok(num) => Ok(num),
+            Err(wcstodError::InvalidChar) => Err(Error {
+                kind: ErrorKind::Unknown,
+                position: *consumed,
+                len: 1,
+            }),
+            Err(wcstodError::Overflow) => Err(Error {
+                kind: ErrorKind::NumberTooLarge,
+                position: 0,
+                len: *consumed,
+            }),
+            Err(wcstodError::Empty) => {
+                // We have a matches! in get_token, this can't be?
+                unreachable!()
+            }
+        }
+    }
+
+    fn find_function(name: &wstr) -> Result<Function, ErrorKind> {
+        find_builtin(name).ok_or(ErrorKind::UnknownFunction)
+    }
+
+    fn arity(f: &Function) -> Option<usize> {
+        f.arity()
+    }
+
+    fn call(f: &Function, args: &[f64]) -> Result<f64, ErrorKind> {
+        Ok(f.call(args))
+    }
+
+    fn is_zero(self) -> bool {
+        self == 0.0
+    }
+
+    fn from_bool(b: bool) -> f64 {
+        f64::from(b)
+    }
+
+    fn negate(self) -> Result<f64, ErrorKind> {
+        Ok(-self)
+    }
+
+    fn apply(op: Operator, a: f64, b: f64) -> Result<f64, ErrorKind> {
+        Ok(op.eval(a, b))
+    }
+}
+
+impl Number for i64 {
+    type Function = IntFunction;
+
+    const ERROR: Self = 1;
+    const HAS_BITWISE_OPERATORS: bool = true;
+
+    fn parse(chars: &[char], consumed: &mut usize) -> Result<Self, Error> {
+        let (radix, prefix_len) = match chars {
+            ['0', 'x' | 'X', c, ..] if c.is_ascii_hexdigit() || *c == '_' => (16, 2),
+            _ => (10, 0),
+        };
+        let len = prefix_len
+            + chars[prefix_len..]
+                .iter()
+                .take_while(|&&c| c.is_digit(radix) || c == '_')
+                .count();
+
+        // Anything that reads as a longer floating point number (like "1.5" or "1e3") is not an
+        // integer.
+        let mut float_len = 0;
+        if wcstod_underscores(chars, &mut float_len).is_ok() && float_len > len {
+            *consumed = float_len;
+            return Err(Error {
+                kind: ErrorKind::NotAnInteger,
+                position: 0,
+                len: float_len,
+            });
+        }
+        if len == 0 {
+            // A lone ".".
+            *consumed = 1;
+            return Err(Error {
+                kind: ErrorKind::Unknown,
+                position: 0,
+                len: 1,
+            });
+        }
+
+        *consumed = len;
+        if !chars[prefix_len..len].iter().any(|c| c.is_digit(radix)) {
+            // Just "0x_".
+            return Err(Error {
+                kind: ErrorKind::Unknown,
+                position: 0,
+                len,
+            });
+        }
+        chars[prefix_len..len]
+            .iter()
+            .filter_map(|c| c.to_digit(radix))
+            .try_fold(0_i64, |acc, digit| {
+                acc.checked_mul(i64::from(radix))?
+                    .checked_add(i64::from(digit))
+            })
+            .ok_or(Error {
+                kind: ErrorKind::NumberTooLarge,
+                position: 0,
+                len,
+            })
+    }
+
+    fn find_function(name: &wstr) -> Result<IntFunction, ErrorKind> {
+        match INT_BUILTINS.binary_search_by_key(&name, |(name, _expr)| name) {
+            Ok(idx) => Ok(INT_BUILTINS[idx].1),
+            Err(_) if find_builtin(name).is_some() => Err(ErrorKind::FloatOnlyFunction),
+            Err(_) => Err(ErrorKind::UnknownFunction),
+        }
+    }
+
+    fn arity(f: &IntFunction) -> Option<usize> {
+        f.arity()
+    }
+
+    fn call(f: &IntFunction, args: &[i64]) -> Result<i64, ErrorKind> {
+        f.call(args)
+    }
+
+    fn is_zero(self) -> bool {
+        self == 0
+    }
+
+    fn from_bool(b: bool) -> i64 {
+        i64::from(b)
+    }
+
+    fn negate(self) -> Result<i64, ErrorKind> {
+        self.checked_neg().ok_or(ErrorKind::Overflow)
+    }
+
+    fn apply(op: Operator, a: i64, b: i64) -> Result<i64, ErrorKind> {
+        op.eval_int(a, b)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
-enum Token {
+enum Token<N: Number> {
     Error,
     End,
     Sep,
     Open,
     Close,
-    Number(f64),
-    Function(Function),
+    Question,
+    Colon,
+    Not,
+    Number(N),
+    Function(N::Function),
     Infix(Operator),
 }
 
-struct State<'s> {
+struct State<'s, N: Number> {
     start: &'s wstr,
     pos: usize,
-    current: Token,
+    /// Where the current token starts.
+    token_start: usize,
+    current: Token<N>,
     error: Option<Error>,
+    /// Whether we are in an operand which doesn't affect the result, like the untaken branch of
+    /// `?:`. Runtime errors like division by zero are not reported there.
+    dead: bool,
+    /// Whether the last value we computed is a truth value, e.g. the result of a comparison.
+    condition: bool,
 }
 
 fn bitwise_op(a: f64, b: f64, f: fn(u64, u64) -> u64) -> f64 {
@@ -260,6 +580,56 @@ fn npr(n: f64, r: f64) -> f64 {
     ncr(n, r) * fac(r)
 }
 
+fn int_pow(base: i64, exponent: i64) -> Result<i64, ErrorKind> {
+    match (base, exponent) {
+        (1, _) => Ok(1),
+        (-1, _) => Ok(if exponent % 2 == 0 { 1 } else { -1 }),
+        (0, ..=-1) => Err(ErrorKind::DivByZero),
+        (_, ..=-1) => Err(ErrorKind::NotAnInteger),
+        (0, 1..) => Ok(0),
+        _ => u32::try_from(exponent)
+            .ok()
+            .and_then(|exponent| base.checked_pow(exponent))
+            .ok_or(ErrorKind::Overflow),
+    }
+}
+
+fn int_fac(n: i64) -> Result<i64, ErrorKind> {
+    if n < 0 {
+        return Err(ErrorKind::OutOfRange);
+    }
+    // This overflows long before the loop gets expensive.
+    (1..=n)
+        .try_fold(1_i64, |acc, i| acc.checked_mul(i))
+        .ok_or(ErrorKind::Overflow)
+}
+
+fn int_ncr(n: i64, r: i64) -> Result<i64, ErrorKind> {
+    if n < 0 || r < 0 || n < r {
+        return Err(ErrorKind::OutOfRange);
+    }
+    let r = r.min(n - r);
+    // Each intermediate result is itself a binomial coefficient, and they only grow, so we can
+    // stop as soon as one doesn't fit.
+    let mut result = 1_i128;
+    for i in 1..=i128::from(r) {
+        result = result * (i128::from(n - r) + i) / i;
+        if result > i128::from(i64::MAX) {
+            return Err(ErrorKind::Overflow);
+        }
+    }
+    Ok(result as i64)
+}
+
+fn int_npr(n: i64, r: i64) -> Result<i64, ErrorKind> {
+    if n < 0 || r < 0 || n < r {
+        return Err(ErrorKind::OutOfRange);
+    }
+    (n - r + 1..=n)
+        .try_fold(1_i64, |acc, i| acc.checked_mul(i))
+        .ok_or(ErrorKind::Overflow)
+}
+
 const BUILTINS: &[(&wstr, Function)] = &[
     // must be in alphabetical order
     (L!("abs"), Function::Fn1(f64::abs)),
@@ -310,6 +680,32 @@ fn npr(n: f64, r: f64) -> f64 {
 
 assert_sorted_by_name!(BUILTINS, 0);
 
+/// The functions available in integer mode.
+const INT_BUILTINS: &[(&wstr, IntFunction)] = &[
+    // must be in alphabetical order
+    (
+        L!("abs"),
+        IntFunction::Fn1(|n| n.checked_abs().ok_or(ErrorKind::Overflow)),
+    ),
+    (L!("bitand"), IntFunction::Fn2(|a, b| Ok(a & b))),
+    (L!("bitor"), IntFunction::Fn2(|a, b| Ok(a | b))),
+    (L!("bitxor"), IntFunction::Fn2(|a, b| Ok(a ^ b))),
+    (L!("fac"), IntFunction::Fn1(int_fac)),
+    (
+        L!("max"),
+        IntFunction::FnN(|n| n.iter().copied().max().ok_or(ErrorKind::TooFewArgs)),
+    ),
+    (
+        L!("min"),
+        IntFunction::FnN(|n| n.iter().copied().min().ok_or(ErrorKind::TooFewArgs)),
+    ),
+    (L!("ncr"), IntFunction::Fn2(int_ncr)),
+    (L!("npr"), IntFunction::Fn2(int_npr)),
+    (L!("pow"), IntFunction::Fn2(int_pow)),
+];
+
+assert_sorted_by_name!(INT_BUILTINS, 0);
+
 fn find_builtin(name: &wstr) -> Option<Function> {
     let idx = BUILTINS
         .binary_search_by_key(&name, |(name, _expr)| name)
@@ -318,13 +714,38 @@ fn find_builtin(name: &wstr) -> Option<Function> {
     Some(BUILTINS[idx].1)
 }
 
-impl<'s> State<'s> {
+/// Recognize the operators which start with one of `=<>&|!`.
+fn comparison_or_bitwise_operator<N: Number>(chars: &[char]) -> (usize, Token<N>) {
+    let (len, op) = match chars {
+        ['!', '=', ..] => (2, Operator::Ne),
+        ['!', ..] => return (1, Token::Not),
+        ['=', '=', ..] => (2, Operator::Eq),
+        ['=', ..] => return (1, Token::Error),
+        ['<', '=', ..] => (2, Operator::Le),
+        ['>', '=', ..] => (2, Operator::Ge),
+        ['<', '<', ..] => (2, Operator::Shl),
+        ['>', '>', ..] => (2, Operator::Shr),
+        ['&', '&', ..] => (2, Operator::And),
+        ['|', '|', ..] => (2, Operator::Or),
+        ['<', ..] => (1, Operator::Lt),
+        ['>', ..] => (1, Operator::Gt),
+        ['&', ..] => (1, Operator::BitAnd),
+        ['|', ..] => (1, Operator::BitOr),
+        _ => unreachable!(),
+    };
+    (len, Token::Infix(op))
+}
+
+impl<'s, N: Number> State<'s, N> {
     pub fn new(input: &'s wstr) -> Self {
         let mut state = Self {
             start: input,
             pos: 0,
+            token_start: 0,
             current: Token::End,
             error: None,
+            dead: false,
+            condition: false,
         };
         state.next_token();
         state
@@ -346,7 +767,7 @@ pub fn error(&self) -> Result<(), Error> {
         }
     }
 
-    pub fn eval(&mut self) -> f64 {
+    pub fn eval(&mut self) -> N {
         self.expr()
     }
 
@@ -371,10 +792,37 @@ fn no_specific_error(&self) -> bool {
             )
     }
 
+    /// The position and length of the current token, for error reporting.
+    fn token_pos_len(&self) -> (usize, usize) {
+        (self.token_start, self.pos - self.token_start)
+    }
+
+    /// Apply a binary operator, reporting errors at `op_pos_len`.
+    fn binary(&mut self, op: Operator, a: N, b: N, op_pos_len: (usize, usize)) -> N {
+        self.condition = op.is_condition();
+        self.check(N::apply(op, a, b), op_pos_len)
+    }
+
+    /// Call a function, reporting errors at its name.
+    fn call(&mut self, f: &N::Function, args: &[N], name_pos_len: (usize, usize)) -> N {
+        self.condition = false;
+        self.check(N::call(f, args), name_pos_len)
+    }
+
+    /// Take the result of an operation, reporting its error at `pos_len`.
+    fn check(&mut self, result: Result<N, ErrorKind>, pos_len: (usize, usize)) -> N {
+        result.unwrap_or_else(|kind| {
+            if !self.dead && self.no_specific_error() {
+                self.set_error(kind, Some(pos_len));
+            }
+            N::ERROR
+        })
+    }
+
     /// Tries to get the next token from the input. If the input does not contain enough data for
     /// another token, `None` is returned. Otherwise, the number of consumed characters is returned
     /// along with either the token, or `None` in case of ignored (whitespace) input.
-    fn get_token(&mut self) -> Option<(usize, Option<Token>)> {
+    fn get_token(&mut self) -> Option<(usize, Option<Token<N>>)> {
         debug_assert!(!matches!(self.current, Token::Error));
 
         let next = &self.start.as_char_slice().get(self.pos..)?;
@@ -382,20 +830,12 @@ fn get_token(&mut self) -> Option<(usize, Option<Token>)> {
         // Try reading a number.
         if matches!(next.first(), Some('0'..='9') | Some('.')) {
             let mut consumed = 0;
-            match wcstod_underscores(*next, &mut consumed) {
+            match N::parse(next, &mut consumed) {
                 Ok(num) => Some((consumed, Some(Token::Number(num)))),
-                Err(wcstodError::InvalidChar) => {
-                    self.set_error(ErrorKind::Unknown, Some((self.pos + consumed, 1)));
-                    Some((consumed, Some(Token::Error)))
-                }
-                Err(wcstodError::Overflow) => {
-                    self.set_error(ErrorKind::NumberTooLarge, Some((self.pos, consumed)));
+                Err(err) => {
+                    self.set_error(err.kind, Some((self.pos + err.position, err.len)));
                     Some((consumed, Some(Token::Error)))
                 }
-                Err(wcstodError::Empty) => {
-                    // We have a matches! above, this can't be?
-                    unreachable!()
-                }
             }
         } else {
             // Look for a function call.
@@ -412,11 +852,14 @@ fn get_token(&mut self) -> Option<(usize, Option<Token>)> {
                     .unwrap_or(next.len());
 
                 let ident = &next[..ident_len];
-                if let Some(var) = find_builtin(wstr::from_char_slice(ident)) {
-                    return Some((ident_len, Some(Token::Function(var))));
-                } else if self.no_specific_error() {
-                    // Our error is more specific, so it takes precedence.
-                    self.set_error(ErrorKind::UnknownFunction, Some((self.pos, ident_len)));
+                match N::find_function(wstr::from_char_slice(ident)) {
+                    Ok(var) => return Some((ident_len, Some(Token::Function(var)))),
+                    Err(kind) => {
+                        if self.no_specific_error() {
+                            // Our error is more specific, so it takes precedence.
+                            self.set_error(kind, Some((self.pos, ident_len)));
+                        }
+                    }
                 }
 
                 Some((ident_len, Some(Token::Error)))
@@ -433,9 +876,23 @@ fn get_token(&mut self) -> Option<(usize, Option<Token>)> {
                     ')' => Token::Close,
                     ',' => Token::Sep,
                     ' ' | '\t' | '\n' | '\r' => return Some((1, None)),
+                    '?' => Token::Question,
+                    ':' => Token::Colon,
                     '=' | '>' | '<' | '&' | '|' | '!' => {
-                        self.set_error(ErrorKind::LogicalOperator, None);
-                        Token::Error
+                        let (len, tok) = comparison_or_bitwise_operator(next);
+                        match tok {
+                            Token::Error => {
+                                self.set_error(ErrorKind::SingleEquals, Some((self.pos, 1)))
+                            }
+                            Token::Infix(
+                                Operator::Shl | Operator::Shr | Operator::BitAnd | Operator::BitOr,
+                            ) if !N::HAS_BITWISE_OPERATORS => {
+                                self.set_error(ErrorKind::IntegerOperator, Some((self.pos, len)));
+                                return Some((len, Some(Token::Error)));
+                            }
+                            _ => (),
+                        }
+                        return Some((len, Some(tok)));
                     }
                     _ => {
                         self.set_error(ErrorKind::MissingOperator, None);
@@ -451,11 +908,13 @@ fn get_token(&mut self) -> Option<(usize, Option<Token>)> {
     fn next_token(&mut self) {
         self.current = loop {
             let Some((consumed, token)) = self.get_token() else {
+                self.token_start = self.pos;
                 break Token::End;
             };
 
             self.pos += consumed;
             if let Some(token) = token {
+                self.token_start = self.pos - consumed;
                 break token;
             }
         };
@@ -468,7 +927,7 @@ fn next_token(&mut self) {
     ///            <function-X> "(" <expr> {"," <expr>} ")" |
     ///            "(" <list> ")"
     /// ```
-    fn base(&mut self) -> f64 {
+    fn base(&mut self) -> N {
         match self.current {
             Token::Number(n) => {
                 let after_first = self.pos;
@@ -493,9 +952,11 @@ fn base(&mut self) -> f64 {
                     );
                 }
 
+                self.condition = false;
                 n
             }
             Token::Function(f) => {
+                let name_pos_len = self.token_pos_len();
                 self.next_token();
                 let have_open = matches!(self.current, Token::Open);
                 if have_open {
@@ -505,7 +966,7 @@ fn base(&mut self) -> f64 {
                     self.next_token();
                 }
 
-                if f.arity() == Some(0) {
+                if N::arity(&f) == Some(0) {
                     if have_open {
                         if let Token::Close = self.current {
                             self.next_token();
@@ -514,17 +975,14 @@ fn base(&mut self) -> f64 {
                         }
                     }
 
-                    return match f {
-                        Function::Constant(n) => n,
-                        _ => unreachable!("unhandled function type with arity 0"),
-                    };
+                    return self.call(&f, &[], name_pos_len);
                 }
 
                 let mut parameters = vec![];
                 let mut i = 0;
                 let mut first_err = None;
                 for j in 0.. {
-                    if f.arity() == Some(j) {
+                    if N::arity(&f) == Some(j) {
                         first_err = Some(self.pos - 1);
                     }
                     parameters.push(self.expr());
@@ -535,14 +993,14 @@ fn base(&mut self) -> f64 {
                     i += 1;
                 }
 
-                if f.arity().is_none() || f.arity() == Some(i + 1) {
+                if N::arity(&f).is_none() || N::arity(&f) == Some(i + 1) {
                     if !have_open {
-                        return f.call(&parameters);
+                        return self.call(&f, &parameters, name_pos_len);
                     }
                     if let Token::Close = self.current {
                         // We have an opening and a closing paren, consume the closing one and done.
                         self.next_token();
-                        return f.call(&parameters);
+                        return self.call(&f, &parameters, name_pos_len);
                     }
                     if !matches!(self.current, Token::Error) {
                         // If we had the right number of arguments, we're missing a closing paren.
@@ -563,7 +1021,7 @@ fn base(&mut self) -> f64 {
                     // a closing parenthesis should be more obvious.
                     //
                     // Vararg functions need at least one argument.
-                    let err = if f.arity().map_or(i == 0, |arity| i < arity) {
+                    let err = if N::arity(&f).map_or(i == 0, |arity| i < arity) {
                         ErrorKind::TooFewArgs
                     } else {
                         ErrorKind::TooManyArgs
@@ -587,7 +1045,7 @@ fn base(&mut self) -> f64 {
                     self.set_error(err, err_pos_len);
                 }
 
-                f64::NAN
+                N::ERROR
             }
             Token::Open => {
                 self.next_token();
@@ -603,7 +1061,7 @@ fn base(&mut self) -> f64 {
                     self.set_error(ErrorKind::MissingClosingParen, None);
                 }
 
-                f64::NAN
+                N::ERROR
             }
             Token::End => {
                 // The expression ended before we expected it.
@@ -613,85 +1071,243 @@ fn base(&mut self) -> f64 {
                 // "too few args".
                 self.set_error(ErrorKind::TooFewArgs, None);
 
-                f64::NAN
+                N::ERROR
             }
 
-            Token::Error | Token::Sep | Token::Close | Token::Infix(_) => {
+            Token::Error
+            | Token::Sep
+            | Token::Close
+            | Token::Question
+            | Token::Colon
+            | Token::Not
+            | Token::Infix(_) => {
                 if self.no_specific_error() {
                     self.set_error(ErrorKind::UnexpectedToken, None);
                 }
 
-                f64::NAN
+                N::ERROR
             }
         }
     }
 
-    /// \<power\>  = {("-" | "+")} \<base\>
-    fn power(&mut self) -> f64 {
-        let mut sign = 1.0;
-        while let Token::Infix(op) = self.current {
-            if op == Operator::Sub {
-                sign = -sign;
-                self.next_token();
-            } else if op == Operator::Add {
-                self.next_token();
-            } else {
-                break;
+    /// \<power\>  = {("-" | "+" | "!")} \<base\>
+    fn power(&mut self) -> N {
+        let mut prefixes = vec![];
+        loop {
+            match self.current {
+                Token::Infix(Operator::Add) => (),
+                Token::Infix(Operator::Sub) | Token::Not => {
+                    prefixes.push((self.current, self.token_pos_len()));
+                }
+                _ => break,
             }
+            self.next_token();
         }
 
-        sign * self.base()
+        let mut ret = self.base();
+        for (prefix, pos_len) in prefixes.into_iter().rev() {
+            if let Token::Not = prefix {
+                self.condition = true;
+                ret = N::from_bool(ret.is_zero());
+            } else {
+                self.condition = false;
+                ret = self.check(ret.negate(), pos_len);
+            }
+        }
+        ret
     }
 
     /// \<factor\> = \<power\> {"^" \<power\>}
-    fn factor(&mut self) -> f64 {
+    fn factor(&mut self) -> N {
         let mut ret = self.power();
 
         if let Token::Infix(Operator::Pow) = self.current {
+            let op_pos_len = self.token_pos_len();
             self.next_token();
-            ret = ret.powf(self.factor());
+            let ret2 = self.factor();
+            ret = self.binary(Operator::Pow, ret, ret2, op_pos_len);
         }
 
         ret
     }
 
     /// \<term\>   = \<factor\> {("*" | "/" | "%") \<factor\>}
-    fn term(&mut self) -> f64 {
+    fn term(&mut self) -> N {
         let mut ret = self.factor();
         while let Token::Infix(op @ (Operator::Mul | Operator::Div | Operator::Rem)) = self.current
         {
-            let op_pos = self.pos - 1;
+            let op_pos_len = self.token_pos_len();
             self.next_token();
             let ret2 = self.factor();
-            if ret2 == 0.0 && [Operator::Div, Operator::Rem].contains(&op) {
+            if !self.dead && ret2.is_zero() && [Operator::Div, Operator::Rem].contains(&op) {
                 // Division by zero (also for modulo)
                 // Error position is the "/" or "%" sign for now
-                self.set_error(ErrorKind::DivByZero, Some((op_pos, 1)));
+                self.set_error(ErrorKind::DivByZero, Some(op_pos_len));
+                return N::ERROR;
             }
-            ret = op.eval(ret, ret2);
+            ret = self.binary(op, ret, ret2, op_pos_len);
         }
 
         ret
     }
 
-    /// \<expr\>   = \<term\> {("+" | "-") \<term\>}
-    fn expr(&mut self) -> f64 {
+    /// \<sum\>    = \<term\> {("+" | "-") \<term\>}
+    fn sum(&mut self) -> N {
         let mut ret = self.term();
         while let Token::Infix(op @ (Operator::Add | Operator::Sub)) = self.current {
+            let op_pos_len = self.token_pos_len();
             self.next_token();
-            ret = op.eval(ret, self.term());
+            let ret2 = self.term();
+            ret = self.binary(op, ret, ret2, op_pos_len);
         }
 
         ret
     }
+
+    /// \<shift\>  = \<sum\> {("<<" | ">>") \<sum\>}
+    fn shift(&mut self) -> N {
+        let mut ret = self.sum();
+        while let Token::Infix(op @ (Operator::Shl | Operator::Shr)) = self.current {
+            let op_pos_len = self.token_pos_len();
+            self.next_token();
+            let ret2 = self.sum();
+            ret = self.binary(op, ret, ret2, op_pos_len);
+        }
+
+        ret
+    }
+
+    /// \<bitand\> = \<shift\> {"&" \<shift\>}
+    fn bitand(&mut self) -> N {
+        let mut ret = self.shift();
+        while let Token::Infix(Operator::BitAnd) = self.current {
+            let op_pos_len = self.token_pos_len();
+            self.next_token();
+            let ret2 = self.shift();
+            ret = self.binary(Operator::BitAnd, ret, ret2, op_pos_len);
+        }
+
+        ret
+    }
+
+    /// \<bitor\>  = \<bitand\> {"|" \<bitand\>}
+    fn bitor(&mut self) -> N {
+        let mut ret = self.bitand();
+        while let Token::Infix(Operator::BitOr) = self.current {
+            let op_pos_len = self.token_pos_len();
+            self.next_token();
+            let ret2 = self.bitand();
+            ret = self.binary(Operator::BitOr, ret, ret2, op_pos_len);
+        }
+
+        ret
+    }
+
+    /// \<comparison\> = \<bitor\> {("==" | "!=" | "<" | "<=" | ">" | ">=") \<bitor\>}
+    fn comparison(&mut self) -> N {
+        let mut ret = self.bitor();
+        while let Token::Infix(
+            op @ (Operator::Eq
+            | Operator::Ne
+            | Operator::Lt
+            | Operator::Le
+            | Operator::Gt
+            | Operator::Ge),
+        ) = self.current
+        {
+            let op_pos_len = self.token_pos_len();
+            self.next_token();
+            let ret2 = self.bitor();
+            ret = self.binary(op, ret, ret2, op_pos_len);
+        }
+
+        ret
+    }
+
+    /// Parse the right operand of `&&` or `||`, which only matters if `needed`.
+    fn logical_operand(&mut self, needed: bool, operand: fn(&mut Self) -> N) -> N {
+        let outer_dead = self.dead;
+        self.dead = outer_dead || !needed;
+        let ret = operand(self);
+        self.dead = outer_dead;
+        ret
+    }
+
+    /// \<and\>    = \<comparison\> {"&&" \<comparison\>}
+    fn and(&mut self) -> N {
+        let mut ret = self.comparison();
+        while let Token::Infix(Operator::And) = self.current {
+            let op_pos_len = self.token_pos_len();
+            self.next_token();
+            let ret2 = self.logical_operand(!ret.is_zero(), Self::comparison);
+            ret = self.binary(Operator::And, ret, ret2, op_pos_len);
+        }
+
+        ret
+    }
+
+    /// \<or\>     = \<and\> {"||" \<and\>}
+    fn or(&mut self) -> N {
+        let mut ret = self.and();
+        while let Token::Infix(Operator::Or) = self.current {
+            let op_pos_len = self.token_pos_len();
+            self.next_token();
+            let ret2 = self.logical_operand(ret.is_zero(), Self::and);
+            ret = self.binary(Operator::Or, ret, ret2, op_pos_len);
+        }
+
+        ret
+    }
+
+    /// \<expr\>   = \<or\> ["?" \<expr\> ":" \<expr\>]
+    fn expr(&mut self) -> N {
+        let ret = self.or();
+        let Token::Question = self.current else {
+            return ret;
+        };
+        self.next_token();
+        let truthy = !ret.is_zero();
+        let if_true = self.logical_operand(truthy, Self::expr);
+        let Token::Colon = self.current else {
+            if self.no_specific_error() {
+                self.set_error(ErrorKind::MissingColon, None);
+            }
+            return N::ERROR;
+        };
+        self.next_token();
+        let if_false = self.logical_operand(!truthy, Self::expr);
+        self.condition = false;
+        if truthy { if_true } else { if_false }
+    }
 }
 
-pub fn te_interp(expression: &wstr) -> Result<f64, Error> {
-    let mut s = State::new(expression);
-    let ret = s.eval();
+/// The result of evaluating an expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Evaluation<N> {
+    pub value: N,
+    /// Whether the outermost operation is a comparison or logical operator, so the value is a
+    /// truth value.
+    pub is_condition: bool,
+}
+
+fn interp<N: Number>(expression: &wstr) -> Result<Evaluation<N>, Error> {
+    let mut s = State::<N>::new(expression);
+    let value = s.eval();
 
     match s.error() {
-        Ok(()) => Ok(ret),
+        Ok(()) => Ok(Evaluation {
+            value,
+            is_condition: s.condition,
+        }),
         Err(e) => Err(e),
     }
 }
+
+pub fn te_interp(expression: &wstr) -> Result<Evaluation<f64>, Error> {
+    interp(expression)
+}
+
+/// Evaluate an expression using exact 64-bit integer arithmetic. Overflow is an error.
+pub fn te_interp_int(expression: &wstr) -> Result<Evaluation<i64>, Error> {
+    interp(expression)
+}
@@ -3,8 +3,9 @@
 
 use crate::ast::unescape_keyword;
 use crate::common::valid_var_name_char;
+use crate::common::{UnescapeStringStyle, unescape_string};
 use crate::future_feature_flags::{FeatureFlag, feature_test};
-use crate::parse_constants::SOURCE_OFFSET_INVALID;
+use crate::parse_constants::{SOURCE_OFFSET_INVALID, SourceRange};
 use crate::parser_keywords::parser_keywords_is_subcommand;
 use crate::prelude::*;
 use crate::redirection::RedirectionMode;
@@ -56,6 +57,7 @@ pub enum TokenizerError {
     UnterminatedBrace,
     ExpectedPcloseFoundBclose,
     ExpectedBcloseFoundPclose,
+    UnterminatedHeredoc,
 }
 
 #[derive(Debug)]
@@ -184,6 +186,9 @@ fn from(err: TokenizerError) -> Self {
             TokenizerError::ExpectedBcloseFoundPclose => {
                 wgettext!("Unexpected ')' found, expecting '}'")
             }
+            TokenizerError::UnterminatedHeredoc => {
+                wgettext!("Unexpected end of string, heredoc is not terminated")
+            }
         }
     }
 }
@@ -246,6 +251,26 @@ pub fn set_error_length(&mut self, value: usize) {
     }
 }
 
+/// A heredoc whose body has been read by the tokenizer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Heredoc {
+    /// The delimiter token following the `<<`, like `EOF` or `'EOF'`.
+    pub delimiter: SourceRange,
+    /// The lines making up the body, including the final newline.
+    pub body: SourceRange,
+    /// The line that closes the body, without its newline.
+    pub terminator: SourceRange,
+    /// Whether the body is subject to expansion. This is the case unless the delimiter is quoted.
+    pub expand: bool,
+}
+
+/// A heredoc whose body starts after the next newline.
+struct PendingHeredoc {
+    delimiter_range: SourceRange,
+    delimiter: WString,
+    expand: bool,
+}
+
 struct BraceStatementParser {
     at_command_position: bool,
     unclosed_brace_statements: usize,
@@ -273,6 +298,12 @@ pub struct Tokenizer<'c> {
     continue_line_after_comment: bool,
     /// Called on every quote change.
     on_quote_toggle: Option<&'c mut dyn FnMut(usize)>,
+    /// Whether the next string is the delimiter of a heredoc.
+    expecting_heredoc_delimiter: bool,
+    /// Heredocs on the current line, whose bodies are read at the next newline.
+    pending_heredocs: Vec<PendingHeredoc>,
+    /// The heredocs whose bodies were read so far.
+    heredocs: Vec<Heredoc>,
 }
 
 impl<'c> Tokenizer<'c> {
@@ -314,8 +345,16 @@ fn new_impl(
             continue_after_error: flags & TOK_CONTINUE_AFTER_ERROR,
             continue_line_after_comment: false,
             on_quote_toggle,
+            expecting_heredoc_delimiter: false,
+            pending_heredocs: vec![],
+            heredocs: vec![],
         }
     }
+
+    /// Return the heredocs whose bodies were read so far, sorted by offset.
+    pub fn take_heredocs(&mut self) -> Vec<Heredoc> {
+        std::mem::take(&mut self.heredocs)
+    }
 }
 
 impl<'c> Iterator for Tokenizer<'c> {
@@ -382,6 +421,10 @@ fn next(&mut self) -> Option<Self::Item> {
         let mut at_cmd_pos = false;
         let token = match this_char {
             '\0'=> {
+                if !self.pending_heredocs.is_empty() {
+                    return Some(self.call_error(TokenizerError::UnterminatedHeredoc,
+                                                start_pos, start_pos, None, 0));
+                }
                 self.has_next = false;
                 None
             }
@@ -393,6 +436,11 @@ fn next(&mut self) -> Option<Self::Item> {
                 result.length = 1;
                 self.token_cursor += 1;
                 at_cmd_pos = true;
+                // Heredoc bodies start after the newline that ends the line of their redirection.
+                if this_char == '\n' && !self.read_heredoc_bodies() {
+                    return Some(self.call_error(TokenizerError::UnterminatedHeredoc,
+                                                start_pos, start_pos, None, 1));
+                }
                 // Hack: when we get a newline, swallow as many as we can. This compresses multiple
                 // subsequent newlines into a single one.
                 if !self.show_blank_lines {
@@ -488,6 +536,11 @@ fn next(&mut self) -> Option<Self::Item> {
                     Some(result)
                 }
             }
+            '>' | '<' if next_char == Some('(')
+                && feature_test(FeatureFlag::ProcessSubstitution) => {
+                // A process substitution like <(cmd), which is just a string.
+                Some(self.read_string())
+            }
             '>'| '<' => {
                 // There's some duplication with the code in the default case below. The key
                 // difference here is that we must never parse these as a string; a failed
@@ -561,6 +614,9 @@ fn next(&mut self) -> Option<Self::Item> {
         if let Some(parser) = self.brace_statement_parser.as_mut() {
             parser.at_command_position = at_cmd_pos;
         }
+        if let Some(tok) = &token {
+            self.note_heredoc_delimiter(tok);
+        }
         token
     }
 }
@@ -576,6 +632,65 @@ fn iswspace_not_nl(c: char) -> bool {
 }
 
 impl<'c> Tokenizer<'c> {
+    /// Remember the delimiter of a heredoc, which is the string after a `<<` redirection.
+    fn note_heredoc_delimiter(&mut self, tok: &Tok) {
+        if std::mem::take(&mut self.expecting_heredoc_delimiter) {
+            if tok.type_ == TokenType::String {
+                let text = self.text_of(tok);
+                // Any quoting in the delimiter makes the body literal, like in POSIX shells.
+                let expand = !text.chars().any(|c| matches!(c, '\'' | '"' | '\\'));
+                let delimiter = unescape_string(text, UnescapeStringStyle::default())
+                    .unwrap_or_else(|| text.to_owned());
+                self.pending_heredocs.push(PendingHeredoc {
+                    delimiter_range: SourceRange::new(tok.offset(), tok.length()),
+                    delimiter,
+                    expand,
+                });
+            }
+        } else if tok.type_ == TokenType::Redirect {
+            self.expecting_heredoc_delimiter = PipeOrRedir::try_from(self.text_of(tok))
+                .is_ok_and(|redir| redir.mode == RedirectionMode::HereDoc);
+        }
+    }
+
+    /// Read the bodies of the pending heredocs, which start at the cursor. Each body extends up to
+    /// a line consisting of just its delimiter. Return false if a delimiter line is missing, in
+    /// which case the rest of the input is taken as the body.
+    fn read_heredoc_bodies(&mut self) -> bool {
+        let len = self.start.len();
+        for pending in std::mem::take(&mut self.pending_heredocs) {
+            let body_start = self.token_cursor;
+            loop {
+                let line_start = self.token_cursor;
+                let line_end = self.start[line_start..]
+                    .chars()
+                    .position(|c| c == '\n')
+                    .map_or(len, |pos| line_start + pos);
+                let is_terminator = self.start[line_start..line_end] == pending.delimiter;
+                if is_terminator || line_end == len {
+                    let (body_end, terminator) = if is_terminator {
+                        (line_start, line_start..line_end)
+                    } else {
+                        (len, len..len)
+                    };
+                    self.heredocs.push(Heredoc {
+                        delimiter: pending.delimiter_range,
+                        body: SourceRange::new(body_start, body_end - body_start),
+                        terminator: SourceRange::new(terminator.start, terminator.len()),
+                        expand: pending.expand,
+                    });
+                    self.token_cursor = (line_end + 1).min(len);
+                    if !is_terminator {
+                        return false;
+                    }
+                    break;
+                }
+                self.token_cursor = line_end + 1;
+            }
+        }
+        true
+    }
+
     /// Returns the text of a token, as a string.
     pub fn text_of(&self, tok: &Tok) -> &wstr {
         tok.get_source(self.start)
@@ -675,6 +790,9 @@ fn process_opening_quote(
             // has been explicitly ignored (escaped).
             else if c == '\\' {
                 mode |= TOK_MODE_CHAR_ESCAPE;
+            } else if (c == '<' || c == '>') && self.token_cursor == buff_start {
+                // The start of a process substitution; the tokenizer only calls us for these if
+                // they are followed by a '('.
             } else if c == '#' && is_token_begin {
                 self.token_cursor = comment_end(self.start, self.token_cursor) - 1;
             } else if c == '(' {
@@ -975,6 +1093,8 @@ impl TryFrom<&wstr> for PipeOrRedir {
     ///     cmd 1>&2         fd redirection with an explicit src fd
     ///     cmd <&2          fd redirection with no explicit src fd (stdin is used)
     ///     cmd 3<&0         fd redirection with an explicit src fd
+    ///     cmd <<< text     here-string
+    ///     cmd <<EOF        heredoc, the body follows on the next lines
     ///     cmd &> file      redirection with stderr merge
     ///     cmd ^ file       caret (stderr) redirection, perhaps disabled via feature flags
     ///     cmd ^^ file      caret (stderr) redirection, perhaps disabled via feature flags
@@ -1073,7 +1193,13 @@ fn try_from(buff: &wstr) -> Result<PipeOrRedir, ()> {
             }
             '<' => {
                 consume(&mut cursor, '<');
-                if try_consume(&mut cursor, '&') {
+                if try_consume(&mut cursor, '<') {
+                    result.mode = if try_consume(&mut cursor, '<') {
+                        RedirectionMode::HereString // like <<< text
+                    } else {
+                        RedirectionMode::HereDoc // like <<EOF
+                    };
+                } else if try_consume(&mut cursor, '&') {
                     result.mode = RedirectionMode::Fd;
                 } else if try_consume(&mut cursor, '?') {
                     // <? foo try-input redirection (uses /dev/null if file can't be used).
@@ -1191,6 +1317,8 @@ pub fn variable_assignment_equals_pos(txt: &wstr) -> Option<usize> {
 #[cfg(test)]
 mod tests {
     use super::{PipeOrRedir, TokFlags, TokenType, Tokenizer, TokenizerError};
+    use crate::future_feature_flags::{FeatureFlag, scoped_test};
+    use crate::parse_constants::SourceRange;
     use crate::prelude::*;
     use crate::redirection::RedirectionMode;
     use libc::{STDERR_FILENO, STDOUT_FILENO};
@@ -1332,6 +1460,62 @@ fn test_tokenizer() {
             assert_eq!(token.error_offset_within_token, 4);
         }
 
+        scoped_test(FeatureFlag::ProcessSubstitution, true, || {
+            let s = L!("diff <(echo a) >(cat) <file 2>(echo err)");
+            let mut t = Tokenizer::new(s, TokFlags(0));
+            let mut next = || {
+                let token = t.next().unwrap();
+                (token.type_, t.text_of(&token).to_owned())
+            };
+            assert_eq!(next(), (TokenType::String, L!("diff").to_owned()));
+            assert_eq!(next(), (TokenType::String, L!("<(echo a)").to_owned()));
+            assert_eq!(next(), (TokenType::String, L!(">(cat)").to_owned()));
+            assert_eq!(next(), (TokenType::Redirect, L!("<").to_owned()));
+            assert_eq!(next(), (TokenType::String, L!("file").to_owned()));
+            assert_eq!(next(), (TokenType::Redirect, L!("2>").to_owned()));
+            assert_eq!(next(), (TokenType::String, L!("(echo err)").to_owned()));
+        });
+
+        {
+            let s = L!("cat <<A <<'B'; echo\na\nA\nb\nB\necho done");
+            let mut t = Tokenizer::new(s, TokFlags(0));
+            let mut next = || {
+                let token = t.next().unwrap();
+                (token.type_, t.text_of(&token).to_owned())
+            };
+            assert_eq!(next(), (TokenType::String, L!("cat").to_owned()));
+            assert_eq!(next(), (TokenType::Redirect, L!("<<").to_owned()));
+            assert_eq!(next(), (TokenType::String, L!("A").to_owned()));
+            assert_eq!(next(), (TokenType::Redirect, L!("<<").to_owned()));
+            assert_eq!(next(), (TokenType::String, L!("'B'").to_owned()));
+            assert_eq!(next(), (TokenType::End, L!(";").to_owned()));
+            assert_eq!(next(), (TokenType::String, L!("echo").to_owned()));
+            assert_eq!(next(), (TokenType::End, L!("\n").to_owned()));
+            assert_eq!(next(), (TokenType::String, L!("echo").to_owned()));
+            assert_eq!(next(), (TokenType::String, L!("done").to_owned()));
+            assert!(t.next().is_none());
+
+            let heredocs = t.take_heredocs();
+            let text = |r: SourceRange| &s[r.as_usize()];
+            assert_eq!(heredocs.len(), 2);
+            assert_eq!(text(heredocs[0].delimiter), L!("A"));
+            assert_eq!(text(heredocs[0].body), L!("a\n"));
+            assert_eq!(text(heredocs[0].terminator), L!("A"));
+            assert!(heredocs[0].expand);
+            assert_eq!(text(heredocs[1].delimiter), L!("'B'"));
+            assert_eq!(text(heredocs[1].body), L!("b\n"));
+            assert_eq!(text(heredocs[1].terminator), L!("B"));
+            assert!(!heredocs[1].expand);
+        }
+
+        {
+            let mut t = Tokenizer::new(L!("cat <<EOF\nbody\nEO"), TokFlags(0));
+            let token = t.nth(3).unwrap();
+            assert_eq!(token.type_, TokenType::Error);
+            assert_eq!(token.error, TokenizerError::UnterminatedHeredoc);
+            assert!(t.next().is_none());
+        }
+
         // Test some redirection parsing.
         macro_rules! pipe_or_redir {
             ($s:literal) => {
@@ -1380,5 +1564,8 @@ macro_rules! get_redir_mode {
         assert_eq!(get_redir_mode!("2>&3"), RedirectionMode::Fd);
         assert_eq!(get_redir_mode!("3<&0"), RedirectionMode::Fd);
         assert_eq!(get_redir_mode!("3</tmp/filetxt"), RedirectionMode::Input);
+        assert_eq!(get_redir_mode!("<<EOF"), RedirectionMode::HereDoc);
+        assert_eq!(get_redir_mode!("3<<EOF"), RedirectionMode::HereDoc);
+        assert_eq!(get_redir_mode!("<<<"), RedirectionMode::HereString);
     }
 }
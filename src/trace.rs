@@ -1,13 +1,30 @@
+use crate::expand::replace_home_directory_with_tilde;
 use crate::flog::log_extra_to_flog_file;
 use crate::parser::Parser;
 use crate::{common::escape, global_safety::RelaxedAtomicBool, prelude::*};
 
 static DO_TRACE: RelaxedAtomicBool = RelaxedAtomicBool::new(false);
 static DO_TRACE_ALL: RelaxedAtomicBool = RelaxedAtomicBool::new(false);
+static DO_TRACE_LOCATION: RelaxedAtomicBool = RelaxedAtomicBool::new(false);
 
 pub fn trace_set_enabled(enable: Vec<WString>) {
     DO_TRACE.store(!enable.is_empty());
     DO_TRACE_ALL.store(enable.iter().any(|s| s == "all"));
+    DO_TRACE_LOCATION.store(enable.iter().any(|s| s == "location"));
+}
+
+/// Return where the parser is executing, like "~/file.fish:12: function: ".
+fn trace_location(parser: &Parser) -> WString {
+    let filename = match parser.current_filename() {
+        Some(filename) => replace_home_directory_with_tilde(filename.as_utfstr(), parser.vars()),
+        None => L!("-").to_owned(),
+    };
+    let mut location = sprintf!("%s:%d: ", filename, parser.get_lineno_for_display());
+    if let Some(function) = parser.get_function_name(1) {
+        location.push_utfstr(&function);
+        location.push_str(": ");
+    }
+    location
 }
 
 /// return whether tracing is enabled.
@@ -25,8 +42,13 @@ pub fn trace_enabled(parser: &Parser) -> bool {
 // Allow the `&Vec` parameter as this function only exists temporarily for the FFI
 pub fn trace_argv<S: AsRef<wstr>>(parser: &Parser, command: &wstr, args: &[S]) {
     // Format into a string to prevent interleaving with flog in other threads.
-    // Add the + prefix.
-    let mut trace_text = L!("-").repeat(parser.blocks_size() - 1);
+    // Add the location if requested, and the + prefix.
+    let mut trace_text = if DO_TRACE_LOCATION.load() {
+        trace_location(parser)
+    } else {
+        WString::new()
+    };
+    trace_text.push_utfstr(&L!("-").repeat(parser.blocks_size() - 1));
     trace_text.push('>');
 
     if !command.is_empty() {
@@ -20,6 +20,7 @@
 use std::fs::{self, canonicalize};
 use std::io::{self, Write as _};
 use std::os::unix::prelude::*;
+use std::time::Duration;
 
 pub use crate::wutil::printf::{eprintf, fprintf, printf, sprintf};
 
@@ -455,6 +456,22 @@ pub fn wstr_offset_in(cursor: &wstr, base: &wstr) -> usize {
     offset as usize
 }
 
+/// Parses a duration such as `1.5`, `250ms`, `30s`, `5m`, `2h` or `1d`.
+/// A number without a unit is a number of seconds.
+pub fn parse_duration(arg: &wstr) -> Option<Duration> {
+    let mut consumed = 0;
+    let value = wcstod::wcstod(arg, '.', &mut consumed).ok()?;
+    let seconds_per_unit = match arg.slice_from(consumed) {
+        unit if unit.is_empty() || unit == "s" => 1.0,
+        unit if unit == "ms" => 0.001,
+        unit if unit == "m" => 60.0,
+        unit if unit == "h" => 60.0 * 60.0,
+        unit if unit == "d" => 24.0 * 60.0 * 60.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(value * seconds_per_unit).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
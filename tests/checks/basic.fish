@@ -483,6 +483,15 @@ try_unbalanced_block 'function anything'
 #CHECKERR: - (line 1): Missing end to balance this function definition
 try_unbalanced_block 'if false'
 #CHECKERR: - (line 1): Missing end to balance this if statement
+$fish --features try-catch -c "echo try | source " 2>&1 | grep "Missing end" 1>&2
+#CHECKERR: - (line 1): Missing end to balance this try block
+# Without the feature, try and catch are ordinary commands.
+function try
+    echo try: $argv
+end
+try catch
+#CHECK: try: catch
+functions -e try
 
 # Ensure that quoted keywords work
 'while' false; end
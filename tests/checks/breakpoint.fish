@@ -0,0 +1,63 @@
+#RUN: %fish %s
+
+breakpoint add greet:3
+breakpoint add --condition 'test "$name" = world' greet:2
+breakpoint add -c true conf.d/foo.fish:10
+breakpoint list
+# CHECK: 1 greet:3
+# CHECK: 2 greet:2 --condition 'test "$name" = world'
+# CHECK: 3 conf.d/foo.fish:10
+
+breakpoint erase 2 5
+# CHECKERR: breakpoint: 5: no such breakpoint
+echo $status
+# CHECK: 1
+breakpoint list
+# CHECK: 1 greet:3
+# CHECK: 3 conf.d/foo.fish:10
+
+# Breakpoints only stop interactive shells.
+function greet
+    echo hello
+end
+greet
+# CHECK: hello
+
+breakpoint erase --all
+breakpoint list
+breakpoint add
+# CHECKERR: breakpoint: add: expected 1 arguments; got 0
+breakpoint add greet
+# CHECKERR: breakpoint: greet: invalid location, expected FUNCTION:LINE or FILE:LINE
+breakpoint add greet:0
+# CHECKERR: breakpoint: greet:0: invalid location, expected FUNCTION:LINE or FILE:LINE
+breakpoint erase foo
+# CHECKERR: breakpoint: foo: invalid integer
+
+breakpoint step
+# CHECKERR: breakpoint: step: only valid at the debug prompt
+breakpoint frobnicate
+# CHECKERR: breakpoint: frobnicate: invalid subcommand
+# CHECKERR: {{.*}}checks/breakpoint.fish (line {{\d+}}):
+# CHECKERR: breakpoint frobnicate
+# CHECKERR: ^
+# CHECKERR: (Type 'help breakpoint' for related documentation)
+
+function show_locals
+    set -l x 1 2
+    set -l y 'a b'
+    breakpoint locals
+    breakpoint locals 1
+    breakpoint locals 5
+end
+function caller
+    set -l z 3
+    show_locals
+end
+caller
+# CHECK: argv
+# CHECK: x '1' '2'
+# CHECK: y 'a b'
+# CHECK: argv
+# CHECK: z 3
+# CHECKERR: breakpoint: 5: no such frame
@@ -691,3 +691,91 @@ complete command-line-aware-completions -xa "(commandline --cursor; commandline
 complete -C"command-line-aware-completions "
 # CHECK: 31
 # CHECK: command-line-aware-completions
+
+# Arguments can be cached.
+set -g cached_calls 0
+function list-cached
+    set -g cached_calls (math $cached_calls + 1)
+    echo cached$cached_calls
+end
+complete -c cachedcmd -xa '(list-cached)'
+complete -c cachedcmd --cache-ttl 1h
+complete cachedcmd
+# CHECK: complete --exclusive cachedcmd -a '(list-cached)'
+# CHECK: complete cachedcmd --cache-ttl 3600s
+complete -C 'cachedcmd '
+# CHECK: cached1
+# Typing more of the token uses the cache.
+complete -C 'cachedcmd cach'
+# CHECK: cached1
+# Other arguments before it do not.
+complete -C 'cachedcmd foo '
+# CHECK: cached2
+complete -C 'cachedcmd '
+# CHECK: cached1
+# Neither does another directory.
+begin
+    cd /
+    complete -C 'cachedcmd '
+    cd -
+end
+# CHECK: cached3
+# Changing $fish_complete_path drops the cache.
+set -g fish_complete_path $fish_complete_path
+complete -C 'cachedcmd '
+# CHECK: cached4
+complete -c cachedcmd --cache-ttl 0
+complete cachedcmd
+# CHECK: complete --exclusive cachedcmd -a '(list-cached)'
+complete -C 'cachedcmd '
+# CHECK: cached5
+complete -C 'cachedcmd '
+# CHECK: cached6
+complete -c cachedcmd --cache-ttl 1.5s
+complete cachedcmd
+# CHECK: complete --exclusive cachedcmd -a '(list-cached)'
+# CHECK: complete cachedcmd --cache-ttl 1500ms
+complete -c cachedcmd --cache-ttl soon
+# CHECKERR: complete: 'soon' is not a valid duration
+
+# Completions can be generated from --help output.
+function helptool
+    if contains -- --help $argv
+        printf '%s\n' 'Usage: helptool [OPTIONS] <COMMAND>' '' 'Commands:' \
+            '  build  Compile the project' '  serve  Start the server' '' 'Options:' \
+            '  -v, --verbose      Be verbose' \
+            '      --mode <MODE>  How to build [possible values: debug, release]'
+    end
+end
+complete -c helptool --from-help
+complete helptool
+# CHECK: complete helptool --from-help
+complete -C 'helptool '
+# CHECK: build{{\t}}Compile the project
+# CHECK: serve{{\t}}Start the server
+complete -C 'helptool --v'
+# CHECK: --verbose{{\t}}Be verbose
+complete -C 'helptool --mode '
+# CHECK: debug{{\t}}How to build
+# CHECK: release{{\t}}How to build
+complete helptool
+# CHECK: complete --no-files helptool -d 'Start the server' -a serve -n __fish_use_subcommand
+# CHECK: complete --no-files helptool -d 'Compile the project' -a build -n __fish_use_subcommand
+# CHECK: complete --exclusive helptool -l mode -d 'How to build' -a 'debug release'
+# CHECK: complete helptool -l verbose -d 'Be verbose'
+# CHECK: complete helptool -s v -d 'Be verbose'
+# CHECK: complete helptool --from-help
+complete -c helptool -e
+complete helptool
+complete -p /bin/helptool --from-help
+# CHECKERR: complete: invalid option combination, '--from-help' only works with commands
+
+# A --help that takes too long is stopped, and adds nothing.
+function slowhelp
+    sleep 5
+    echo '      --never  Not reached'
+end
+complete -c slowhelp --from-help
+complete -C 'slowhelp --'
+complete slowhelp
+# CHECK: complete slowhelp --from-help
@@ -0,0 +1,116 @@
+#RUN: %fish --features try-catch %s
+
+function f
+    defer echo first registered
+    defer echo second registered
+    echo body
+end
+f
+# CHECK: body
+# CHECK: second registered
+# CHECK: first registered
+
+# Arguments are expanded when deferring, and deferred commands see the block's variables.
+function g
+    set -l x before
+    defer echo x was $x
+    defer eval 'echo x is $x'
+    set x after
+end
+g
+# CHECK: x is after
+# CHECK: x was before
+
+function h
+    defer echo deferred
+    return 3
+    echo unreachable
+end
+h
+echo status $status
+# CHECK: deferred
+# CHECK: status 3
+
+# Deferred commands don't change the status.
+function i
+    defer false
+    true
+end
+i
+echo status $status
+# CHECK: status 0
+
+begin
+    defer echo end of begin
+    echo in begin
+end
+# CHECK: in begin
+# CHECK: end of begin
+
+for i in 1 2
+    begin
+        defer echo cleanup $i
+        break
+    end
+end
+# CHECK: cleanup 1
+
+begin
+    defer echo redirected
+end | string upper
+# CHECK: REDIRECTED
+
+# Other blocks run their deferred commands at the end of the enclosing function or begin block.
+function j
+    if true
+        defer echo from if
+    end
+    echo after if
+end
+j
+# CHECK: after if
+# CHECK: from if
+
+try
+    begin
+        defer echo cleanup before catch
+        false
+    end
+catch
+    echo caught
+end
+# CHECK: cleanup before catch
+# CHECK: caught
+
+$fish -c 'function k; defer echo exiting; exit 4; end; k; echo unreachable'
+echo status $status
+# CHECK: exiting
+# CHECK: status 4
+
+# Deferred commands run when the function is cancelled.
+$fish -c 'function k; defer echo cleanup after cancel; kill -INT $fish_pid; while true; end; end; k; echo unreachable'
+# CHECK: cleanup after cancel
+
+defer echo nope
+# CHECKERR: defer: Not inside of function or begin block
+
+# The search for a block stops at eval, source and command substitutions.
+function l
+    eval 'defer echo from eval'
+    echo 'defer echo from source' | source
+    echo (defer echo from cmdsub)
+    eval 'begin; defer echo from begin in eval; end'
+end
+l
+# CHECKERR: defer: Not inside of function or begin block
+# CHECKERR: defer: Not inside of function or begin block
+# CHECKERR: defer: Not inside of function or begin block
+# CHECK:
+# CHECK: from begin in eval
+
+defer
+# CHECKERR: defer: expected a command
+# CHECKERR: {{.*}}/defer.fish (line {{\d+}}):
+# CHECKERR: defer
+# CHECKERR: ^
+# CHECKERR: (Type 'help defer' for related documentation)
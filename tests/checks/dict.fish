@@ -0,0 +1,111 @@
+# RUN: fish=%fish %fish %s
+
+set --dict colors sky blue grass green
+echo $colors[sky]
+# CHECK: blue
+echo $colors
+# CHECK: blue green
+count $colors
+# CHECK: 2
+set --keys colors
+# CHECK: sky
+# CHECK: grass
+
+# Missing keys expand to nothing.
+echo [$colors[sea]]
+# CHECK: []
+echo "[$colors[sea]]"
+# CHECK: []
+
+# Keys can come from variables, and a list of keys gives several values.
+set -l which grass sky
+echo $colors[$which]
+# CHECK: green blue
+
+# Everything in the brackets is the key.
+set --dict spaced 'a b' 1 a 2
+echo $spaced["a b"] $spaced[a]
+# CHECK: 1 2
+
+# Setting a key replaces its value in place, or adds it at the end.
+set colors[sky] cyan
+set colors[sun] yellow
+set --show colors
+# CHECK: $colors: set in global scope, unexported, a dictionary with 3 elements
+# CHECK: $colors[sky]: |cyan|
+# CHECK: $colors[grass]: |green|
+# CHECK: $colors[sun]: |yellow|
+
+set -q colors[sun] colors[sea]
+echo $status
+# CHECK: 1
+
+set -e colors[grass]
+set --keys colors
+# CHECK: sky
+# CHECK: sun
+
+set -a --dict colors sea blue sky azure
+echo $colors
+# CHECK: azure yellow blue
+
+# A key given twice keeps its first position and its last value.
+set --dict twice a 1 b 2 a 3
+set --keys twice
+echo $twice
+# CHECK: a
+# CHECK: b
+# CHECK: 3 2
+
+set --dict -l empty
+set -q empty
+and count $empty
+# CHECK: 0
+
+# --dict with a key creates a dictionary.
+set --dict newdict[key] value
+echo $newdict[key]
+# CHECK: value
+
+# Setting without --dict makes a list again.
+set newdict a b
+set --show newdict
+# CHECK: $newdict: set in global scope, unexported, with 2 elements
+# CHECK: $newdict[1]: |a|
+# CHECK: $newdict[2]: |b|
+
+set --keys newdict undefined colors
+echo $status
+# CHECK: sky
+# CHECK: sun
+# CHECK: sea
+# CHECK: 2
+
+set --dict odd a 1 b
+# CHECKERR: set: expected a value for key 'b'
+# CHECKERR: {{.*}}dict.fish (line {{\d+}}):
+# CHECKERR: set --dict odd a 1 b
+# CHECKERR: ^
+# CHECKERR: (Type 'help set' for related documentation)
+
+set colors[sky] a b
+# CHECKERR: set: given 1 indexes but 2 values
+
+set --dict --path odd a 1
+# CHECKERR: set: invalid option combination
+# CHECKERR: {{.*}}dict.fish (line {{\d+}}):
+# CHECKERR: set --dict --path odd a 1
+# CHECKERR: ^
+# CHECKERR: (Type 'help set' for related documentation)
+
+# Exported dictionaries export their values.
+set -x --dict exported a 1 b 2
+$fish -c 'echo $exported'
+# CHECK: 1 2
+
+# Universal dictionaries are stored as such.
+$fish -c 'set -U --dict uvdict "first key" 1 second 2'
+$fish -c 'echo $uvdict["first key"]; set --keys uvdict; set -e -U uvdict'
+# CHECK: 1
+# CHECK: first key
+# CHECK: second
@@ -1,4 +1,4 @@
-# RUN: %fish %s
+# RUN: fish=%fish %fish %s
 
 # A for-loop-variable is a local variable in the enclosing scope.
 set -g i global
@@ -53,3 +53,60 @@ end
 # CHECK: foo set
 # CHECK: foo value is bar
 # CHECK: foo value is 3
+
+# Multiple variables step through the list in tuples.
+for key value in (string split = -- a=1 b=2 c=3)
+    echo $key is $value
+end
+# CHECK: a is 1
+# CHECK: b is 2
+# CHECK: c is 3
+
+# Variables without a value in the last tuple are empty.
+for x y z in 1 2 3 4
+    echo (count $x $y $z): $x $y $z
+end
+# CHECK: 3: 1 2 3
+# CHECK: 1: 4
+
+# The index counts iterations, starting at 1.
+for -i idx fruit in apple banana
+    echo $idx $fruit
+end
+# CHECK: 1 apple
+# CHECK: 2 banana
+set -ql idx && echo $idx
+# CHECK: 2
+
+for --index n a b in w x y z
+    echo $n $a $b
+end
+# CHECK: 1 w x
+# CHECK: 2 y z
+
+# The index variable is untouched if there are no values.
+set -l never unset
+for -i never x in
+end
+echo $never
+# CHECK: unset
+
+# Unknown options are a syntax error, so fish -n catches them.
+echo 'for -x y in z; end' | $fish -n
+# CHECKERR: fish: for: -x: unknown option
+# CHECKERR: for -x y in z; end
+# CHECKERR:     ^^
+echo $status
+# CHECK: 127
+
+for -i status x in y
+end
+# CHECKERR: {{.*}}for.fish (line {{\d+}}): for: status: cannot overwrite read-only variable
+# CHECKERR: for -i status x in y
+# CHECKERR:        ^~~~~^
+
+for a b,c in y
+end
+# CHECKERR: {{.*}}for.fish (line {{\d+}}): for: b,c: invalid variable name. See `help language#shell-variable-and-function-names`
+# CHECKERR: for a b,c in y
+# CHECKERR:       ^~^
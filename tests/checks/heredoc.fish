@@ -0,0 +1,63 @@
+#RUN: %fish %s
+
+set -l name world
+
+cat <<EOF
+hello $name
+  "quotes" and 'apostrophes' stay, \$name and \\ are escaped
+$(string upper $name) (not a command substitution) costs $ 5
+EOF
+# CHECK: hello world
+# CHECK:   "quotes" and 'apostrophes' stay, $name and \ are escaped
+# CHECK: WORLD (not a command substitution) costs $ 5
+
+# A quoted delimiter makes the body literal.
+cat <<'EOF'
+hello $name \$name $(echo no)
+EOF
+# CHECK: hello $name \$name $(echo no)
+
+cat <<\EOF
+still $name
+EOF
+# CHECK: still $name
+
+# The body starts on the line after the redirection, even if the line has more commands.
+cat <<A; cat <<"B"
+first $name
+A
+second $name
+B
+# CHECK: first world
+# CHECK: second $name
+
+# Bodies are not indented, and can be used with other fds.
+function f
+    if true
+        begin
+            cat <&3
+        end 3<<EOF
+    indented
+EOF
+    end
+end
+f
+# CHECK:     indented
+
+cat <<EOF | count
+EOF
+# CHECK: 0
+
+string upper <<< "$name and more"
+# CHECK: WORLD AND MORE
+read -l word <<<$name
+echo $word
+# CHECK: world
+cat <<<'' | count
+# CHECK: 1
+
+$fish -c 'cat <<EOF
+unterminated'
+# CHECKERR: fish: Unexpected end of string, heredoc is not terminated
+# CHECKERR: cat <<EOF
+# CHECKERR: {{ *}}^
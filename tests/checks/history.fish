@@ -62,6 +62,62 @@ builtin history --contains save
 #CHECKERR: history: save: subcommand takes no options
 builtin history -t merge
 #CHECKERR: history: merge: subcommand takes no options
+builtin history vacuum xyz
+#CHECKERR: history: vacuum: expected 0 arguments; got 1
+
+builtin history search --success --failed
+#CHECKERR: history: --success --failed: options cannot be used together
+builtin history clear --cwd /
+#CHECKERR: history: clear: subcommand takes no options
+builtin history delete --exact --case-sensitive --since 2000-01-01 foo
+#CHECKERR: history: delete: subcommand does not support filter options
+history save --failed
+#CHECKERR: history: save: subcommand does not support filter options
+builtin history search --regex 'a('
+#CHECKERR: history: Regular expression compile error: missing closing parenthesis
+builtin history search --since yesterday
+#CHECKERR: history: 'yesterday' is not a valid time
+builtin history search --duration-over 5x
+#CHECKERR: history: '5x' is not a valid duration
+
+# Appended items have no exit status, so they never match status filters.
+builtin history append 'echo filtered'
+builtin history search --exact --case-sensitive 'echo filtered'
+#CHECK: echo filtered
+builtin history search --exact --case-sensitive --success 'echo filtered'
+builtin history search --exact --case-sensitive --since 2000-01-01 --until @4102444800 'echo filtered'
+#CHECK: echo filtered
+builtin history search --exact --case-sensitive --until 2000-01-01 'echo filtered'
+builtin history search --regex '^echo f.*d$'
+#CHECK: echo filtered
+builtin history search --regex --case-sensitive '^ECHO f'
+# Deleting with filters leaves commands that don't pass them alone.
+history delete --exact --case-sensitive --failed 'echo filtered'
+builtin history search --exact --case-sensitive 'echo filtered'
+#CHECK: echo filtered
+
+builtin history export --format bash
+#CHECKERR: history: cannot export history as 'bash'
+builtin history export --format yaml
+#CHECKERR: history: 'yaml' is not a valid history format
+builtin history export --prefix
+#CHECKERR: history: export: subcommand only takes --format and filter options
+builtin history import --cwd / /dev/null
+#CHECKERR: history: import: subcommand takes no options
+
+# Imported items keep everything that was recorded about them, and export writes it back out.
+echo '{"cmd":"echo imported","when":1700000000,"status":3,"duration":1520,"cwd":"/tmp"}' | builtin history import
+builtin history search --exact --case-sensitive --failed --cwd /tmp 'echo imported'
+#CHECK: echo imported
+builtin history export --failed --cwd /tmp
+#CHECK: {"cmd":"echo imported","when":1700000000,"status":3,"duration":1520,"cwd":"/tmp"}
+printf '%s\n' '{"cmd":"echo good"}' 'not json' '{"when":1}' | builtin history import
+#CHECKERR: history: -: skipped 2 malformed entries
+builtin history search --exact --case-sensitive 'echo good'
+#CHECK: echo good
+echo ': 1700000000:12;echo from zsh' | builtin history import --format zsh-extended
+builtin history export --duration-over 10s
+#CHECK: {"cmd":"echo from zsh","when":1700000000,"duration":12000}
 
 # Now do a history command that should succeed so we exit with a zero,
 # success, status.
@@ -0,0 +1,90 @@
+# RUN: fish_indent=%fish_indent %fish %s
+
+echo 'set -l x 1
+set --locl y 2
+read -lk name
+read -p
+contains -x a b
+set -l name -- -z
+status --bogus
+type --bogus ls
+commandline --frob' | $fish_indent --lint
+# CHECK: -:2:5: set: --locl: unknown option
+# CHECK: -:3:6: read: -lk: unknown option
+# CHECK: -:4:6: read: -p: option requires an argument
+# CHECK: -:5:10: contains: -x: unknown option
+# CHECK: -:7:8: status: --bogus: unknown option
+# CHECK: -:8:6: type: --bogus: unknown option
+# CHECK: -:9:13: commandline: --frob: unknown option
+echo $status
+# CHECK: 1
+
+echo 'test -n $foo
+[ -z $foo ]
+test -n "$foo"
+test "$a" = -n $b
+kill %self' | $fish_indent --lint
+# CHECK: -:1:9: Unquoted variable after 'test -n' is not one argument if it is empty or a list, quote it
+# CHECK: -:2:6: Unquoted variable after 'test -z' is not one argument if it is empty or a list, quote it
+# CHECK: -:5:6: %self is deprecated, use $fish_pid
+
+echo 'function f
+    if true
+        return 1
+        echo unreachable
+        echo also unreachable
+    end
+    echo reachable
+    for i in 1 2
+        break
+    end
+    exit
+    or echo unreachable
+end' | $fish_indent --lint
+# CHECK: -:4:9: Unreachable code after 'return'
+# CHECK: -:12:5: Unreachable code after 'exit'
+
+echo 'function g -a first
+    if true
+        set -l inner 1
+        echo $inner
+    end
+    echo $inner
+    echo "$later"
+    set -l later 2
+    echo $later $first $argv $global
+    for i in 1 2
+        set -l loop $i
+    end
+    echo $i $loop
+    set -l maybe 1
+    set -g maybe 2
+    begin
+        echo $maybe
+    end
+end
+set -l top 1
+function h
+    echo $top
+end' | $fish_indent --lint
+# CHECK: -:6:10: $inner is not set here, it is only set locally in another block or further down
+# CHECK: -:7:11: $later is not set here, it is only set locally in another block or further down
+# CHECK: -:13:13: $loop is not set here, it is only set locally in another block or further down
+
+# Syntax errors are reported instead.
+echo 'echo (' | $fish_indent --lint
+# CHECK: -:1:{{\d+}}: {{.+}}
+
+# Clean scripts give no output.
+echo 'set -l x 1; echo $x' | $fish_indent --lint
+echo $status
+# CHECK: 0
+
+set -l tmpdir (mktemp -d)
+echo 'test -n $x' >$tmpdir/a.fish
+echo 'echo ok' >$tmpdir/b.fish
+$fish_indent --lint $tmpdir/a.fish $tmpdir/b.fish
+# CHECK: {{.*}}/a.fish:1:9: Unquoted variable after 'test -n' is not one argument if it is empty or a list, quote it
+echo $status
+# CHECK: 1
+rm -r $tmpdir
@@ -247,6 +247,16 @@ end
 #CHECK: {{^    }}# comment
 #CHECK: {{^}}end
 
+echo -n '
+try echo a; catch echo b
+end
+' | fish_features=try-catch $fish_indent
+#CHECK: {{^}}try
+#CHECK: {{^    }}echo a
+#CHECK: {{^}}catch
+#CHECK: {{^    }}echo b
+#CHECK: {{^}}end
+
 echo -n '
 cmd \\
 continuation
@@ -665,6 +675,22 @@ end
 # CHECK: {{^}}    end
 # CHECK: {{^}})
 
+# Heredoc bodies are kept as they are, and so is the quoting of their delimiters.
+echo 'if true
+cat <<EOF |   grep x
+  keep   this $x
+EOF
+cat <<"EOF";    echo "after"
+EOF
+end' | $fish_indent
+# CHECK: {{^}}if true
+# CHECK: {{^}}    cat <<EOF | grep x
+# CHECK: {{^}}  keep   this $x
+# CHECK: {{^}}EOF
+# CHECK: {{^}}    cat <<"EOF"; echo after
+# CHECK: {{^}}EOF
+# CHECK: {{^}}end
+
 set -l tmpdir (mktemp -d)
 echo 'echo "foo" "bar"' > $tmpdir/indent_test.fish
 $fish_indent --write $tmpdir/indent_test.fish
@@ -85,6 +85,21 @@ $fish --no-config --profile $tmp/nostartup.prof -c 'echo foo'
 count < $tmp/nostartup.prof
 # CHECK: 2
 
+# The located format shows where each command is, and the folded format sums them up by function.
+echo 'function slow; sleep 0.01; end
+slow' >$tmp/script.fish
+$fish --no-config --profile $tmp/script.prof --profile-format=located $tmp/script.fish
+string match -rq 'script.fish:2 +> slow$' <$tmp/script.prof
+and string match -rq 'script.fish:1 +-> sleep 0.01$' <$tmp/script.prof
+and echo matched
+# CHECK: matched
+$fish --no-config --profile $tmp/folded.prof --profile-format=folded $tmp/script.fish
+string match -rq '^\S*script.fish;slow \d+$' <$tmp/folded.prof
+and echo matched
+# CHECK: matched
+$fish --no-config --profile-format=json -c true
+# CHECKERR: fish: json: invalid profile format
+
 $fish --no-config -c 'echo notprinted; echo foo | exec true; echo banana'
 # CHECKERR: fish: The 'exec' command can not be used in a pipeline
 # CHECKERR: echo notprinted; echo foo | exec true; echo banana
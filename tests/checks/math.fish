@@ -213,9 +213,9 @@ math 0x 3
 # CHECK: 0
 
 math "42 >= 1337"
-# CHECKERR: math: Error: Logical operations are not supported, use `test` instead
-# CHECKERR: '42 >= 1337'
-# CHECKERR:     ^
+# CHECK: 0
+echo $status
+# CHECK: 1
 
 math "bitand(0xFE, 1)"
 # CHECK: 0
@@ -428,3 +428,123 @@ math -s 6 --scale-mode=ceiling "1 / 3 - 1"
 # CHECK: -0.666666
 math -s 6 --scale-mode=ceiling "2 / 3 - 1"
 # CHECK: -0.333333
+
+# Integer mode is exact beyond 2^53.
+math --int 2 ^ 53 + 1
+# CHECK: 9007199254740993
+math --int '9007199254740993 * 1000'
+# CHECK: 9007199254740993000
+math -i 7 / 2
+# CHECK: 3
+math -i '-7 / 2'
+# CHECK: -3
+math -i '-7 % 3'
+# CHECK: -1
+math -i '1 << 62 | 0xff & 0x0f'
+# CHECK: 4611686018427387919
+math -i '-256 >> 4'
+# CHECK: -16
+math -i --base hex '0x7fff_ffff_ffff_ffff'
+# CHECK: 0x7fffffffffffffff
+math -i bitxor 0xff, 0x0f
+# CHECK: 240
+math -i fac 20
+# CHECK: 2432902008176640000
+math -i ncr 62, 31
+# CHECK: 465428353255261088
+math -i 'max(1, 2 ^ 62, 3)'
+# CHECK: 4611686018427387904
+math -i 9223372036854775807 + 1
+# CHECKERR: math: Error: Integer overflow
+# CHECKERR: '9223372036854775807 + 1'
+# CHECKERR:                      ^
+math -i '1 << 63'
+# CHECKERR: math: Error: Integer overflow
+# CHECKERR: '1 << 63'
+# CHECKERR:    ^^
+math -i fac 21
+# CHECKERR: math: Error: Integer overflow
+# CHECKERR: 'fac 21'
+# CHECKERR:  ^~^
+math -i 9223372036854775808
+# CHECKERR: math: Error: Number is too large
+# CHECKERR: '9223372036854775808'
+# CHECKERR:  ^~~~~~~~~~~~~~~~~~^
+math -i 1.5 + 1
+# CHECKERR: math: Error: Not an integer
+# CHECKERR: '1.5 + 1'
+# CHECKERR:  ^~^
+math -i 2 ^ -1
+# CHECKERR: math: Error: Not an integer
+# CHECKERR: '2 ^ -1'
+# CHECKERR:    ^
+math -i 5 % 0
+# CHECKERR: math: Error: Division by zero
+# CHECKERR: '5 % 0'
+# CHECKERR:    ^
+math -i 'sin(1)'
+# CHECKERR: math: Error: Function is not available in integer mode
+# CHECKERR: 'sin(1)'
+# CHECKERR:  ^~^
+math -i '1 && 1'
+# CHECK: 1
+math '1 & 1'
+# CHECKERR: math: Error: Bitwise operators require --int
+# CHECKERR: '1 & 1'
+# CHECKERR:    ^
+math '1 >> 1'
+# CHECKERR: math: Error: Bitwise operators require --int
+# CHECKERR: '1 >> 1'
+# CHECKERR:    ^^
+math -i -s 2 1
+# CHECKERR: math: invalid option combination, --scale and --scale-mode are not valid with --int
+
+# Comparisons, logical operators and the conditional operator.
+math '0.1 + 0.2 == 0.3'
+# CHECK: 0
+math '1 + 2 * 3 == 7 && 2 > 1'
+# CHECK: 1
+math '1 < 2 || 1 / 0'
+# CHECK: 1
+math '0 && 1 / 0'
+# CHECK: 0
+math '!0 + !5'
+# CHECK: 1
+math '1 && 1 / 0'
+# CHECKERR: math: Error: Division by zero
+# CHECKERR: '1 && 1 / 0'
+# CHECKERR:         ^
+set -l x 0
+math "$x == 0 ? 0 : 1 / $x"
+# CHECK: 0
+math '2 > 1 ? 10 : 0 ? 20 : 30'
+# CHECK: 10
+math '0 ? 10 : 0 ? 20 : 30'
+# CHECK: 30
+math '3 > 2 ? 1'
+# CHECKERR: math: Error: Missing ':' after '?'
+# CHECKERR: '3 > 2 ? 1'
+# CHECKERR:           ^
+math '1 = 1'
+# CHECKERR: math: Error: Use '==' to compare numbers
+# CHECKERR: '1 = 1'
+# CHECKERR:    ^
+math -i '1 | 2 == 3'
+# CHECK: 1
+
+# Only comparisons and logical operators set the status.
+math '2 < 1'; echo $status
+# CHECK: 0
+# CHECK: 1
+math '2 - 2'; echo $status
+# CHECK: 0
+# CHECK: 0
+math '2 < 1 ? 1 : 0'; echo $status
+# CHECK: 0
+# CHECK: 0
+if math -q '1.5 * 2 >= 3'
+    echo big
+end
+# CHECK: big
+math --quiet '1 > 2' || echo small
+# CHECK: small
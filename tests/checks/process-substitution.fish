@@ -0,0 +1,68 @@
+#RUN: %fish --features process-substitution %s
+
+cat <(echo hello)
+# CHECK: hello
+
+string match -rq '^/dev/fd/\d+$' <(true) >(true)
+and echo is a path
+# CHECK: is a path
+
+diff <(seq 3) <(seq 3)
+and echo same
+# CHECK: same
+
+# Output of builtins and functions is collected before the job starts.
+function produce
+    printf '%s\n' a b
+end
+diff <(produce) <(printf '%s\n' a c)
+# CHECK: 2c2
+# CHECK: < b
+# CHECK: ---
+# CHECK: > c
+
+# Large outputs don't block.
+count < <(seq 100000)
+# CHECK: 100000
+count < <(seq 100000 | string match '*')
+# CHECK: 100000
+
+while read -l line
+    echo line $line
+end < <(seq 2)
+# CHECK: line 1
+# CHECK: line 2
+
+# A >(cmd) that runs within fish gets its input once the job is done.
+echo hello | tee >(string upper) >/dev/null
+# CHECK: HELLO
+
+# Otherwise it runs in the background.
+set -l tmpdir (mktemp -d)
+echo hello | tee >(sed s/hello/goodbye/ >$tmpdir/out) >/dev/null
+for i in (seq 50)
+    test -s $tmpdir/out
+    and break
+    sleep 0.1
+end
+cat $tmpdir/out
+# CHECK: goodbye
+rm -r $tmpdir
+
+# Commands run in the background are not jobs.
+true <(sleep 0.5)
+jobs -q
+or echo no jobs
+# CHECK: no jobs
+set -q last_pid
+or echo no last_pid
+# CHECK: no last_pid
+
+# The status of the substituted command is not observable.
+set -l file <(false)
+echo $status
+# CHECK: 0
+
+# Quoted or escaped, these are not special.
+echo '<(echo hi)' \<(echo hi)
+# CHECK: <(echo hi) <hi
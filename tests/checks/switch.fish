@@ -118,4 +118,70 @@ begin
     # CHECKERR:        ^~~~~~~~~~~~~^
 end
 
+# Regex cases match anywhere in the value and set their named capture groups.
+for version in v3.7 v12.0-rc1 release
+    switch $version
+        case --regex '^v(?<major>\d+)\.(?<minor>\d+)$'
+            echo stable $major $minor
+        case --regex 'rc(?<rc>\d+)' 'beta(?<rc>\d+)'
+            echo candidate $rc
+        case --regex '^(?<name>[a-z]+)$'
+            echo name $name
+    end
+end
+#CHECK: stable 3 7
+#CHECK: candidate 1
+#CHECK: name release
+
+# Groups that did not participate in the match are empty.
+switch ab
+    case --regex '(?<first>a)(?<second>x)?'
+        echo (count $first $second) $first
+end
+#CHECK: 1 a
+
+# $case_match has the whole match and the numbered groups, with empty unmatched groups.
+for value in 1.2 3
+    switch $value
+        case --regex '(\d+)(?:\.(\d+))?'
+            echo (count $case_match) (string join , -- $case_match)
+    end
+end
+#CHECK: 3 1.2,1,2
+#CHECK: 3 3,3,
+
+# Glob and regex cases can be mixed, and a quoted --regex is literal.
+for value in --regex '[' 42
+    switch $value
+        case '--regex'
+            echo literal
+        case --regex '^\d+$'
+            echo number
+        case '*'
+            echo other
+    end
+end
+#CHECK: literal
+#CHECK: other
+#CHECK: number
+
+switch abc
+    case --regex 'a('
+        echo bad regex
+    case --regex 'b' '('
+        echo good regex first
+end
+#CHECKERR: {{.*}}checks/switch.fish (line {{\d+}}): case: Regular expression compile error: missing closing parenthesis
+#CHECKERR: case --regex 'a('
+#CHECKERR: {{ *}}^{{~*}}^
+#CHECK: good regex first
+
+switch foo
+    case --regex '(?<status>o)'
+        echo read-only
+end
+#CHECKERR: {{.*}}checks/switch.fish (line {{\d+}}): case: status: cannot overwrite read-only variable
+#CHECKERR: case --regex '(?<status>o)'
+#CHECKERR: {{ *}}^{{~*}}^
+
 exit 0
@@ -0,0 +1,95 @@
+#RUN: %fish %s
+
+timeout 5 echo fast 'with spaces' '$notexpanded'
+echo $status
+# CHECK: fast with spaces $notexpanded
+# CHECK: 0
+
+timeout 5 false
+echo $status
+# CHECK: 1
+
+timeout 0.2 sleep 5
+echo $status
+# CHECK: 124
+
+# Functions are stopped as well, whether they run fish code or wait for a process.
+function spin
+    while true
+    end
+end
+timeout 200ms spin
+echo $status
+# CHECK: 124
+
+function slow
+    echo start
+    sleep 5
+    echo not reached
+end
+timeout 0.2 slow
+echo $status
+# CHECK: start
+# CHECK: 124
+
+# Code after the timeout runs as usual.
+begin
+    timeout 0.2 slow
+    echo after
+end
+# CHECK: start
+# CHECK: after
+
+timeout --preserve-status 0.2 sleep 5
+echo $status
+# CHECK: 143
+
+timeout --preserve-status -s HUP 0.2 sleep 5
+echo $status
+# CHECK: 129
+
+# Processes which ignore the signal only get SIGKILL with --kill-after.
+timeout 0.2 sh -c 'trap "" TERM; sleep 0.5; echo survived'
+echo $status
+# CHECK: survived
+# CHECK: 124
+
+timeout --preserve-status -k 0.2 0.2 sh -c 'trap "" TERM; exec sleep 5'
+echo $status
+# CHECK: 137
+
+timeout -v -k 0.2 0.2 sh -c 'trap "" TERM; exec sleep 5'
+echo $status
+# CHECKERR: timeout: sending signal TERM to command 'sh'
+# CHECKERR: timeout: sending signal KILL to command 'sh'
+# CHECK: 137
+
+timeout --foreground --verbose 0.2 sleep 5
+echo $status
+# CHECKERR: timeout: sending signal TERM to command 'sleep'
+# CHECK: 124
+
+# An outer timeout also stops an inner one.
+timeout 0.2 timeout 5 sleep 5
+echo $status
+# CHECK: 124
+
+# A duration of 0 disables the timeout.
+timeout 0 echo no limit
+# CHECK: no limit
+
+echo hello | timeout 5 string upper | string join ,
+# CHECK: HELLO
+
+timeout 1x echo
+# CHECKERR: timeout: '1x' is not a valid duration
+
+timeout -s NOSIG 1 echo
+# CHECKERR: timeout: Unknown signal 'NOSIG'
+
+timeout 1
+# CHECKERR: timeout: expected a command
+# CHECKERR: {{.*}}/timeout.fish (line {{\d+}}):
+# CHECKERR: timeout 1
+# CHECKERR: ^
+# CHECKERR: (Type 'help timeout' for related documentation)
@@ -67,3 +67,16 @@ isolated-tmux send-keys f Tab
 tmux-sleep
 isolated-tmux capture-pane -p
 # CHECK: prompt {{\d+}}> : some-file
+
+# Commands run in the current directory are preferred over more recent ones run elsewhere.
+mkdir -p ranked-a ranked-b
+isolated-tmux send-keys C-u 'cd ranked-a' Enter ': ranked one' Enter
+isolated-tmux send-keys 'cd ../ranked-b' Enter ': ranked two' Enter
+isolated-tmux send-keys 'cd ../ranked-a' Enter C-l ': ranked '
+tmux-sleep
+isolated-tmux capture-pane -p
+# CHECK: prompt {{\d+}}> : ranked one
+isolated-tmux send-keys C-u 'cd ../ranked-b' Enter C-l ': ranked '
+tmux-sleep
+isolated-tmux capture-pane -p
+# CHECK: prompt {{\d+}}> : ranked two
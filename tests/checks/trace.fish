@@ -70,3 +70,25 @@ set -e fish_trace
 
 echo untraced
 # CHECK: untraced
+
+function traced
+    echo in function
+end
+
+set fish_trace location
+traced
+set -e fish_trace
+
+# CHECK: in function
+# CHECKERR: {{.*}}trace.fish:79: > traced
+# CHECKERR: {{.*}}trace.fish:75: traced: -> echo in function
+# CHECKERR: {{.*}}trace.fish:80: > set -e fish_trace
+
+# Files in $HOME are shown relative to ~.
+set -l tmpdir (mktemp -d)
+echo 'echo home' >$tmpdir/home.fish
+HOME=$tmpdir $fish --no-config -c 'set fish_trace location; source ~/home.fish'
+# CHECK: home
+# CHECKERR: {{.*}}:1: > source {{.*}}home.fish
+# CHECKERR: ~/home.fish:1: -> echo home
+rm -r $tmpdir
@@ -0,0 +1,168 @@
+#RUN: %fish --features try-catch %s
+
+try
+    echo first
+    false
+    echo unreachable
+catch
+    echo caught $status
+end
+# CHECK: first
+# CHECK: caught 1
+
+try
+    sh -c 'exit 5' | cat
+catch
+    echo $pipestatus: (status failed-commandline)
+end
+# CHECK: 5 0: sh -c 'exit 5' | cat
+
+# Tested statuses are not failures.
+try
+    if false
+    else if test -z x
+    end
+    while false
+    end
+    false; or echo or
+    false && echo unreachable
+    not true
+    ! true
+    true; and false; or echo and-or
+catch
+    echo unreachable
+end
+# CHECK: or
+# CHECK: and-or
+
+# The status of the last job in a chain is not tested.
+try
+    true && false
+catch
+    echo chain: (status failed-commandline)
+end
+# CHECK: chain: false
+
+# Without a catch clause, the block ends with the failing status.
+try
+    echo before
+    sh -c 'exit 3'
+    echo unreachable
+end
+echo status $status
+# CHECK: before
+# CHECK: status 3
+
+function fails-inside
+    false
+    echo still running
+end
+
+try
+    fails-inside
+    echo function succeeded
+catch
+    echo unreachable
+end
+# CHECK: still running
+# CHECK: function succeeded
+
+function fails-at-end
+    false
+end
+
+try
+    fails-at-end
+catch
+    echo function failed: (status failed-commandline)
+end
+# CHECK: function failed: fails-at-end
+
+# Command substitutions are not part of the block.
+try
+    echo (false; echo subst)
+catch
+    echo unreachable
+end
+# CHECK: subst
+
+# Failures in nested blocks are reported as such.
+try
+    begin
+        if true
+            false
+        end
+    end
+catch
+    echo nested: (status failed-commandline)
+end
+# CHECK: nested: false
+
+# Failures are handled by the innermost try block.
+try
+    try
+        false
+    catch
+        echo inner
+        try
+            test 1 = 2
+        catch
+            echo nested (status failed-commandline) $status
+        end
+        status failed-commandline
+    end
+    echo outer continues
+    command-that-does-not-exist
+catch
+    echo outer $status
+end
+# CHECK: inner
+# CHECK: nested test 1 = 2 1
+# CHECK: false
+# CHECK: outer continues
+# CHECKERR: fish: Unknown command: command-that-does-not-exist
+# CHECKERR: {{.*}}/try.fish (line {{\d+}}):
+# CHECKERR: command-that-does-not-exist
+# CHECKERR: ^~~~~~~~~~~~~~~~~~~~~~~~~~^
+# CHECK: outer 127
+
+# Loops are left as well.
+try
+    for i in 1 2 3
+        echo $i
+        test $i -lt 2
+    end
+catch
+    echo stopped: (status failed-commandline)
+end
+# CHECK: 1
+# CHECK: 2
+# CHECK: stopped: test $i -lt 2
+
+# Failures in a catch clause are not handled by the same block.
+try
+    false
+catch
+    false
+    echo in catch
+end
+echo $status
+# CHECK: in catch
+# CHECK: 0
+
+status failed-commandline
+echo $status
+# CHECK: 1
+
+try
+    false
+catch
+    status stack-trace
+end
+# CHECK: in catch block for failed command 'false'
+# CHECK: {{\t}}called on line {{\d+}} of file {{.*}}/try.fish
+
+$fish --features try-catch -c catch
+# CHECKERR: fish: 'catch' builtin not inside of try block
+# CHECKERR: catch
+# CHECKERR: ^~~~^
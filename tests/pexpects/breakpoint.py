@@ -0,0 +1,75 @@
+#!/usr/bin/env python3
+from pexpect_helper import SpawnedProc
+import os
+import tempfile
+
+with tempfile.TemporaryDirectory() as tmpdir:
+    script = os.path.join(tmpdir, "debugme.fish")
+    with open(script, "w") as f:
+        f.write(
+            "function f\n"
+            "    set -l x 1\n"
+            "    g $x\n"
+            "    echo f done\n"
+            "end\n"
+            "function g\n"
+            "    echo in g $argv\n"
+            "end\n"
+        )
+
+    sp = SpawnedProc()
+    sendline, expect_prompt, expect_re, expect_str = (
+        sp.sendline,
+        sp.expect_prompt,
+        sp.expect_re,
+        sp.expect_str,
+    )
+    expect_prompt()
+
+    sendline("function fish_breakpoint_prompt; echo -n 'debug> '; end")
+    expect_prompt()
+    sendline("source " + script)
+    expect_prompt()
+
+    # Stop at a line of a function, without changing it.
+    sendline("breakpoint add f:2")
+    expect_prompt()
+    sendline("f")
+    expect_str("Breakpoint 1 in function 'f'")
+    expect_re(r"debugme\.fish \(line 2\): set -l x 1")
+    expect_str("debug> ")
+
+    sendline("breakpoint next")
+    expect_str("Stopped in function 'f'")
+    expect_re(r"\(line 3\): g \$x")
+    expect_str("debug> ")
+    sendline("breakpoint locals")
+    expect_re(r"argv\r\nx 1\r\n")
+    expect_str("debug> ")
+
+    # Step into g, and look at the variables of its caller.
+    sendline("breakpoint step")
+    expect_str("Stopped in function 'g'")
+    expect_re(r"\(line 7\): echo in g \$argv")
+    expect_str("debug> ")
+    sendline("breakpoint locals 1")
+    expect_re(r"argv\r\nx 1\r\n")
+    expect_str("debug> ")
+
+    sendline("breakpoint finish")
+    expect_str("in g 1")
+    expect_str("Stopped in function 'f'")
+    expect_re(r"\(line 4\): echo f done")
+    expect_str("debug> ")
+    sendline("breakpoint continue")
+    expect_prompt("f done")
+
+    # Conditional breakpoints only stop if the condition succeeds.
+    sendline("breakpoint erase --all; breakpoint add -c 'test $argv[1] = b' g:2")
+    expect_prompt()
+    sendline("g a; g b")
+    expect_str("in g a")
+    expect_str("Breakpoint 2 in function 'g'")
+    expect_str("debug> ")
+    sendline("breakpoint continue")
+    expect_prompt("in g b")
@@ -90,3 +90,50 @@ send("source foo/b/baz.fish")
 send(control("b") * 9 + "\t")
 expect_str("source foo/bar/baz.fish")
 send(control("u"))
+
+# Typing cancels completions that run a command substitution.
+sendline(
+    "function slowcomp; echo args: $argv; end; complete -c slowcomp -xa '(sleep 0.5; echo completed)'"
+)
+expect_prompt()
+send("slowcomp \t")
+send("x")
+sendline("")
+expect_str("args: x")
+expect_prompt()
+# Without typing, they are applied once they are done.
+send("slowcomp \t")
+expect_str("slowcomp completed")
+sendline("")
+expect_str("args: completed")
+expect_prompt()
+
+# fish_complete_preview is shown for the selected completion, once the selection settles.
+sendline(
+    "function fish_complete_preview; echo preview:$argv[1]:$argv[2]; end; complete -c previewcmd -xa '(printf \"%s\\t%s\\n\" ab1 first ab2 second)'"
+)
+expect_prompt()
+send("previewcmd \t\t")
+expect_str("preview:ab1:first")
+send("\t")
+expect_str("preview:ab2:second")
+send(control("u"))
+sendline("")
+expect_prompt()
+# The candidate includes the part of the token that the completions are appended to.
+send("previewcmd a\t\t")
+expect_str("preview:ab1:first")
+send(control("u"))
+
+# Typing stops a slow preview.
+sendline("function fish_complete_preview; sleep 10; echo slow preview; end")
+expect_prompt()
+send("previewcmd \t\t")
+expect_str("ab2")
+sleep(0.5)
+send(control("u"))
+sendline("string upper typed")
+expect_str("TYPED", timeout=5)
+expect_prompt()
+sendline("functions -e fish_complete_preview")
+expect_prompt()